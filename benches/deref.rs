@@ -0,0 +1,56 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use pointerguard::{EncryptedPtr, EncryptedVec, Speck};
+
+fn deref(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deref");
+
+    let boxed = Box::new(100u64);
+    group.bench_function("box", |b| b.iter(|| **black_box(&boxed)));
+
+    let builtin: EncryptedPtr<u64> = Box::new(100u64).into();
+    group.bench_function("builtin", |b| b.iter(|| *black_box(&builtin).access()));
+
+    let speck = EncryptedPtr::with_method(Box::into_raw(Box::new(100u64)), Speck::new());
+    group.bench_function("speck", |b| b.iter(|| *black_box(&speck).access()));
+
+    // many pointers, so the random method choice doesn't flatter the branch predictor.
+    let many: Vec<EncryptedPtr<u64>> = (0..1024).map(|i| Box::new(i).into()).collect();
+    group.bench_function("builtin_1024", |b| {
+        b.iter(|| black_box(&many).iter().map(|p| *p.access()).sum::<u64>())
+    });
+
+    group.finish();
+}
+
+fn iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iter");
+
+    let plain: Vec<u64> = (0..1024).collect();
+    group.bench_function("vec", |b| b.iter(|| black_box(&plain).iter().sum::<u64>()));
+
+    let encrypted: EncryptedVec<u64> = plain.clone().into();
+    group.bench_function("encrypted_vec", |b| {
+        b.iter(|| black_box(&encrypted).iter().sum::<u64>())
+    });
+    group.bench_function("encrypted_vec_chunks_64", |b| {
+        b.iter(|| {
+            black_box(&encrypted)
+                .chunks(64)
+                .map(|chunk| chunk.iter().sum::<u64>())
+                .sum::<u64>()
+        })
+    });
+
+    group.finish();
+}
+
+fn construct(c: &mut Criterion) {
+    c.bench_function("new", |b| {
+        b.iter(|| EncryptedPtr::<u64>::from(Box::new(black_box(100u64))))
+    });
+}
+
+criterion_group!(benches, deref, iterate, construct);
+criterion_main!(benches);
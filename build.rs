@@ -0,0 +1,31 @@
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=POINTERGUARD_LAYOUT_SEED");
+
+    // allow pinning the seed for reproducible builds, otherwise pick a fresh one.
+    let seed = match env::var("POINTERGUARD_LAYOUT_SEED") {
+        Ok(seed) => seed.parse().expect("POINTERGUARD_LAYOUT_SEED must be a u64"),
+        Err(_) => RandomState::new().build_hasher().finish(),
+    };
+
+    // passthrough only ever applies to builds with debug assertions, so a
+    // release build can't ship unencrypted pointers by accident.
+    println!("cargo:rustc-check-cfg=cfg(passthrough)");
+    if env::var_os("CARGO_FEATURE_PASSTHROUGH").is_some()
+        && env::var_os("CARGO_CFG_DEBUG_ASSERTIONS").is_some()
+    {
+        println!("cargo:rustc-cfg=passthrough");
+    }
+
+    // set by hand for the loom model tests, see src/atomic.rs.
+    println!("cargo:rustc-check-cfg=cfg(loom)");
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("layout_seed.rs");
+    fs::write(out, format!("const LAYOUT_SEED: u64 = {seed:#x};\n")).unwrap();
+}
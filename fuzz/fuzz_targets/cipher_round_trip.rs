@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pointerguard::fuzz;
+
+fuzz_target!(|input: (u8, u64, u64, u8)| {
+    let (cipher, data, key, method) = input;
+
+    assert!(fuzz::cipher_round_trip(cipher as usize, data, key));
+    assert!(fuzz::sealed_round_trip(data, method));
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pointerguard::fuzz;
+
+// arbitrary key words must be rejected or decoded, never crash.
+fuzz_target!(|input: (u64, u64)| {
+    let (ciphertext, word) = input;
+    let _ = fuzz::unseal(ciphertext, word);
+});
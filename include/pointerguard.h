@@ -0,0 +1,54 @@
+/*
+ * C interface to pointerguard, enabled with the `ffi` cargo feature.
+ *
+ * Build a linkable library with:
+ *   cargo rustc --release --features ffi --crate-type staticlib
+ */
+#ifndef POINTERGUARD_H
+#define POINTERGUARD_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define PG_OK 0
+#define PG_ERR_NULL -1
+#define PG_ERR_METHOD -2
+
+/* opaque handle to an encrypted pointer. */
+typedef struct PgPtr PgPtr;
+
+/* fixed-layout encrypted pointer, safe to embed in structs and copy by value. */
+typedef struct PgHandle {
+  uint64_t ciphertext;
+  uint64_t key;
+  uint32_t method;
+  uint32_t version;
+} PgHandle;
+
+/* encrypt/decrypt a value with built-in method `method` (0, 1 or 2). */
+int32_t pg_encrypt(uint64_t data, uint64_t key, uint32_t method, uint64_t *out);
+int32_t pg_decrypt(uint64_t data, uint64_t key, uint32_t method, uint64_t *out);
+
+/* wrap `ptr`; `free_fn` (may be NULL) is called on it by pg_ptr_free. */
+PgPtr *pg_ptr_new(void *ptr, void (*free_fn)(void *));
+
+/* decrypt the wrapped pointer, NULL once poisoned or on a MAC mismatch. */
+void *pg_ptr_deref(const PgPtr *handle);
+
+/* free the handle, and the pointee if a free function was given and it decrypts. */
+void pg_ptr_free(PgPtr *handle);
+
+/* create a (non-owning) handle for `ptr`. */
+PgHandle pg_handle_new(void *ptr);
+
+/* decrypt the pointer held by `handle`, NULL if malformed, forgotten or poisoned. */
+void *pg_handle_deref(PgHandle handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
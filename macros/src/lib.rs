@@ -0,0 +1,656 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, Span as Span2, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use syn::{
+    parse_macro_input, parse_quote, Data, DataStruct, DeriveInput, Error, Expr, ExprLit, ExprMacro,
+    Fields, Index, Lit, LitStr, Member,
+};
+
+/// One invertible step of a generated cipher.
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    /// `data ^= key.rotate_left(r) ^ c`.
+    XorKey(u32, u64),
+    /// `data = data.wrapping_add(key.rotate_left(r) ^ c)`.
+    AddKey(u32, u64),
+    /// `data = data.rotate_left(n)`.
+    Rotate(u32),
+    /// `data = data.wrapping_mul(m)` for odd `m`.
+    Multiply(u64),
+    /// `data ^= data >> s` for `s >= 32`, which is its own inverse.
+    XorShift(u32),
+}
+
+impl Op {
+    fn random(rng: &mut impl Rng) -> Self {
+        match rng.random_range(0..5) {
+            0 => Op::XorKey(rng.random_range(0..64), rng.random()),
+            1 => Op::AddKey(rng.random_range(0..64), rng.random()),
+            2 => Op::Rotate(rng.random_range(1..64)),
+            3 => Op::Multiply(rng.random::<u64>() | 1),
+            _ => Op::XorShift(rng.random_range(32..64)),
+        }
+    }
+
+    fn encrypt(self) -> TokenStream2 {
+        match self {
+            Op::XorKey(r, c) => quote! { data ^= key.rotate_left(#r) ^ #c; },
+            Op::AddKey(r, c) => quote! { data = data.wrapping_add(key.rotate_left(#r) ^ #c); },
+            Op::Rotate(n) => quote! { data = data.rotate_left(#n); },
+            Op::Multiply(m) => quote! { data = data.wrapping_mul(#m); },
+            Op::XorShift(s) => quote! { data ^= data >> #s; },
+        }
+    }
+
+    fn decrypt(self) -> TokenStream2 {
+        match self {
+            Op::XorKey(r, c) => quote! { data ^= key.rotate_left(#r) ^ #c; },
+            Op::AddKey(r, c) => quote! { data = data.wrapping_sub(key.rotate_left(#r) ^ #c); },
+            Op::Rotate(n) => quote! { data = data.rotate_right(#n); },
+            Op::Multiply(m) => {
+                let inverse = mul_inverse(m);
+                quote! { data = data.wrapping_mul(#inverse); }
+            }
+            Op::XorShift(s) => quote! { data ^= data >> #s; },
+        }
+    }
+}
+
+/// Multiplicative inverse of an odd `m` modulo 2^64, by Newton's iteration.
+fn mul_inverse(m: u64) -> u64 {
+    // each step doubles the number of correct low bits, starting from 3.
+    let mut inverse = m;
+    for _ in 0..5 {
+        inverse = inverse.wrapping_mul(2u64.wrapping_sub(m.wrapping_mul(inverse)));
+    }
+    inverse
+}
+
+/// Randomness for one call site.
+///
+/// `POINTERGUARD_LAYOUT_SEED` makes the generated ciphers reproducible, the
+/// same as it does for the paranoid layout.
+fn callsite_rng() -> StdRng {
+    let Ok(seed) = std::env::var("POINTERGUARD_LAYOUT_SEED") else {
+        return StdRng::from_os_rng();
+    };
+
+    let span = proc_macro::Span::call_site();
+    let mut hasher = DefaultHasher::new();
+    (seed, span.file(), span.line(), span.column()).hash(&mut hasher);
+    StdRng::seed_from_u64(hasher.finish())
+}
+
+/// Box `expr` into an `EncryptedPtr` using a cipher generated for this call site.
+///
+/// Every invocation gets its own randomly-parameterized sequence of
+/// key-dependent XORs, adds, rotates and multiplies, inlined at the call
+/// site, so no two protected pointers share the same decrypt code.
+///
+/// ```ignore
+/// let health = pointerguard::protect!(100u32);
+/// assert_eq!(*health, 100);
+/// ```
+#[proc_macro]
+pub fn protect(input: TokenStream) -> TokenStream {
+    let expr = parse_macro_input!(input as Expr);
+    let mut rng = callsite_rng();
+
+    // always start from a key XOR so the plaintext never passes through unkeyed.
+    let mut ops = vec![Op::XorKey(rng.random_range(0..64), rng.random())];
+    for _ in 0..rng.random_range(4..9) {
+        ops.push(Op::random(&mut rng));
+    }
+    ops.push(Op::AddKey(rng.random_range(0..64), rng.random()));
+
+    let encrypt = ops.iter().map(|op| op.encrypt());
+    let decrypt = ops.iter().rev().map(|op| op.decrypt());
+
+    quote! {
+        {
+            struct CallsiteCipher;
+
+            impl ::pointerguard::Encrypt for CallsiteCipher {
+                #[inline(always)]
+                fn encrypt(&self, mut data: u64, key: u64) -> u64 {
+                    #(#encrypt)*
+                    data
+                }
+
+                #[inline(always)]
+                fn decrypt(&self, mut data: u64, key: u64) -> u64 {
+                    #(#decrypt)*
+                    data
+                }
+            }
+
+            ::pointerguard::EncryptedPtr::with_method(
+                ::std::boxed::Box::into_raw(::std::boxed::Box::new(#expr)),
+                CallsiteCipher,
+            )
+        }
+    }
+    .into()
+}
+
+/// Word `index` of the keystream embedded literals are encrypted under.
+///
+/// Must match `keystream` in pointerguard's `src/literal.rs`.
+fn keystream(key: u64, index: usize) -> u64 {
+    let mut x = key.wrapping_add((index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// `bytes` encrypted under `key`, as a byte string literal.
+fn encrypt_literal(bytes: &[u8], key: u64) -> Literal {
+    let ciphertext: Vec<u8> = bytes
+        .chunks(8)
+        .enumerate()
+        .flat_map(|(index, chunk)| {
+            let stream = keystream(key, index).to_le_bytes();
+            chunk.iter().zip(stream).map(|(byte, stream)| byte ^ stream)
+        })
+        .collect();
+    Literal::byte_string(&ciphertext)
+}
+
+/// Embed a string literal encrypted in the binary, as a `&'static EncryptedString`.
+///
+/// The literal is encrypted at compile time under a random key, fresh for
+/// every build unless `POINTERGUARD_LAYOUT_SEED` pins it, and decrypted into
+/// protected storage on first use, so server URLs, detection messages and
+/// key names don't show up in `strings` output.
+///
+/// ```ignore
+/// let url: &str = pointerguard::encrypted_str!("https://auth.example.com");
+/// ```
+#[proc_macro]
+pub fn encrypted_str(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    let key: u64 = callsite_rng().random();
+    let ciphertext = encrypt_literal(literal.value().as_bytes(), key);
+
+    quote! {
+        {
+            static LITERAL: ::pointerguard::EncryptedLiteral =
+                ::pointerguard::EncryptedLiteral::new(#ciphertext, #key);
+            LITERAL.get()
+        }
+    }
+    .into()
+}
+
+/// The bytes `encrypted_bytes!` was given: a byte string, an array of byte
+/// literals, or `include_bytes!("path")` relative to the crate root.
+fn literal_bytes(expr: &Expr) -> syn::Result<Vec<u8>> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::ByteStr(bytes),
+            ..
+        }) => Ok(bytes.value()),
+        Expr::Array(array) => array
+            .elems
+            .iter()
+            .map(|elem| match elem {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(int), ..
+                }) => int.base10_parse::<u8>(),
+                _ => Err(Error::new_spanned(elem, "expected a byte literal")),
+            })
+            .collect(),
+        Expr::Macro(ExprMacro { mac, .. }) if mac.path.is_ident("include_bytes") => {
+            let path = mac.parse_body::<LitStr>()?;
+            let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+            std::fs::read(std::path::Path::new(&root).join(path.value())).map_err(|e| {
+                Error::new_spanned(&path, format!("couldn't read {}: {e}", path.value()))
+            })
+        }
+        _ => Err(Error::new_spanned(
+            expr,
+            "expected a byte string, an array of byte literals or include_bytes!(\"path\")",
+        )),
+    }
+}
+
+/// Embed a byte array encrypted in the binary, as an `EncryptedBlob`.
+///
+/// Takes a byte string, an array of byte literals, or
+/// `include_bytes!("path")` with the path relative to the crate root (the
+/// file's plaintext never reaches the binary, but cargo won't notice it
+/// changing either, so touch a source file to re-embed it). The bytes are
+/// encrypted at compile time under a per-build random key, like
+/// [`encrypted_str!`], and are only readable through `EncryptedBlob::access`.
+///
+/// ```ignore
+/// static SHADER: pointerguard::EncryptedBlob =
+///     pointerguard::encrypted_bytes!(include_bytes!("shaders/esp.spv"));
+/// ```
+#[proc_macro]
+pub fn encrypted_bytes(input: TokenStream) -> TokenStream {
+    let expr = parse_macro_input!(input as Expr);
+    let bytes = match literal_bytes(&expr) {
+        Ok(bytes) => bytes,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let key: u64 = callsite_rng().random();
+    let ciphertext = encrypt_literal(&bytes, key);
+
+    quote! {
+        ::pointerguard::EncryptedBlob::new(#ciphertext, #key)
+    }
+    .into()
+}
+
+/// A `u64` key constant fixed at compile time, random and fresh for every build.
+///
+/// `const_key!("VAR")` derives it from the build secret in environment
+/// variable `VAR` instead, so builds sharing the secret share their keys.
+/// Every call site gets its own key either way. Cargo doesn't see the macro
+/// reading `VAR`, so rebuild from clean after changing it. Meant for
+/// `Config::key_whitening` and `KeySource::Custom` generators.
+///
+/// ```ignore
+/// const WHITENING: u64 = pointerguard::const_key!("GAME_BUILD_SECRET");
+/// ```
+#[proc_macro]
+pub fn const_key(input: TokenStream) -> TokenStream {
+    if input.is_empty() {
+        let key: u64 = callsite_rng().random();
+        return quote!(#key).into();
+    }
+
+    let var = parse_macro_input!(input as LitStr);
+    let Ok(secret) = std::env::var(var.value()) else {
+        return Error::new_spanned(
+            &var,
+            format!("environment variable {} isn't set", var.value()),
+        )
+        .into_compile_error()
+        .into();
+    };
+
+    let span = proc_macro::Span::call_site();
+    let mut hasher = DefaultHasher::new();
+    (secret, span.file(), span.line(), span.column()).hash(&mut hasher);
+    let key = hasher.finish();
+    quote!(#key).into()
+}
+
+/// `PlayerStats` -> `player_stats`.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.char_indices() {
+        if c.is_uppercase() && i != 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// Generate a `<struct>_offsets` module of encrypted field offsets and accessors.
+///
+/// For every field `health: u32` the module holds a `HEALTH` constant of
+/// type `EncryptedOffset<Struct, u32>`, each under its own random key, plus
+/// `health(&Struct) -> &u32` and `health_mut(&mut Struct) -> &mut u32`.
+/// Tuple struct fields are named `field_0`, `field_1`, ...
+///
+/// ```ignore
+/// #[derive(pointerguard::EncryptedOffsets)]
+/// struct Player {
+///     health: u32,
+/// }
+///
+/// let player = Player { health: 100 };
+/// assert_eq!(*player_offsets::health(&player), 100);
+/// ```
+#[proc_macro_derive(EncryptedOffsets)]
+pub fn derive_encrypted_offsets(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    offsets_module(&input, &mut callsite_rng())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Implement `pointerguard::Protect`, so `value.protected()` moves the value behind an `EncryptedPtr`.
+///
+/// ```ignore
+/// #[derive(pointerguard::Protect)]
+/// struct Player {
+///     health: u32,
+/// }
+///
+/// let player = Player { health: 100 }.protected();
+/// assert_eq!(player.health, 100);
+/// ```
+#[proc_macro_derive(Protect)]
+pub fn derive_protect(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::pointerguard::Protect for #name #ty_generics #where_clause {
+            type Protected = ::pointerguard::EncryptedPtr<Self>;
+
+            fn protected(self) -> Self::Protected {
+                ::pointerguard::EncryptedPtr::from(self)
+            }
+        }
+    }
+    .into()
+}
+
+/// Implement `pointerguard::SealGraph` by visiting every field in order, for `pointerguard::seal_graph`.
+///
+/// Enums also feed in which variant they are. Fields marked
+/// `#[seal_graph(skip)]` are left out, and every type parameter must
+/// implement `SealGraph` itself.
+///
+/// ```ignore
+/// #[derive(pointerguard::SealGraph)]
+/// struct Player {
+///     score: pointerguard::EncryptedU32,
+///     #[seal_graph(skip)]
+///     frame_time: f32,
+/// }
+/// ```
+#[proc_macro_derive(SealGraph, attributes(seal_graph))]
+pub fn derive_seal_graph(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    seal_graph_impl(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Shuffle a struct's fields into a random order and emit its encrypted offsets.
+///
+/// The struct becomes `#[repr(C)]` in the shuffled order (unless it already
+/// has a `repr`), so its layout differs between builds; pin it with
+/// `POINTERGUARD_LAYOUT_SEED`. Field offsets are emitted as with
+/// `#[derive(EncryptedOffsets)]`, so don't derive that as well. Only structs
+/// with named fields can be shuffled, and not ones that are already
+/// `#[repr(C)]`, since those promise their declared layout.
+///
+/// Fields are dropped in declaration order, so the shuffle also changes the
+/// order they're dropped in: don't shuffle structs whose fields have to be
+/// dropped in a particular order.
+///
+/// ```ignore
+/// #[pointerguard::shuffle_fields]
+/// struct Player {
+///     health: u32,
+///     ammo: u16,
+///     name: [u8; 16],
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn shuffle_fields(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        return Error::new(Span2::call_site(), "shuffle_fields takes no arguments")
+            .into_compile_error()
+            .into();
+    }
+
+    let mut input = parse_macro_input!(input as DeriveInput);
+    if let Some(repr) = input.attrs.iter().find(|attr| is_repr_c(attr)) {
+        return Error::new_spanned(
+            repr,
+            "shuffle_fields can't shuffle a #[repr(C)] struct: repr(C) promises \
+             the declared field order, e.g. to C code, and shuffling would break it",
+        )
+        .into_compile_error()
+        .into();
+    }
+    let mut rng = callsite_rng();
+
+    let fields = match &mut input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => &mut fields.named,
+        _ => {
+            return Error::new_spanned(
+                &input.ident,
+                "shuffle_fields only supports structs with named fields",
+            )
+            .into_compile_error()
+            .into()
+        }
+    };
+
+    let mut shuffled: Vec<_> = std::mem::take(fields).into_iter().collect();
+    shuffled.shuffle(&mut rng);
+    fields.extend(shuffled);
+
+    // repr(Rust) is free to undo the shuffle, repr(C) keeps it.
+    if !input.attrs.iter().any(|attr| attr.path().is_ident("repr")) {
+        input.attrs.push(parse_quote!(#[repr(C)]));
+    }
+
+    let offsets = offsets_module(&input, &mut rng).unwrap_or_else(Error::into_compile_error);
+    quote! {
+        #input
+        #offsets
+    }
+    .into()
+}
+
+/// Whether `attr` is a `#[repr(..)]` that includes `C`.
+fn is_repr_c(attr: &syn::Attribute) -> bool {
+    attr.path().is_ident("repr")
+        && attr.meta.require_list().is_ok_and(|list| {
+            list.tokens
+                .clone()
+                .into_iter()
+                .any(|token| matches!(token, proc_macro2::TokenTree::Ident(ident) if ident == "C"))
+        })
+}
+
+/// The `SealGraph` impl for a struct or enum.
+fn seal_graph_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, visits) = visit_fields(&data.fields)?;
+            quote! {
+                let Self #pattern = self;
+                #(#visits)*
+            }
+        }
+        // an uninhabited enum has no value to visit.
+        Data::Enum(data) if data.variants.is_empty() => quote! { match *self {} },
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(index, variant)| {
+                    let (ident, index) = (&variant.ident, index as u64);
+                    let (pattern, visits) = visit_fields(&variant.fields)?;
+                    Ok(quote! {
+                        Self::#ident #pattern => {
+                            hasher.write_u64(#index);
+                            #(#visits)*
+                        }
+                    })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! { match self { #(#arms)* } }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "SealGraph doesn't support unions",
+            ))
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::pointerguard::SealGraph));
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::pointerguard::SealGraph for #name #ty_generics #where_clause {
+            fn visit(&self, hasher: &mut ::pointerguard::GraphHasher) {
+                #body
+            }
+        }
+    })
+}
+
+/// A pattern binding the visited fields of a struct or variant, and a `visit` call for each.
+fn visit_fields(fields: &Fields) -> syn::Result<(TokenStream2, Vec<TokenStream2>)> {
+    let mut bindings = Vec::new();
+    let mut visits = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let mut skip = false;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("seal_graph"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("skip") {
+                    return Err(meta.error("expected `skip`"));
+                }
+                skip = true;
+                Ok(())
+            })?;
+        }
+
+        let binding = format_ident!("field_{}", i);
+        let member = match &field.ident {
+            Some(name) => Member::Named(name.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        };
+        if skip {
+            bindings.push(quote! { #member: _ });
+        } else {
+            bindings.push(quote! { #member: #binding });
+            visits.push(quote! { ::pointerguard::SealGraph::visit(#binding, hasher); });
+        }
+    }
+
+    let pattern = match fields {
+        Fields::Unit => quote! {},
+        _ => quote! { { #(#bindings),* } },
+    };
+    Ok((pattern, visits))
+}
+
+/// The `<struct>_offsets` module shared by `EncryptedOffsets` and `shuffle_fields`.
+fn offsets_module(input: &DeriveInput, rng: &mut impl Rng) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "EncryptedOffsets only supports structs",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "EncryptedOffsets doesn't support generic structs",
+        ));
+    }
+
+    let (vis, ident) = (&input.vis, &input.ident);
+    let module = format_ident!("{}_offsets", snake_case(&ident.to_string()));
+
+    let accessors = fields.iter().enumerate().map(|(i, field)| {
+        let (member, name) = match &field.ident {
+            Some(name) => (Member::Named(name.clone()), name.to_string()),
+            None => (Member::Unnamed(Index::from(i)), format!("field_{i}")),
+        };
+        // raw field names like `r#type` keep their raw getter, but not in derived names.
+        let bare = name.trim_start_matches("r#");
+        let constant = format_ident!("{}", bare.to_uppercase());
+        let getter = match &field.ident {
+            Some(name) => name.clone(),
+            None => format_ident!("{}", bare),
+        };
+        let getter_mut = format_ident!("{}_mut", bare);
+        let ty = &field.ty;
+        let key: u64 = rng.random();
+
+        quote! {
+            #[doc = concat!("Encrypted offset of `", stringify!(#ident), "::", stringify!(#member), "`.")]
+            pub const #constant: ::pointerguard::EncryptedOffset<#ident, #ty> =
+                unsafe { ::pointerguard::EncryptedOffset::new(::core::mem::offset_of!(#ident, #member), #key) };
+
+            #[doc = concat!("Borrow `", stringify!(#ident), "::", stringify!(#member), "` through its encrypted offset.")]
+            #[inline(always)]
+            pub fn #getter(base: &#ident) -> &#ty {
+                #constant.get(base)
+            }
+
+            #[doc = concat!("Mutably borrow `", stringify!(#ident), "::", stringify!(#member), "` through its encrypted offset.")]
+            #[inline(always)]
+            pub fn #getter_mut(base: &mut #ident) -> &mut #ty {
+                #constant.get_mut(base)
+            }
+        }
+    });
+
+    Ok(quote! {
+        #[doc = concat!("Encrypted field offsets of [`", stringify!(#ident), "`].")]
+        #[allow(dead_code)]
+        #vis mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #(#accessors)*
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_ops_invert() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let (key, data) = (0x1234567890ABCDEFu64, 0xFEDCBA0987654321u64);
+
+        for _ in 0..1000 {
+            let op = Op::random(&mut rng);
+            let encrypted = match op {
+                Op::XorKey(r, c) => data ^ key.rotate_left(r) ^ c,
+                Op::AddKey(r, c) => data.wrapping_add(key.rotate_left(r) ^ c),
+                Op::Rotate(n) => data.rotate_left(n),
+                Op::Multiply(m) => data.wrapping_mul(m),
+                Op::XorShift(s) => data ^ data >> s,
+            };
+            let decrypted = match op {
+                Op::XorKey(r, c) => encrypted ^ key.rotate_left(r) ^ c,
+                Op::AddKey(r, c) => encrypted.wrapping_sub(key.rotate_left(r) ^ c),
+                Op::Rotate(n) => encrypted.rotate_right(n),
+                Op::Multiply(m) => encrypted.wrapping_mul(mul_inverse(m)),
+                Op::XorShift(s) => encrypted ^ encrypted >> s,
+            };
+
+            assert_eq!(decrypted, data, "{op:?}");
+        }
+    }
+
+    #[test]
+    fn snake_case_names() {
+        assert_eq!(snake_case("PlayerStats"), "player_stats");
+        assert_eq!(snake_case("Player"), "player");
+    }
+}
@@ -0,0 +1,216 @@
+use crate::cpu::cpu_features;
+use crate::diag::diag;
+use crate::Encrypt;
+
+/// The AES S-box.
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// Multiply by x in GF(2^8), for MixColumns.
+#[inline(always)]
+fn xtime(byte: u8) -> u8 {
+    (byte << 1) ^ ((byte >> 7) * 0x1b)
+}
+
+/// One AES encryption round (ShiftRows, SubBytes, MixColumns, AddRoundKey) in software.
+///
+/// Bytes are in little-endian order, matching AESENC on a 128-bit load.
+fn soft_round(state: u128, key: u128) -> u128 {
+    let state = state.to_le_bytes();
+
+    let mut shifted = [0u8; 16];
+    for column in 0..4 {
+        for row in 0..4 {
+            shifted[row + 4 * column] = SBOX[state[row + 4 * ((column + row) % 4)] as usize];
+        }
+    }
+
+    let mut mixed = [0u8; 16];
+    for column in 0..4 {
+        let [a0, a1, a2, a3] = [0, 1, 2, 3].map(|row| shifted[row + 4 * column]);
+        mixed[4 * column] = xtime(a0) ^ xtime(a1) ^ a1 ^ a2 ^ a3;
+        mixed[4 * column + 1] = a0 ^ xtime(a1) ^ xtime(a2) ^ a2 ^ a3;
+        mixed[4 * column + 2] = a0 ^ a1 ^ xtime(a2) ^ xtime(a3) ^ a3;
+        mixed[4 * column + 3] = xtime(a0) ^ a0 ^ a1 ^ a2 ^ xtime(a3);
+    }
+
+    u128::from_le_bytes(mixed) ^ key
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "aes")]
+unsafe fn hard_round(state: u128, key: u128) -> u128 {
+    use std::arch::x86_64::{__m128i, _mm_aesenc_si128};
+    use std::mem::transmute;
+
+    let state = transmute::<u128, __m128i>(state);
+    let key = transmute::<u128, __m128i>(key);
+    transmute::<__m128i, u128>(_mm_aesenc_si128(state, key))
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "aes,neon")]
+unsafe fn hard_round(state: u128, key: u128) -> u128 {
+    use std::arch::aarch64::{uint8x16_t, vaeseq_u8, vaesmcq_u8, vdupq_n_u8};
+    use std::mem::transmute;
+
+    // AESE adds the round key before substituting, so add a zero key there
+    // and the real one after MixColumns, as AESENC does.
+    let state = transmute::<u128, uint8x16_t>(state);
+    let mixed = vaesmcq_u8(vaeseq_u8(state, vdupq_n_u8(0)));
+    transmute::<uint8x16_t, u128>(mixed) ^ key
+}
+
+/// One AES encryption round, in hardware when the CPU has AES instructions.
+#[inline(always)]
+pub(crate) fn round(state: u128, key: u128) -> u128 {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    if cpu_features().aes {
+        return unsafe { hard_round(state, key) };
+    }
+
+    soft_round(state, key)
+}
+
+/// Stretch a 64-bit key into a 128-bit round key.
+#[inline(always)]
+fn expand_key(key: u64) -> u128 {
+    ((key.rotate_left(32) ^ 0xA076_1D64_78BD_642F) as u128) << 64 | key as u128
+}
+
+/// A keyed 64-bit MAC of `data`: three AES rounds, folded to one word.
+#[inline(always)]
+pub(crate) fn mac(key: u64, data: u64) -> u64 {
+    let key = expand_key(key);
+    let mut state = data as u128 ^ key;
+    for _ in 0..3 {
+        state = round(state, key.rotate_left(64));
+    }
+
+    (state >> 64) as u64 ^ state as u64
+}
+
+/// A 64-bit Feistel network using two AES rounds as the round function.
+///
+/// The rounds run on AES-NI or the AArch64 crypto extension when the CPU has
+/// them (see [`cpu_features`](crate::cpu_features)), and on a table-based
+/// software round otherwise. Both produce the same ciphertext.
+#[derive(Debug, Clone, Copy)]
+pub struct AesFeistel {
+    rounds: u32,
+}
+
+impl Default for AesFeistel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AesFeistel {
+    /// Default number of Feistel rounds.
+    pub const DEFAULT_ROUNDS: u32 = 6;
+
+    /// A Feistel network with the default six rounds.
+    pub const fn new() -> Self {
+        Self::with_rounds(Self::DEFAULT_ROUNDS)
+    }
+
+    /// A Feistel network with a custom number of rounds, at least 1.
+    pub const fn with_rounds(rounds: u32) -> Self {
+        assert!(
+            rounds >= 1,
+            "{}",
+            diag!("AesFeistel needs at least 1 round")
+        );
+        Self { rounds }
+    }
+
+    /// Number of rounds used.
+    pub const fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    /// The round function: two AES rounds over the half-block and round number.
+    #[inline(always)]
+    fn round_function(key: u128, half: u32, feistel_round: u32) -> u32 {
+        let block = ((feistel_round as u64) << 32 | half as u64) as u128 ^ key;
+        round(round(block, key.rotate_left(64)), key) as u32
+    }
+}
+
+impl Encrypt for AesFeistel {
+    #[inline(always)]
+    fn encrypt(&self, data: u64, key: u64) -> u64 {
+        let key = expand_key(key);
+        let (mut left, mut right) = ((data >> 32) as u32, data as u32);
+
+        for round in 0..self.rounds {
+            (left, right) = (right, left ^ Self::round_function(key, right, round));
+        }
+
+        (left as u64) << 32 | right as u64
+    }
+
+    #[inline(always)]
+    fn decrypt(&self, data: u64, key: u64) -> u64 {
+        let key = expand_key(key);
+        let (mut left, mut right) = ((data >> 32) as u32, data as u32);
+
+        for round in (0..self.rounds).rev() {
+            (left, right) = (right ^ Self::round_function(key, left, round), left);
+        }
+
+        (left as u64) << 32 | right as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn soft_round_matches_fips_197() {
+        // round 1 of the FIPS-197 appendix B example, written big-endian.
+        let state: u128 = 0x193de3bea0f4e22b9ac68d2ae9f84808;
+        let key: u128 = 0xa0fafe1788542cb123a339392a6c7605;
+
+        assert_eq!(
+            soft_round(state.swap_bytes(), key.swap_bytes()).swap_bytes(),
+            0xa49c7ff2689f352b6b5bea43026a5049
+        );
+    }
+
+    #[test]
+    fn hardware_round_matches_software() {
+        for _ in 0..64 {
+            let (state, key) = (rand::random(), rand::random());
+            assert_eq!(round(state, key), soft_round(state, key));
+        }
+    }
+
+    #[test]
+    fn aes_feistel_round_trip() {
+        let cipher = AesFeistel::new();
+        for _ in 0..64 {
+            let (data, key) = (rand::random(), rand::random());
+            assert_eq!(cipher.decrypt(cipher.encrypt(data, key), key), data);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+#[cfg(debug_assertions)]
+use std::collections::HashMap;
+#[cfg(debug_assertions)]
+use std::panic::Location;
+#[cfg(debug_assertions)]
+use std::sync::{Mutex, MutexGuard};
+
+#[cfg(debug_assertions)]
+use crate::diag::diag_panic;
+
+#[cfg(debug_assertions)]
+static GUARDS: Mutex<Option<Guards>> = Mutex::new(None);
+
+/// Live guards per pointee, tracked in debug builds only.
+#[cfg(debug_assertions)]
+struct Guards {
+    /// Mask applied to every stored address, as in the registry.
+    mask: u64,
+    next_id: u64,
+    live: HashMap<u64, Vec<Live>>,
+}
+
+/// One live guard and where it was taken.
+#[cfg(debug_assertions)]
+struct Live {
+    id: u64,
+    exclusive: bool,
+    location: &'static Location<'static>,
+}
+
+#[cfg(debug_assertions)]
+fn lock() -> MutexGuard<'static, Option<Guards>> {
+    GUARDS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A guard's entry in the tracker, removed on drop; zero-sized in release builds.
+///
+/// Two pointers made from the same raw address hand out references into the
+/// same pointee, which the borrow checker can't see. Guards holding one of
+/// these panic on creation instead when an exclusive guard would overlap any
+/// other guard, naming where each was taken.
+pub(crate) struct Borrow {
+    #[cfg(debug_assertions)]
+    masked_addr: u64,
+    #[cfg(debug_assertions)]
+    id: u64,
+}
+
+impl Borrow {
+    /// Track shared access to `ptr`.
+    ///
+    /// # Panics
+    /// In debug builds, if an exclusive guard to `ptr` is alive.
+    #[inline(always)]
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub(crate) fn shared<T>(ptr: *const T) -> Self {
+        Self::acquire(ptr, false)
+    }
+
+    /// Track exclusive access to `ptr`.
+    ///
+    /// # Panics
+    /// In debug builds, if any other guard to `ptr` is alive.
+    #[inline(always)]
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub(crate) fn exclusive<T>(ptr: *const T) -> Self {
+        Self::acquire(ptr, true)
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[inline(always)]
+    fn acquire<T>(_ptr: *const T, _exclusive: bool) -> Self {
+        Self {}
+    }
+
+    #[cfg(debug_assertions)]
+    #[track_caller]
+    fn acquire<T>(ptr: *const T, exclusive: bool) -> Self {
+        // zero-sized pointees all share one dangling address and never overlap.
+        if size_of::<T>() == 0 {
+            return Self {
+                masked_addr: 0,
+                id: u64::MAX,
+            };
+        }
+
+        let location = Location::caller();
+        let mut guard = lock();
+        let guards = guard.get_or_insert_with(|| Guards {
+            mask: rand::random(),
+            next_id: 0,
+            live: HashMap::new(),
+        });
+
+        let masked_addr = ptr as u64 ^ guards.mask;
+        let live = guards.live.entry(masked_addr).or_default();
+        let conflicts: Vec<String> = live
+            .iter()
+            .filter(|other| exclusive || other.exclusive)
+            .map(|other| other.location.to_string())
+            .collect();
+        if !conflicts.is_empty() {
+            drop(guard);
+            diag_panic!(
+                "{} access at {} aliases live guards taken at {}",
+                if exclusive { "exclusive" } else { "shared" },
+                location,
+                conflicts.join(", ")
+            );
+        }
+
+        let id = guards.next_id;
+        guards.next_id += 1;
+        live.push(Live {
+            id,
+            exclusive,
+            location,
+        });
+        Self { masked_addr, id }
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for Borrow {
+    fn drop(&mut self) {
+        if self.id == u64::MAX {
+            return;
+        }
+
+        if let Some(guards) = lock().as_mut() {
+            if let Some(live) = guards.live.get_mut(&self.masked_addr) {
+                live.retain(|other| other.id != self.id);
+                if live.is_empty() {
+                    guards.live.remove(&self.masked_addr);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, debug_assertions, not(feature = "opaque-diagnostics")))]
+mod tests {
+    use crate::SendEncryptedPtr;
+    use pretty_assertions::assert_eq;
+    use std::mem::ManuallyDrop;
+
+    #[test]
+    fn shared_guards_coexist() {
+        let raw = Box::into_raw(Box::new(100u32));
+        let mut a = SendEncryptedPtr::new(raw);
+        let b = ManuallyDrop::new(SendEncryptedPtr::new(raw));
+
+        let (first, second) = (a.access(), b.access());
+        assert_eq!(*first, *second);
+        drop((first, second));
+
+        // released guards don't count.
+        *a.access_mut() -= 25;
+        assert_eq!(*b.access(), 75);
+    }
+
+    #[test]
+    #[should_panic(expected = "aliases live guards taken at src/aliasing.rs")]
+    fn aliased_exclusive_access_panics() {
+        let raw = Box::into_raw(Box::new(100u32));
+        let mut a = SendEncryptedPtr::new(raw);
+        let b = ManuallyDrop::new(SendEncryptedPtr::new(raw));
+
+        let _reading = b.access();
+        *a.access_mut() -= 25;
+    }
+}
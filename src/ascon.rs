@@ -0,0 +1,188 @@
+use crate::diag::diag;
+use crate::Encrypt;
+
+/// A 64-bit Feistel network using the Ascon permutation as the round function.
+///
+/// Ascon is the NIST lightweight cryptography standard; its permutation is
+/// cheap in software and needs no tables, which suits embedded targets. Each
+/// Feistel round absorbs the key, round number and half-block into an Ascon
+/// state and squeezes 32 bits out after a few permutation rounds.
+#[derive(Debug, Clone, Copy)]
+pub struct Ascon {
+    rounds: u32,
+    permutation_rounds: u32,
+}
+
+impl Default for Ascon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ascon {
+    /// Default number of Feistel rounds.
+    pub const DEFAULT_ROUNDS: u32 = 4;
+
+    /// Default number of permutation rounds per Feistel round, as in Ascon's `p^6`.
+    pub const DEFAULT_PERMUTATION_ROUNDS: u32 = 6;
+
+    /// Ascon with the default round counts.
+    pub const fn new() -> Self {
+        Self::with_rounds(Self::DEFAULT_ROUNDS, Self::DEFAULT_PERMUTATION_ROUNDS)
+    }
+
+    /// Ascon with custom Feistel and permutation round counts, each at least 1 (at most 12 permutation rounds).
+    pub const fn with_rounds(rounds: u32, permutation_rounds: u32) -> Self {
+        assert!(
+            rounds >= 1,
+            "{}",
+            diag!("Ascon needs at least 1 Feistel round")
+        );
+        assert!(
+            permutation_rounds >= 1 && permutation_rounds <= 12,
+            "{}",
+            diag!("Ascon has from 1 to 12 rounds")
+        );
+        Self {
+            rounds,
+            permutation_rounds,
+        }
+    }
+
+    /// Number of Feistel rounds used.
+    pub const fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    /// Number of permutation rounds per Feistel round.
+    pub const fn permutation_rounds(&self) -> u32 {
+        self.permutation_rounds
+    }
+
+    /// The round function: 32 bits squeezed from the permuted state.
+    #[inline(always)]
+    fn round_function(&self, key: u64, half: u32, round: u32) -> u32 {
+        let mut state = [
+            0x80400c0600000000,
+            key,
+            key.rotate_left(32) ^ 0x9E3779B97F4A7C15,
+            (round as u64) << 32 | half as u64,
+            0,
+        ];
+
+        permute(&mut state, self.permutation_rounds);
+        (state[0] ^ state[3]) as u32
+    }
+}
+
+/// Apply the last `rounds` rounds of the Ascon permutation `p^12`.
+#[inline(always)]
+fn permute(x: &mut [u64; 5], rounds: u32) {
+    for r in (12 - rounds)..12 {
+        // round constant.
+        x[2] ^= 0xf0 - r as u64 * 0x0f;
+
+        // substitution layer, the 5-bit s-box in bitsliced form.
+        x[0] ^= x[4];
+        x[4] ^= x[3];
+        x[2] ^= x[1];
+        let t = [
+            !x[0] & x[1],
+            !x[1] & x[2],
+            !x[2] & x[3],
+            !x[3] & x[4],
+            !x[4] & x[0],
+        ];
+        x[0] ^= t[1];
+        x[1] ^= t[2];
+        x[2] ^= t[3];
+        x[3] ^= t[4];
+        x[4] ^= t[0];
+        x[1] ^= x[0];
+        x[0] ^= x[4];
+        x[3] ^= x[2];
+        x[2] = !x[2];
+
+        // linear diffusion layer.
+        x[0] ^= x[0].rotate_right(19) ^ x[0].rotate_right(28);
+        x[1] ^= x[1].rotate_right(61) ^ x[1].rotate_right(39);
+        x[2] ^= x[2].rotate_right(1) ^ x[2].rotate_right(6);
+        x[3] ^= x[3].rotate_right(10) ^ x[3].rotate_right(17);
+        x[4] ^= x[4].rotate_right(7) ^ x[4].rotate_right(41);
+    }
+}
+
+impl Encrypt for Ascon {
+    #[inline(always)]
+    fn encrypt(&self, data: u64, key: u64) -> u64 {
+        let (mut left, mut right) = ((data >> 32) as u32, data as u32);
+
+        for round in 0..self.rounds {
+            (left, right) = (right, left ^ self.round_function(key, right, round));
+        }
+
+        (left as u64) << 32 | right as u64
+    }
+
+    #[inline(always)]
+    fn decrypt(&self, data: u64, key: u64) -> u64 {
+        let (mut left, mut right) = ((data >> 32) as u32, data as u32);
+
+        for round in (0..self.rounds).rev() {
+            (left, right) = (right ^ self.round_function(key, left, round), left);
+        }
+
+        (left as u64) << 32 | right as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn decrypt_value_ascon() {
+        let key: u64 = 0x1234567890ABCDEF;
+        let data: u64 = 0xFEDCBA0987654321;
+
+        for ascon in [Ascon::new(), Ascon::with_rounds(6, 12)] {
+            let encrypted = ascon.encrypt(data, key);
+            let decrypted = ascon.decrypt(encrypted, key);
+
+            assert_eq!(data, decrypted);
+        }
+    }
+
+    #[test]
+    fn ascon_permutation_known_answer() {
+        // the Ascon-Hash v1.2 IV, permuted into the initial state the reference implementation precomputes.
+        let mut state = [0x00400c0000000100, 0, 0, 0, 0];
+        permute(&mut state, 12);
+
+        assert_eq!(
+            state,
+            [
+                0xee9398aadb67f03d,
+                0x8bb21831c60f1002,
+                0xb48a92db98d5da62,
+                0x43189921b8f8e3e8,
+                0x348fa5c9d525e140,
+            ]
+        );
+    }
+
+    #[test]
+    fn ascon_permutation_diffuses() {
+        let mut a = [0u64; 5];
+        let mut b = [0u64; 5];
+        b[4] = 1;
+
+        permute(&mut a, 12);
+        permute(&mut b, 12);
+
+        // a single flipped bit should change roughly half the state.
+        let flipped: u32 = a.iter().zip(&b).map(|(a, b)| (a ^ b).count_ones()).sum();
+        assert!((100..220).contains(&flipped));
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::atomic::Ordering;
+
+#[cfg(not(loom))]
+use portable_atomic::AtomicU128;
+
+#[cfg(loom)]
+use model::AtomicU128;
+
+use crate::sealed::{random_method, Sealed};
+
+/// An encrypted raw pointer that can be shared between threads, like [`AtomicPtr`](std::sync::atomic::AtomicPtr).
+///
+/// The ciphertext and key word are updated together as one 16-byte atomic,
+/// so readers never see a ciphertext paired with the wrong key. This is
+/// lock-free wherever the CPU has a 16-byte compare-and-swap (`cmpxchg16b` on
+/// x86-64, `casp` on aarch64). Like `AtomicPtr` it never owns its pointee.
+pub struct AtomicEncryptedPtr<T> {
+    pair: AtomicU128,
+    _marker: PhantomData<*mut T>,
+}
+
+// same as `AtomicPtr`: only the address is shared, never the pointee.
+unsafe impl<T> Send for AtomicEncryptedPtr<T> {}
+unsafe impl<T> Sync for AtomicEncryptedPtr<T> {}
+
+/// Encrypt `ptr` under a fresh key, packed as `key word << 64 | ciphertext`.
+#[inline(always)]
+fn seal<T>(ptr: *mut T) -> u128 {
+    // random_method never picks an owned method, so the pair owns nothing.
+    let (encrypted_ptr, word) = Sealed::encode(ptr as u64, random_method());
+    (word as u128) << 64 | encrypted_ptr as u128
+}
+
+#[inline(always)]
+fn unseal<T>(pair: u128) -> *mut T {
+    Sealed::decode(pair as u64, (pair >> 64) as u64) as *mut T
+}
+
+/// Stop counting a pair that has been replaced, see [`Sealed::release`].
+#[inline(always)]
+fn release(pair: u128) {
+    Sealed::release((pair >> 64) as u64);
+}
+
+impl<T> AtomicEncryptedPtr<T> {
+    /// Create a new atomic encrypted pointer.
+    pub fn new(ptr: *mut T) -> Self {
+        Self {
+            pair: AtomicU128::new(seal(ptr)),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Whether operations are lock-free on this CPU.
+    pub fn is_lock_free() -> bool {
+        AtomicU128::is_lock_free()
+    }
+
+    /// Load and decrypt the pointer.
+    ///
+    /// A pair encrypted before a [`rotate_master_key`](crate::rotate_master_key)
+    /// is re-encrypted under the current key, unless another thread stores first.
+    #[inline(always)]
+    pub fn load(&self, order: Ordering) -> *mut T {
+        let pair = self.pair.load(order);
+        let ptr = unseal(pair);
+        if let Some((encrypted_ptr, word)) = Sealed::migrate_pair(pair as u64, (pair >> 64) as u64)
+        {
+            self.migrate(pair, (word as u128) << 64 | encrypted_ptr as u128);
+        }
+        ptr
+    }
+
+    #[cold]
+    fn migrate(&self, old: u128, new: u128) {
+        // the same address either way, so losing the race to a store is fine.
+        match self
+            .pair
+            .compare_exchange_weak(old, new, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => release(old),
+            Err(_) => release(new),
+        }
+    }
+
+    /// Encrypt `ptr` under a fresh key and store it.
+    #[inline(always)]
+    pub fn store(&self, ptr: *mut T, order: Ordering) {
+        release(self.pair.swap(seal(ptr), order));
+    }
+
+    /// Store `ptr`, returning the previous pointer.
+    #[inline(always)]
+    pub fn swap(&self, ptr: *mut T, order: Ordering) -> *mut T {
+        let old = self.pair.swap(seal(ptr), order);
+        let ptr = unseal(old);
+        release(old);
+        ptr
+    }
+
+    /// Store `new` if the current pointer is `current`, like [`AtomicPtr::compare_exchange`](std::sync::atomic::AtomicPtr::compare_exchange).
+    ///
+    /// Pointers are compared after decryption: the same address stored under
+    /// a different key still matches.
+    pub fn compare_exchange(
+        &self,
+        current: *mut T,
+        new: *mut T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<*mut T, *mut T> {
+        let new = seal(new);
+        let mut observed = self.pair.load(failure);
+
+        loop {
+            let ptr = unseal::<T>(observed);
+            if !ptr::eq(ptr, current) {
+                release(new);
+                return Err(ptr);
+            }
+
+            // the pair may have been re-keyed since, so retry until it settles.
+            match self
+                .pair
+                .compare_exchange_weak(observed, new, success, failure)
+            {
+                Ok(_) => {
+                    release(observed);
+                    return Ok(ptr);
+                }
+                Err(actual) => observed = actual,
+            }
+        }
+    }
+}
+
+impl<T> Drop for AtomicEncryptedPtr<T> {
+    fn drop(&mut self) {
+        release(self.pair.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> Default for AtomicEncryptedPtr<T> {
+    fn default() -> Self {
+        Self::new(ptr::null_mut())
+    }
+}
+
+impl<T> fmt::Debug for AtomicEncryptedPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "AtomicEncryptedPtr");
+        f.debug_struct("AtomicEncryptedPtr")
+            .field(
+                "encrypted_value",
+                &format!("{:#x}", self.pair.load(Ordering::Relaxed) as u64),
+            )
+            .finish()
+    }
+}
+
+/// loom has no 16-byte atomic, so the model tests check the algorithms
+/// above against one built from a loom mutex.
+#[cfg(loom)]
+mod model {
+    use std::sync::atomic::Ordering;
+
+    use loom::sync::Mutex;
+
+    pub(super) struct AtomicU128(Mutex<u128>);
+
+    impl AtomicU128 {
+        pub(super) fn new(value: u128) -> Self {
+            Self(Mutex::new(value))
+        }
+
+        pub(super) fn is_lock_free() -> bool {
+            false
+        }
+
+        pub(super) fn load(&self, _: Ordering) -> u128 {
+            *self.0.lock().unwrap()
+        }
+
+        pub(super) fn store(&self, value: u128, _: Ordering) {
+            *self.0.lock().unwrap() = value;
+        }
+
+        pub(super) fn swap(&self, value: u128, _: Ordering) -> u128 {
+            std::mem::replace(&mut *self.0.lock().unwrap(), value)
+        }
+
+        pub(super) fn compare_exchange_weak(
+            &self,
+            current: u128,
+            new: u128,
+            _: Ordering,
+            _: Ordering,
+        ) -> Result<u128, u128> {
+            let mut value = self.0.lock().unwrap();
+            if *value == current {
+                Ok(std::mem::replace(&mut *value, new))
+            } else {
+                Err(*value)
+            }
+        }
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::*;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    fn targets() -> [usize; 3] {
+        let values: &'static mut [u64; 3] = Box::leak(Box::new([0; 3]));
+        values.each_mut().map(|value| value as *mut u64 as usize)
+    }
+
+    #[test]
+    fn store_never_torn() {
+        loom::model(|| {
+            let [a, b, _] = targets();
+            let ptr = Arc::new(AtomicEncryptedPtr::new(a as *mut u64));
+
+            let writer = {
+                let ptr = ptr.clone();
+                thread::spawn(move || ptr.store(b as *mut u64, Ordering::Release))
+            };
+
+            let loaded = ptr.load(Ordering::Acquire) as usize;
+            assert!(loaded == a || loaded == b);
+
+            writer.join().unwrap();
+            assert_eq!(ptr.load(Ordering::Acquire) as usize, b);
+        });
+    }
+
+    #[test]
+    fn compare_exchange_has_one_winner() {
+        loom::model(|| {
+            let [a, b, c] = targets();
+            let ptr = Arc::new(AtomicEncryptedPtr::new(a as *mut u64));
+
+            let racers = [b, c].map(|new| {
+                let ptr = ptr.clone();
+                thread::spawn(move || {
+                    ptr.compare_exchange(
+                        a as *mut u64,
+                        new as *mut u64,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    )
+                    .is_ok()
+                })
+            });
+
+            let wins = racers
+                .map(|racer| racer.join().unwrap())
+                .iter()
+                .filter(|&&won| won)
+                .count();
+            assert_eq!(wins, 1);
+
+            let last = ptr.load(Ordering::Acquire) as usize;
+            assert!(last == b || last == c);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn atomic_load_store_swap() {
+        let (mut a, mut b) = (1u32, 2u32);
+        let (a, b) = (&mut a as *mut u32, &mut b as *mut u32);
+
+        let ptr = AtomicEncryptedPtr::new(a);
+        assert_eq!(ptr.load(Ordering::Acquire), a);
+
+        ptr.store(b, Ordering::Release);
+        assert_eq!(ptr.swap(a, Ordering::AcqRel), b);
+
+        assert_eq!(
+            ptr.compare_exchange(b, b, Ordering::AcqRel, Ordering::Acquire),
+            Err(a)
+        );
+        assert_eq!(
+            ptr.compare_exchange(a, b, Ordering::AcqRel, Ordering::Acquire),
+            Ok(a)
+        );
+        assert_eq!(ptr.load(Ordering::Acquire), b);
+    }
+
+    #[test]
+    fn atomic_never_torn() {
+        let values: &'static mut [u64; 2] = Box::leak(Box::new([1, 2]));
+        let targets = [
+            &mut values[0] as *mut u64 as usize,
+            &mut values[1] as *mut u64 as usize,
+        ];
+        let ptr = Arc::new(AtomicEncryptedPtr::new(targets[0] as *mut u64));
+
+        let writer = {
+            let ptr = ptr.clone();
+            thread::spawn(move || {
+                for i in 0..10_000 {
+                    ptr.store(targets[i % 2] as *mut u64, Ordering::Release);
+                }
+            })
+        };
+
+        // every load decrypts to one of the stored pointers, never a mix.
+        for _ in 0..10_000 {
+            let loaded = ptr.load(Ordering::Acquire) as usize;
+            assert!(targets.contains(&loaded));
+        }
+
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,272 @@
+use std::hint::black_box;
+use std::io;
+
+use crate::diag::type_name;
+use crate::{DropPolicy, EncryptedPtr};
+
+/// A protected object to look for during [`self_audit`].
+///
+/// The plaintext address and signature are stored masked, so the target
+/// itself never shows up in the scan.
+pub struct AuditTarget {
+    masked_addr: u64,
+    masked_signature: Vec<u8>,
+    mask: u64,
+    size: usize,
+    type_name: &'static str,
+}
+
+impl AuditTarget {
+    fn new<T>(addr: *mut T) -> Self {
+        Self::from_raw(addr as u64, size_of::<T>(), type_name::<T>())
+    }
+
+    pub(crate) fn from_raw(addr: u64, size: usize, type_name: &'static str) -> Self {
+        let mask = rand::random::<u64>() | 1;
+
+        Self {
+            masked_addr: addr ^ mask,
+            masked_signature: Vec::new(),
+            mask,
+            size,
+            type_name,
+        }
+    }
+
+    /// Also look for copies of `signature` outside the protected object itself.
+    pub fn with_signature(mut self, signature: &[u8]) -> Self {
+        self.masked_signature = signature.iter().map(|b| b ^ self.mask as u8).collect();
+        self
+    }
+
+    #[inline(always)]
+    fn object_range(&self) -> (u64, u64) {
+        let addr = self.masked_addr ^ black_box(self.mask);
+        (addr, addr + self.size as u64)
+    }
+}
+
+impl<T, P: DropPolicy> EncryptedPtr<T, P> {
+    /// Describe this pointer for a [`self_audit`] scan.
+    pub fn audit_target(&self) -> AuditTarget {
+        AuditTarget::new(self.decrypt_ptr())
+    }
+}
+
+/// What an audit finding matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingKind {
+    /// The plaintext address of the protected object.
+    Address,
+    /// A copy of the object's signature bytes.
+    Signature,
+}
+
+/// A plaintext leak found during [`self_audit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditFinding {
+    /// Index of the matching target in the slice passed to [`self_audit`].
+    pub target: usize,
+    /// Type name of the protected object.
+    pub type_name: &'static str,
+    /// What was found.
+    pub kind: FindingKind,
+    /// Where it was found.
+    pub location: usize,
+}
+
+/// Result of a [`self_audit`] scan.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    /// Every plaintext leak that was found.
+    pub findings: Vec<AuditFinding>,
+    /// Number of memory regions scanned.
+    pub regions_scanned: usize,
+    /// Number of bytes scanned.
+    pub bytes_scanned: usize,
+}
+
+impl AuditReport {
+    /// Whether the scan found nothing.
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+/// Scan the process's own writable memory for plaintext copies of `targets`.
+///
+/// Only supported on Linux, where memory is read through `/proc/self/mem` so
+/// regions unmapped mid-scan are skipped instead of faulting.
+pub fn self_audit(targets: &[AuditTarget]) -> io::Result<AuditReport> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::scan(targets)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = targets;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Run [`self_audit`] against every pointer in the [`registry`](crate::registry).
+pub fn self_audit_registered() -> io::Result<AuditReport> {
+    self_audit(&crate::registry::audit_targets())
+}
+
+/// Search one chunk of memory starting at `base` for every target.
+fn scan_chunk(targets: &[AuditTarget], base: usize, chunk: &[u8], report: &mut AuditReport) {
+    for (index, target) in targets.iter().enumerate() {
+        let (start, end) = target.object_range();
+
+        // aligned words that decrypt to the plaintext address.
+        for (i, word) in chunk.chunks_exact(8).enumerate() {
+            let word = u64::from_ne_bytes(word.try_into().unwrap());
+            if word ^ black_box(target.mask) == target.masked_addr {
+                report.findings.push(AuditFinding {
+                    target: index,
+                    type_name: target.type_name,
+                    kind: FindingKind::Address,
+                    location: base + i * 8,
+                });
+            }
+        }
+
+        let signature = &target.masked_signature;
+        if signature.is_empty() || chunk.len() < signature.len() {
+            continue;
+        }
+
+        // copies of the signature anywhere but inside the object itself. copies
+        // straddling two chunks are missed; scans are best-effort.
+        for (i, window) in chunk.windows(signature.len()).enumerate() {
+            let location = base + i;
+            if (start..end).contains(&(location as u64)) {
+                continue;
+            }
+
+            let mask = black_box(target.mask) as u8;
+            if window.iter().zip(signature).all(|(b, s)| b ^ mask == *s) {
+                report.findings.push(AuditFinding {
+                    target: index,
+                    type_name: target.type_name,
+                    kind: FindingKind::Signature,
+                    location,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs::{self, File};
+    use std::io;
+    use std::os::unix::fs::FileExt;
+
+    use super::{scan_chunk, AuditReport, AuditTarget};
+
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    pub(super) fn scan(targets: &[AuditTarget]) -> io::Result<AuditReport> {
+        let maps = fs::read_to_string("/proc/self/maps")?;
+        let mem = File::open("/proc/self/mem")?;
+
+        let mut report = AuditReport::default();
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let scratch = buf.as_ptr_range();
+        let scratch = scratch.start as usize..scratch.end as usize;
+
+        for line in maps.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(range), Some(perms)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+
+            // only private or shared writable memory can hold runtime copies.
+            if !perms.starts_with("rw") {
+                continue;
+            }
+
+            let Some((start, end)) = range.split_once('-') else {
+                continue;
+            };
+            let (Ok(start), Ok(end)) = (
+                usize::from_str_radix(start, 16),
+                usize::from_str_radix(end, 16),
+            ) else {
+                continue;
+            };
+
+            report.regions_scanned += 1;
+
+            let mut offset = start;
+            while offset < end {
+                // the buffer holds a copy of what was read last, so never scan it.
+                if scratch.contains(&offset) {
+                    offset = scratch.end;
+                    continue;
+                }
+
+                let mut len = CHUNK_SIZE.min(end - offset);
+                if offset < scratch.start {
+                    len = len.min(scratch.start - offset);
+                }
+                match mem.read_at(&mut buf[..len], offset as u64) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => {
+                        scan_chunk(targets, offset, &buf[..read], &mut report);
+                        report.bytes_scanned += read;
+                        offset += read;
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn finds_leaked_address() {
+        let player: EncryptedPtr<[u8; 16]> = Box::new([0xAB; 16]).into();
+        let target = player.audit_target();
+
+        // deliberately leak the plaintext address into the heap.
+        let leak = Box::new(player.decrypt_ptr() as u64);
+
+        let report = self_audit(&[target]).unwrap();
+        let leak_location = &*leak as *const u64 as usize;
+
+        assert!(report.regions_scanned > 0);
+        assert!(report
+            .findings
+            .iter()
+            .any(|f| f.kind == FindingKind::Address && f.location == leak_location));
+    }
+
+    #[test]
+    fn finds_signature_copy() {
+        let signature = *b"pointerguard-sig";
+        let player: EncryptedPtr<[u8; 16]> = Box::new(signature).into();
+        let target = player.audit_target().with_signature(&signature);
+
+        let copy = Box::new(signature);
+
+        let report = self_audit(&[target]).unwrap();
+        let copy_location = copy.as_ptr() as usize;
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|f| f.kind == FindingKind::Signature && f.location == copy_location)
+            .collect();
+
+        assert_eq!(found.len(), 1);
+    }
+}
@@ -0,0 +1,160 @@
+use std::alloc::{self, Layout};
+
+use region::Error;
+
+/// Where a page-backed pointer's pages come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum PageBacking {
+    /// The global allocator.
+    #[default]
+    Heap,
+    /// A `memfd_secret` mapping (Linux 5.14+), removed from the kernel's
+    /// direct map so other processes, and most kernel-side forensic tooling,
+    /// can't read it.
+    ///
+    /// Unsupported elsewhere; Linux needs `secretmem.enable=1` on older kernels.
+    Secret,
+    /// An anonymous mapping excluded from core dumps: `MAP_CONCEAL` on
+    /// OpenBSD, which also hides it from some introspection, `MAP_NOCORE` on
+    /// FreeBSD and DragonFly, `MADV_DONTDUMP` on Linux.
+    ///
+    /// Unsupported elsewhere.
+    Concealed,
+}
+
+/// Allocate zeroed memory for `layout` from `backing`.
+pub(crate) fn allocate(layout: Layout, backing: PageBacking) -> Result<*mut u8, Error> {
+    match backing {
+        PageBacking::Heap => {
+            let ptr = unsafe { alloc::alloc_zeroed(layout) };
+            if ptr.is_null() {
+                alloc::handle_alloc_error(layout);
+            }
+            Ok(ptr)
+        }
+        PageBacking::Secret => secret(layout.size()),
+        PageBacking::Concealed => concealed(layout.size()),
+    }
+}
+
+/// Return memory from [`allocate`].
+///
+/// # Safety
+/// `ptr` must come from `allocate(layout, backing)` and not be used afterwards.
+pub(crate) unsafe fn release(ptr: *mut u8, layout: Layout, backing: PageBacking) {
+    match backing {
+        PageBacking::Heap => alloc::dealloc(ptr, layout),
+        PageBacking::Secret | PageBacking::Concealed => unmap(ptr, layout.size()),
+    }
+}
+
+/// The last OS error, as `region` reports failed system calls.
+#[cfg(any(
+    target_os = "openbsd",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "linux"
+))]
+fn last_error() -> Error {
+    Error::SystemCall(std::io::Error::last_os_error().raw_os_error().unwrap_or(0))
+}
+
+#[cfg(target_os = "linux")]
+fn secret(len: usize) -> Result<*mut u8, Error> {
+    let fd = unsafe { libc::syscall(libc::SYS_memfd_secret, libc::O_CLOEXEC) } as i32;
+    if fd < 0 {
+        return Err(last_error());
+    }
+
+    // the mapping keeps the memory alive once the descriptor is closed.
+    let ptr = unsafe {
+        match libc::ftruncate(fd, len as libc::off_t) {
+            0 => libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            ),
+            _ => libc::MAP_FAILED,
+        }
+    };
+    let error = last_error();
+    unsafe { libc::close(fd) };
+
+    match ptr {
+        libc::MAP_FAILED => Err(error),
+        ptr => Ok(ptr as *mut u8),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn secret(_len: usize) -> Result<*mut u8, Error> {
+    Err(Error::InvalidParameter(
+        "memfd_secret is only available on linux",
+    ))
+}
+
+/// Extra `mmap` flags keeping a mapping out of core dumps, where there are any.
+#[cfg(target_os = "openbsd")]
+const CONCEAL_FLAGS: libc::c_int = libc::MAP_CONCEAL;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+const CONCEAL_FLAGS: libc::c_int = libc::MAP_NOCORE;
+#[cfg(target_os = "linux")]
+const CONCEAL_FLAGS: libc::c_int = 0;
+
+#[cfg(any(
+    target_os = "openbsd",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "linux"
+))]
+fn concealed(len: usize) -> Result<*mut u8, Error> {
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANON | CONCEAL_FLAGS,
+            -1,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(last_error());
+    }
+
+    // linux has no mmap flag for it, only the advice.
+    #[cfg(target_os = "linux")]
+    if unsafe { libc::madvise(ptr, len, libc::MADV_DONTDUMP) } != 0 {
+        let error = last_error();
+        unsafe { libc::munmap(ptr, len) };
+        return Err(error);
+    }
+
+    Ok(ptr as *mut u8)
+}
+
+#[cfg(not(any(
+    target_os = "openbsd",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "linux"
+)))]
+fn concealed(_len: usize) -> Result<*mut u8, Error> {
+    Err(Error::InvalidParameter(
+        "concealed mappings need openbsd, freebsd, dragonfly or linux",
+    ))
+}
+
+#[cfg(unix)]
+unsafe fn unmap(ptr: *mut u8, len: usize) {
+    libc::munmap(ptr as *mut libc::c_void, len);
+}
+
+#[cfg(not(unix))]
+unsafe fn unmap(_ptr: *mut u8, _len: usize) {
+    crate::diag::diag_panic!("nothing is mapped on this platform");
+}
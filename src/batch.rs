@@ -0,0 +1,66 @@
+use crate::diag::type_name;
+use crate::observer::{self, Access};
+use crate::sealed::Sealed;
+use crate::{jitter, DropPolicy, EncryptedPtr};
+
+/// Decrypt every pointer in `ptrs` and hand `f` the references, in order.
+///
+/// For systems that touch thousands of protected entities per tick: the
+/// poison check, the master key load (and unseal, if evicted) and the access
+/// jitter happen once for the whole batch instead of once per pointer, and
+/// the built-in methods decrypt with the precomputed schedule. Per-pointer
+/// MACs, with the `mac` feature, are still checked on each address.
+///
+/// ```
+/// use pointerguard::{decrypt_many, EncryptedPtr};
+///
+/// let healths: Vec<EncryptedPtr<u32>> = (0..1000).map(EncryptedPtr::from).collect();
+/// let total: u32 = decrypt_many(&healths, |healths| healths.iter().copied().sum());
+/// assert_eq!(total, 499_500);
+/// ```
+pub fn decrypt_many<T, P: DropPolicy, R>(
+    ptrs: &[EncryptedPtr<T, P>],
+    f: impl FnOnce(&[&T]) -> R,
+) -> R {
+    jitter::jitter();
+    let master = Sealed::batch_key();
+
+    let refs: Vec<&T> = ptrs
+        .iter()
+        .map(|ptr| {
+            observer::observe(Access::Deref, type_name::<T>());
+            let ptr = ptr.sealed.get_with(master) as *const T;
+            #[cfg(all(windows, feature = "veh"))]
+            crate::veh::record(ptr as u64, size_of::<T>(), type_name::<T>());
+
+            unsafe { &*ptr }
+        })
+        .collect();
+    f(&refs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Speck, Xtea};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn batch_matches_single_access() {
+        let mut ptrs: Vec<EncryptedPtr<u64>> = (0..64).map(EncryptedPtr::from).collect();
+        ptrs.push(EncryptedPtr::with_method(
+            Box::into_raw(Box::new(64)),
+            Speck::default(),
+        ));
+        ptrs.push(EncryptedPtr::with_method(
+            Box::into_raw(Box::new(65)),
+            Xtea::default(),
+        ));
+
+        let values = decrypt_many(&ptrs, |values| {
+            values.iter().map(|&&v| v).collect::<Vec<_>>()
+        });
+        assert_eq!(values, (0..66).collect::<Vec<_>>());
+        assert_eq!(decrypt_many(&ptrs[..0], |values| values.len()), 0);
+    }
+}
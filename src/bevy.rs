@@ -0,0 +1,112 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use bevy_ecs::component::Component;
+use bevy_ecs::resource::Resource;
+
+use crate::SendEncryptedPtr;
+
+/// A bevy component stored behind an encrypted pointer.
+///
+/// Use `EncryptedComponent<Health>` wherever the plain component would go;
+/// the ECS table then only holds the pointer's ciphertext, and queries
+/// dereference to the `Health` inside.
+#[derive(Component)]
+pub struct EncryptedComponent<T: Send + Sync + 'static> {
+    ptr: SendEncryptedPtr<T>,
+}
+
+/// A bevy resource stored behind an encrypted pointer, see [`EncryptedComponent`].
+#[derive(Resource)]
+pub struct EncryptedRes<T: Send + Sync + 'static> {
+    ptr: SendEncryptedPtr<T>,
+}
+
+macro_rules! impl_wrapper {
+    ($wrapper:ident) => {
+        impl<T: Send + Sync + 'static> $wrapper<T> {
+            /// Protect `value`.
+            pub fn new(value: T) -> Self {
+                Self { ptr: value.into() }
+            }
+
+            /// Take the value back out.
+            pub fn into_inner(self) -> T {
+                *unsafe { Box::from_raw(self.ptr.into_inner().into_raw()) }
+            }
+        }
+
+        impl<T: Send + Sync + 'static> From<T> for $wrapper<T> {
+            fn from(value: T) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl<T: Send + Sync + 'static> Deref for $wrapper<T> {
+            type Target = T;
+
+            #[inline(always)]
+            fn deref(&self) -> &Self::Target {
+                self.ptr.inner.access()
+            }
+        }
+
+        impl<T: Send + Sync + 'static> DerefMut for $wrapper<T> {
+            #[inline(always)]
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                self.ptr.inner.access_mut()
+            }
+        }
+
+        impl<T: Send + Sync + fmt::Debug + 'static> fmt::Debug for $wrapper<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                crate::diag::opaque_debug!(f, stringify!($wrapper));
+                f.debug_tuple(stringify!($wrapper))
+                    .field(&self.ptr)
+                    .finish()
+            }
+        }
+    };
+}
+
+impl_wrapper!(EncryptedComponent);
+impl_wrapper!(EncryptedRes);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::{Query, Res, RunSystemOnce};
+    use bevy_ecs::world::World;
+    use pretty_assertions::assert_eq;
+
+    struct Health {
+        value: u32,
+    }
+
+    struct Damage {
+        value: u32,
+    }
+
+    fn apply_damage(
+        damage: Res<EncryptedRes<Damage>>,
+        mut query: Query<&mut EncryptedComponent<Health>>,
+    ) {
+        for mut health in &mut query {
+            health.value -= damage.value;
+        }
+    }
+
+    #[test]
+    fn systems_see_protected_values() {
+        let mut world = World::new();
+        world.insert_resource(EncryptedRes::new(Damage { value: 25 }));
+        let player = world
+            .spawn(EncryptedComponent::new(Health { value: 100 }))
+            .id();
+
+        world.run_system_once(apply_damage).unwrap();
+
+        let health = world.get::<EncryptedComponent<Health>>(player).unwrap();
+        assert_eq!(health.value, 75);
+    }
+}
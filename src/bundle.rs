@@ -0,0 +1,255 @@
+use std::any::TypeId;
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::diag::{diag, diag_panic, type_name};
+use crate::master::{self, MaskedKey};
+use crate::observer::{self, Access};
+use crate::sealed::{self, random_method, Method};
+use crate::{config, stats, Encrypt};
+
+/// Handle to one pointer in an [`EncryptedBundle`].
+pub struct BundleKey<T> {
+    index: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for BundleKey<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for BundleKey<T> {}
+
+impl<T> fmt::Debug for BundleKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "BundleKey");
+        f.debug_tuple("BundleKey").field(&self.index).finish()
+    }
+}
+
+struct Entry {
+    encrypted_ptr: u64,
+    type_id: TypeId,
+    drop: unsafe fn(*mut u8),
+}
+
+/// A group of related pointers protected under one shared, rolling key.
+///
+/// Useful for clusters of objects that are always used together (player,
+/// inventory, stats): a single key and method are shared, and
+/// [`EncryptedBundle::rekey`] re-encrypts every pointer at once, which also
+/// happens automatically every few mutable accesses.
+pub struct EncryptedBundle {
+    entries: Vec<Option<Entry>>,
+    key: MaskedKey,
+    method: Method,
+    interval: u32,
+    accesses: u32,
+}
+
+impl Default for EncryptedBundle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EncryptedBundle {
+    /// Create an empty bundle, rekeying at the configured [`rekey_interval`](crate::Config::rekey_interval).
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            key: MaskedKey::new(sealed::generate_key()),
+            method: random_method(),
+            interval: config::read(|config| config.rekey_interval),
+            accesses: 0,
+        }
+    }
+
+    /// Set how many mutable accesses happen between automatic key rolls; zero disables them.
+    pub fn set_rekey_interval(&mut self, interval: u32) {
+        self.interval = interval;
+        self.accesses = 0;
+    }
+
+    /// Move `value` into the bundle.
+    pub fn insert<T: 'static>(&mut self, value: T) -> BundleKey<T> {
+        unsafe fn drop_boxed<T>(ptr: *mut u8) {
+            drop(Box::from_raw(ptr as *mut T));
+        }
+
+        let ptr = Box::into_raw(Box::new(value)) as u64;
+        self.entries.push(Some(Entry {
+            encrypted_ptr: self.method.encrypt(ptr, self.key.get()),
+            type_id: TypeId::of::<T>(),
+            drop: drop_boxed::<T>,
+        }));
+
+        BundleKey {
+            index: self.entries.len() - 1,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Take a value back out of the bundle.
+    pub fn remove<T: 'static>(&mut self, key: BundleKey<T>) -> T {
+        let ptr = self.decrypt_ptr(key);
+        self.entries[key.index] = None;
+
+        *unsafe { Box::from_raw(ptr) }
+    }
+
+    /// Borrow a value.
+    pub fn get<T: 'static>(&self, key: BundleKey<T>) -> &T {
+        unsafe { &*self.decrypt_ptr(key) }
+    }
+
+    /// Mutably borrow a value.
+    pub fn get_mut<T: 'static>(&mut self, key: BundleKey<T>) -> &mut T {
+        self.tick();
+        unsafe { &mut *self.decrypt_ptr(key) }
+    }
+
+    /// Mutably borrow two different values at once.
+    pub fn pair_mut<A: 'static, B: 'static>(
+        &mut self,
+        a: BundleKey<A>,
+        b: BundleKey<B>,
+    ) -> (&mut A, &mut B) {
+        assert!(
+            a.index != b.index,
+            "{}",
+            diag!("pair_mut needs two different keys")
+        );
+
+        self.tick();
+        unsafe { (&mut *self.decrypt_ptr(a), &mut *self.decrypt_ptr(b)) }
+    }
+
+    /// Roll the shared key and method, re-encrypting every pointer.
+    pub fn rekey(&mut self) {
+        stats::rekeyed();
+        observer::observe(Access::Rekey, type_name::<Self>());
+        let old_key = self.key.get();
+        let new_method = random_method();
+        let new_key = MaskedKey::new(sealed::generate_key());
+
+        for entry in self.entries.iter_mut().flatten() {
+            let ptr = self.method.decrypt(entry.encrypted_ptr, old_key);
+            entry.encrypted_ptr = new_method.encrypt(ptr, new_key.get());
+        }
+
+        self.method = new_method;
+        self.key = new_key;
+    }
+
+    /// Number of values in the bundle.
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    /// Whether the bundle holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Count a mutable access, rolling the key once the interval is reached.
+    ///
+    /// A key from before a [`rotate_master_key`](crate::rotate_master_key) is rolled right away.
+    fn tick(&mut self) {
+        if !self.key.is_current() {
+            self.accesses = 0;
+            self.rekey();
+            return;
+        }
+
+        if self.interval == 0 {
+            return;
+        }
+
+        self.accesses += 1;
+        if self.accesses >= self.interval {
+            self.accesses = 0;
+            self.rekey();
+        }
+    }
+
+    /// Decrypt the pointer for `key`, checking it belongs to a live T.
+    #[inline(always)]
+    fn decrypt_ptr<T: 'static>(&self, key: BundleKey<T>) -> *mut T {
+        if master::is_poisoned() {
+            diag_panic!("protected pointers have been poisoned");
+        }
+
+        let entry = match self.entries.get(key.index) {
+            Some(Some(entry)) if entry.type_id == TypeId::of::<T>() => entry,
+            _ => diag_panic!("{} is not in this bundle", type_name::<T>()),
+        };
+
+        self.method.decrypt(entry.encrypted_ptr, self.key.get()) as *mut T
+    }
+}
+
+impl Drop for EncryptedBundle {
+    fn drop(&mut self) {
+        if master::is_poisoned() {
+            return;
+        }
+
+        let key = self.key.get();
+        for entry in self.entries.iter().flatten() {
+            let ptr = self.method.decrypt(entry.encrypted_ptr, key);
+            unsafe { (entry.drop)(ptr as *mut u8) };
+        }
+    }
+}
+
+impl fmt::Debug for EncryptedBundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedBundle");
+        f.debug_struct("EncryptedBundle")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn bundle_survives_rekey() {
+        let mut bundle = EncryptedBundle::new();
+        let health = bundle.insert(100u32);
+        let name = bundle.insert(String::from("player"));
+
+        let before = bundle.entries[0].as_ref().unwrap().encrypted_ptr;
+        bundle.rekey();
+
+        // passthrough builds store plain addresses, which rekeying can't change.
+        if !cfg!(passthrough) {
+            assert!(before != bundle.entries[0].as_ref().unwrap().encrypted_ptr);
+        }
+        assert_eq!(*bundle.get(health), 100);
+        assert_eq!(bundle.get(name), "player");
+    }
+
+    #[test]
+    fn bundle_pair_mut() {
+        let mut bundle = EncryptedBundle::new();
+        bundle.set_rekey_interval(1);
+
+        let health = bundle.insert(100u32);
+        let inventory = bundle.insert(vec!["sword"]);
+
+        let (health_ref, inventory_ref) = bundle.pair_mut(health, inventory);
+        *health_ref -= 10;
+        inventory_ref.push("shield");
+
+        assert_eq!(*bundle.get(health), 90);
+        assert_eq!(bundle.remove(inventory), ["sword", "shield"]);
+        assert_eq!(bundle.len(), 1);
+    }
+}
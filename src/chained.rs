@@ -0,0 +1,77 @@
+use crate::Encrypt;
+
+/// Two methods applied in sequence, each under its own key.
+///
+/// `first` runs under the pointer key and `second` under a key derived from
+/// it, so layering e.g. a fast XOR method with a block cipher doesn't let one
+/// layer's key schedule cancel the other. Build longer chains with
+/// [`Chained::then`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chained<A, B>(pub A, pub B);
+
+impl<A: Encrypt, B: Encrypt> Chained<A, B> {
+    /// Chain `first` and `second`.
+    pub const fn new(first: A, second: B) -> Self {
+        Self(first, second)
+    }
+
+    /// Append another method to the end of the chain.
+    pub const fn then<C: Encrypt>(self, next: C) -> Chained<Self, C> {
+        Chained(self, next)
+    }
+
+    /// Derive the key for the second method from the pointer key.
+    #[inline(always)]
+    fn second_key(key: u64) -> u64 {
+        // splitmix64 finalizer, so related pointer keys give unrelated layer keys.
+        let mut z = key.wrapping_add(0x9E3779B97F4A7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl<A: Encrypt, B: Encrypt> Encrypt for Chained<A, B> {
+    #[inline(always)]
+    fn encrypt(&self, data: u64, key: u64) -> u64 {
+        let data = self.0.encrypt(data, key);
+        self.1.encrypt(data, Self::second_key(key))
+    }
+
+    #[inline(always)]
+    fn decrypt(&self, data: u64, key: u64) -> u64 {
+        let data = self.1.decrypt(data, Self::second_key(key));
+        self.0.decrypt(data, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EncryptedPtr, MethodA, MethodB, Speck};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn decrypt_value_chained() {
+        let key: u64 = 0x1234567890ABCDEF;
+        let data: u64 = 0xFEDCBA0987654321;
+
+        let chained = Chained::new(MethodA, Speck::with_rounds(8)).then(MethodB);
+
+        let encrypted = chained.encrypt(data, key);
+        let decrypted = chained.decrypt(encrypted, key);
+
+        assert_eq!(data, decrypted);
+        assert!(encrypted != Speck::with_rounds(8).encrypt(MethodA.encrypt(data, key), key));
+    }
+
+    #[test]
+    fn decrypt_ptr_chained() {
+        let health = EncryptedPtr::with_method(
+            Box::into_raw(Box::new(100u32)),
+            Chained::new(MethodA, Speck::new()),
+        );
+
+        assert_eq!(*health, 100);
+    }
+}
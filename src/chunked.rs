@@ -0,0 +1,308 @@
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::{Deref, DerefMut};
+
+use crate::diag::diag;
+use crate::master::MaskedKey;
+use crate::{aes, wipe};
+
+/// A large byte buffer kept encrypted at rest, decrypted one fixed-size chunk at a time.
+///
+/// For multi-megabyte pointees such as asset tables or nav meshes: reading
+/// goes through [`ChunkedBuffer::chunk`] or a [`ChunkCursor`], which decrypt
+/// a copy of one window and wipe it when done, and
+/// [`ChunkedBuffer::chunk_mut`] reseals its window under a fresh keystream
+/// when the guard drops, so the whole buffer is never plaintext at once. The
+/// portable counterpart of `LazyBuffer`, without page faults.
+pub struct ChunkedBuffer {
+    ciphertext: Vec<u8>,
+    chunk_size: usize,
+    key: MaskedKey,
+    /// Bumped every time a chunk is re-encrypted, so no keystream is reused.
+    generations: Vec<u64>,
+}
+
+impl ChunkedBuffer {
+    /// Encrypt `bytes` in place, in chunks of `chunk_size` bytes.
+    ///
+    /// # Panics
+    /// If `chunk_size` is zero.
+    pub fn new(bytes: Vec<u8>, chunk_size: usize) -> Self {
+        assert!(chunk_size != 0, "{}", diag!("chunk size must be non-zero"));
+
+        let mut buffer = Self {
+            generations: vec![0; bytes.len().div_ceil(chunk_size)],
+            ciphertext: bytes,
+            chunk_size,
+            key: MaskedKey::new(rand::random()),
+        };
+        for index in 0..buffer.chunk_count() {
+            let (key, range) = (buffer.chunk_key(index), buffer.range(index));
+            apply_keystream(key, index, &mut buffer.ciphertext[range]);
+        }
+        buffer
+    }
+
+    /// Length of the buffer in bytes.
+    pub fn len(&self) -> usize {
+        self.ciphertext.len()
+    }
+
+    /// Whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.ciphertext.is_empty()
+    }
+
+    /// Size of every chunk but possibly the last.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Number of chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.generations.len()
+    }
+
+    /// Decrypt a copy of chunk `index`, wiped when the guard drops.
+    pub fn chunk(&self, index: usize) -> Option<ChunkGuard> {
+        (index < self.chunk_count()).then(|| ChunkGuard {
+            bytes: self.decrypt(index),
+        })
+    }
+
+    /// Decrypt chunk `index` for writing; it is resealed under a fresh keystream when the guard drops.
+    pub fn chunk_mut(&mut self, index: usize) -> Option<ChunkGuardMut<'_>> {
+        self.key.migrate();
+        (index < self.chunk_count()).then(|| ChunkGuardMut {
+            bytes: self.decrypt(index),
+            buffer: self,
+            index,
+        })
+    }
+
+    /// A reader over the whole buffer that holds at most one decrypted chunk.
+    pub fn cursor(&self) -> ChunkCursor<'_> {
+        ChunkCursor {
+            buffer: self,
+            position: 0,
+            window: None,
+        }
+    }
+
+    fn range(&self, index: usize) -> std::ops::Range<usize> {
+        let start = index * self.chunk_size;
+        start..(start + self.chunk_size).min(self.len())
+    }
+
+    fn decrypt(&self, index: usize) -> Vec<u8> {
+        let mut bytes = self.ciphertext[self.range(index)].to_vec();
+        apply_keystream(self.chunk_key(index), index, &mut bytes);
+        bytes
+    }
+
+    /// The keystream key of chunk `index` in its current generation.
+    fn chunk_key(&self, index: usize) -> u64 {
+        self.key.get() ^ self.generations[index].rotate_left(32)
+    }
+}
+
+/// XOR chunk `index` with its keystream under `key`, encrypting or decrypting it.
+pub(crate) fn apply_keystream(key: u64, index: usize, bytes: &mut [u8]) {
+    for (word, chunk) in bytes.chunks_mut(8).enumerate() {
+        let stream = aes::mac(key, ((index as u64) << 32) | word as u64).to_ne_bytes();
+        for (byte, stream) in chunk.iter_mut().zip(stream) {
+            *byte ^= stream;
+        }
+    }
+}
+
+impl fmt::Debug for ChunkedBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ChunkedBuffer");
+        f.debug_struct("ChunkedBuffer")
+            .field("len", &self.len())
+            .field("chunk_size", &self.chunk_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A decrypted copy of one chunk of a [`ChunkedBuffer`] or `MappedBuffer`, wiped on drop.
+pub struct ChunkGuard {
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl Deref for ChunkGuard {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Drop for ChunkGuard {
+    fn drop(&mut self) {
+        unsafe { wipe::wipe(self.bytes.as_mut_ptr(), self.bytes.len()) };
+    }
+}
+
+impl fmt::Debug for ChunkGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ChunkGuard");
+        f.debug_struct("ChunkGuard")
+            .field("len", &self.bytes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Write access to one chunk of a [`ChunkedBuffer`], resealed on drop.
+pub struct ChunkGuardMut<'a> {
+    buffer: &'a mut ChunkedBuffer,
+    index: usize,
+    bytes: Vec<u8>,
+}
+
+impl Deref for ChunkGuardMut<'_> {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl DerefMut for ChunkGuardMut<'_> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+}
+
+impl Drop for ChunkGuardMut<'_> {
+    fn drop(&mut self) {
+        let buffer = &mut *self.buffer;
+        buffer.generations[self.index] += 1;
+
+        let (key, range) = (buffer.chunk_key(self.index), buffer.range(self.index));
+        let chunk = &mut buffer.ciphertext[range];
+        chunk.copy_from_slice(&self.bytes);
+        apply_keystream(key, self.index, chunk);
+
+        unsafe { wipe::wipe(self.bytes.as_mut_ptr(), self.bytes.len()) };
+    }
+}
+
+impl fmt::Debug for ChunkGuardMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ChunkGuardMut");
+        f.debug_struct("ChunkGuardMut")
+            .field("index", &self.index)
+            .field("len", &self.bytes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Read`] and [`Seek`] cursor over a [`ChunkedBuffer`], see [`ChunkedBuffer::cursor`].
+///
+/// Only the chunk under the cursor is decrypted, and it is wiped as soon as
+/// the cursor moves past it or drops.
+pub struct ChunkCursor<'a> {
+    buffer: &'a ChunkedBuffer,
+    position: u64,
+    window: Option<(usize, ChunkGuard)>,
+}
+
+impl ChunkCursor<'_> {
+    /// The current position in bytes.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+impl Read for ChunkCursor<'_> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let position = self.position as usize;
+        if position >= self.buffer.len() || out.is_empty() {
+            return Ok(0);
+        }
+
+        let index = position / self.buffer.chunk_size;
+        if self.window.as_ref().map(|(current, _)| *current) != Some(index) {
+            // drop the old window, wiping it, before decrypting the next.
+            self.window = None;
+            self.window = self.buffer.chunk(index).map(|chunk| (index, chunk));
+        }
+
+        let (_, chunk) = self.window.as_ref().unwrap();
+        let offset = position - index * self.buffer.chunk_size;
+        let read = out.len().min(chunk.len() - offset);
+        out[..read].copy_from_slice(&chunk[offset..offset + read]);
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for ChunkCursor<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => (0, offset as i64),
+            SeekFrom::End(offset) => (self.buffer.len() as u64, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        self.position = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                diag!("seek to a negative position"),
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+impl fmt::Debug for ChunkCursor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ChunkCursor");
+        f.debug_struct("ChunkCursor")
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn navmesh() -> Vec<u8> {
+        (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn cursor_streams_the_plaintext() {
+        let buffer = ChunkedBuffer::new(navmesh(), 4096);
+        assert_eq!(buffer.chunk_count(), 3);
+        assert!(buffer.ciphertext[..64] != navmesh()[..64]);
+
+        let mut all = Vec::new();
+        buffer.cursor().read_to_end(&mut all).unwrap();
+        assert_eq!(all, navmesh());
+
+        let mut cursor = buffer.cursor();
+        let mut tail = [0; 8];
+        cursor.seek(SeekFrom::End(-8)).unwrap();
+        cursor.read_exact(&mut tail).unwrap();
+        assert_eq!(&tail, &navmesh()[9992..]);
+    }
+
+    #[test]
+    fn writes_reseal_their_chunk() {
+        let mut buffer = ChunkedBuffer::new(navmesh(), 4096);
+        let before = buffer.ciphertext.clone();
+
+        buffer.chunk_mut(2).unwrap()[..4].copy_from_slice(b"edge");
+        assert_eq!(&buffer.chunk(2).unwrap()[..4], b"edge");
+        assert_eq!(&buffer.chunk(1).unwrap()[..], &navmesh()[4096..8192]);
+        assert!(buffer.ciphertext[8196..] != before[8196..]);
+        assert!(buffer.chunk(3).is_none());
+    }
+}
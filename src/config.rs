@@ -0,0 +1,200 @@
+use std::fmt;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "region")]
+use crate::PageBacking;
+use crate::{hwrng, jitter, methods, AccessJitter, Encrypt};
+
+/// Where fresh pointer keys come from.
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum KeySource {
+    /// The current time in nanoseconds; cheap, but guessable.
+    #[default]
+    Time,
+    /// The thread-local CSPRNG.
+    Random,
+    /// The CPU's RDSEED or RDRAND instruction where available, else the thread-local CSPRNG.
+    Hardware,
+    /// A user-supplied generator, e.g. backed by hardware.
+    Custom(fn() -> u64),
+}
+
+impl KeySource {
+    /// Produce a key.
+    #[inline(always)]
+    pub fn generate(&self) -> u64 {
+        match self {
+            KeySource::Time => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos() as _,
+            KeySource::Random => rand::random(),
+            KeySource::Hardware => hwrng::random(),
+            KeySource::Custom(generate) => generate(),
+        }
+    }
+}
+
+/// What happens after tampering has been reported to the tamper callback.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TamperPolicy {
+    /// Only report; the detecting type decides what to do next.
+    #[default]
+    Report,
+    /// Panic at the point of detection.
+    Panic,
+    /// Poison every protected pointer, see [`is_poisoned`](crate::is_poisoned).
+    Poison,
+    /// Abort the process.
+    Abort,
+}
+
+/// Process-wide defaults consulted by every constructor, see [`configure`].
+#[derive(Clone)]
+pub struct Config {
+    /// Methods new pointers choose from in place of the built-ins; empty keeps the built-ins.
+    ///
+    /// These take the built-ins' place in the rotation, so methods added with
+    /// [`register_method`](crate::register_method) still join in.
+    pub default_methods: Vec<&'static (dyn Encrypt + Send + Sync)>,
+    /// Where new pointer keys come from.
+    pub key_source: KeySource,
+    /// Mixed into every key from `key_source`, so part of each key is baked into the binary.
+    ///
+    /// Set it from [`const_key!`](crate::const_key) to have it differ per
+    /// build: a dump of one build's memory then doesn't carry over to the
+    /// next, and guessable sources like [`KeySource::Time`] stop being
+    /// guessable without the binary. Zero leaves keys as generated.
+    pub key_whitening: u64,
+    /// What happens after tampering is detected.
+    pub tamper_policy: TamperPolicy,
+    /// Default number of mutable accesses between automatic key rolls of a new
+    /// [`EncryptedBundle`](crate::EncryptedBundle); zero disables them.
+    ///
+    /// Only bundles roll their key automatically; other types keep theirs
+    /// until rekeyed explicitly.
+    pub rekey_interval: u32,
+    /// Overwrite pointees with random bytes before their memory is freed, so freed heap chunks hold no secrets.
+    ///
+    /// Applies to the default [`Dealloc`](crate::Dealloc) policy and to
+    /// [`EncryptedVec`](crate::EncryptedVec); [`Wipe`](crate::Wipe) always wipes.
+    pub wipe_on_drop: bool,
+    /// Random delays injected into [`EncryptedPtr`](crate::EncryptedPtr) accesses; off by default.
+    pub access_jitter: AccessJitter,
+    /// Where [`PageGuardedPtr::new`](crate::PageGuardedPtr::new) takes its pages from.
+    #[cfg(feature = "region")]
+    pub page_backing: PageBacking,
+}
+
+impl Config {
+    const DEFAULT: Config = Config {
+        default_methods: Vec::new(),
+        key_source: KeySource::Time,
+        key_whitening: 0,
+        tamper_policy: TamperPolicy::Report,
+        rekey_interval: 64,
+        wipe_on_drop: false,
+        access_jitter: AccessJitter::OFF,
+        #[cfg(feature = "region")]
+        page_backing: PageBacking::Heap,
+    };
+}
+
+impl Config {
+    /// A fresh key from `key_source`, whitened with `key_whitening`.
+    #[inline(always)]
+    pub(crate) fn generate_key(&self) -> u64 {
+        self.key_source.generate() ^ self.key_whitening
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "Config");
+        let mut f = f.debug_struct("Config");
+        f.field("default_methods", &self.default_methods.len())
+            .field("key_source", &self.key_source)
+            .field("key_whitening", &format_args!("{:#x}", self.key_whitening))
+            .field("tamper_policy", &self.tamper_policy)
+            .field("rekey_interval", &self.rekey_interval)
+            .field("wipe_on_drop", &self.wipe_on_drop)
+            .field("access_jitter", &self.access_jitter);
+        #[cfg(feature = "region")]
+        f.field("page_backing", &self.page_backing);
+        f.finish()
+    }
+}
+
+static CONFIG: RwLock<Config> = RwLock::new(Config::DEFAULT);
+
+/// Set the process-wide defaults.
+///
+/// Only affects objects created afterwards; existing pointers keep the keys
+/// and methods they were created with.
+pub fn configure(config: Config) {
+    methods::set_defaults(&config.default_methods);
+    jitter::set(config.access_jitter);
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+/// Read a setting from the process-wide defaults.
+///
+/// The lock is released before returning, so nothing runs while it's held.
+pub(crate) fn read<T>(setting: impl FnOnce(&Config) -> T) -> T {
+    setting(&CONFIG.read().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// A fresh key from the configured `key_source`, whitened with `key_whitening`.
+#[inline(always)]
+pub(crate) fn generate_key() -> u64 {
+    // a custom source may itself call `configure`, so run it unlocked.
+    let (key_source, key_whitening) = read(|config| (config.key_source, config.key_whitening));
+    Config {
+        key_source,
+        key_whitening,
+        ..Config::DEFAULT
+    }
+    .generate_key()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn custom_key_source() {
+        let source = KeySource::Custom(|| 0x1234);
+
+        assert_eq!(source.generate(), 0x1234);
+        assert_eq!(Config::default().rekey_interval, 64);
+    }
+
+    #[test]
+    fn keys_are_whitened() {
+        let config = Config {
+            key_source: KeySource::Custom(|| 0x1234),
+            key_whitening: 0xFF00,
+            ..Config::default()
+        };
+
+        assert_eq!(config.generate_key(), 0xED34);
+        assert_eq!(Config::default().key_whitening, 0);
+    }
+
+    #[test]
+    fn hardware_key_source() {
+        let keys: Vec<u64> = (0..4).map(|_| KeySource::Hardware.generate()).collect();
+
+        assert!(keys.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::sealed::Sealed;
+use crate::EncryptedPtr;
+
+/// A clone-on-write pointer whose address is encrypted, the protected counterpart of `Cow<'a, T>`.
+///
+/// Starts out borrowing shared data and clones it into an owned
+/// [`EncryptedPtr`] on the first mutable access.
+pub struct EncryptedCow<'a, T: Clone> {
+    inner: Inner<'a, T>,
+}
+
+enum Inner<'a, T> {
+    Borrowed(Sealed, PhantomData<&'a T>),
+    Owned(EncryptedPtr<T>),
+}
+
+impl<'a, T: Clone> EncryptedCow<'a, T> {
+    /// Borrow `value` until the first mutation.
+    pub fn borrowed(value: &'a T) -> Self {
+        Self {
+            inner: Inner::Borrowed(Sealed::new(value as *const T as u64), PhantomData),
+        }
+    }
+
+    /// Own `value` from the start.
+    pub fn owned(value: T) -> Self {
+        Self {
+            inner: Inner::Owned(value.into()),
+        }
+    }
+
+    /// Whether this still borrows the original data.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.inner, Inner::Borrowed(..))
+    }
+
+    /// Whether this owns its data.
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+
+    /// Get a mutable reference, cloning borrowed data first.
+    pub fn to_mut(&mut self) -> &mut T {
+        if let Inner::Borrowed(..) = self.inner {
+            self.inner = Inner::Owned(T::clone(self).into());
+        }
+
+        match &mut self.inner {
+            Inner::Owned(owned) => owned.access_mut(),
+            Inner::Borrowed(..) => unreachable!(),
+        }
+    }
+
+    /// Take the data out, cloning it if it is still borrowed.
+    pub fn into_owned(self) -> T {
+        match self.inner {
+            Inner::Borrowed(..) => T::clone(&self),
+            Inner::Owned(owned) => *unsafe { Box::from_raw(owned.into_raw()) },
+        }
+    }
+}
+
+impl<'a, T: Clone> From<&'a T> for EncryptedCow<'a, T> {
+    fn from(value: &'a T) -> Self {
+        Self::borrowed(value)
+    }
+}
+
+impl<T: Clone> Deref for EncryptedCow<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        match &self.inner {
+            Inner::Borrowed(sealed, _) => unsafe { &*(sealed.get() as *const T) },
+            Inner::Owned(owned) => owned.access(),
+        }
+    }
+}
+
+impl<T: Clone> DerefMut for EncryptedCow<'_, T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.to_mut()
+    }
+}
+
+impl<T: Clone + fmt::Debug> fmt::Debug for EncryptedCow<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedCow");
+        let ciphertext = match &self.inner {
+            Inner::Borrowed(sealed, _) => sealed.ciphertext(),
+            Inner::Owned(owned) => owned.sealed.ciphertext(),
+        };
+
+        f.debug_struct("EncryptedCow")
+            .field("encrypted_value", &format!("{:#x}", ciphertext))
+            .field("owned", &self.is_owned())
+            .field("pointed_value", &self.deref())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Stats {
+        speed: u32,
+    }
+
+    #[test]
+    fn cow_promotes_on_write() {
+        let defaults = Stats { speed: 10 };
+        let mut stats = EncryptedCow::borrowed(&defaults);
+
+        assert_eq!(stats.speed, 10);
+        assert!(stats.is_borrowed());
+
+        stats.speed = 20;
+        assert!(stats.is_owned());
+        assert_eq!(defaults.speed, 10);
+        assert_eq!(stats.into_owned(), Stats { speed: 20 });
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::OnceLock;
+
+/// CPU features the accelerated methods can use, probed once per process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct CpuFeatures {
+    /// AES round instructions: AES-NI on x86-64, the crypto extension on AArch64.
+    pub aes: bool,
+    /// NEON, which the AArch64 AES instructions operate on.
+    pub neon: bool,
+}
+
+/// Features detected on the running CPU.
+///
+/// Methods such as [`AesFeistel`](crate::AesFeistel) dispatch on these, using
+/// hardware instructions where present and a bit-identical scalar fallback
+/// elsewhere, so one binary runs at its best on every machine and ciphertexts
+/// stay portable between them.
+pub fn cpu_features() -> CpuFeatures {
+    static FEATURES: OnceLock<CpuFeatures> = OnceLock::new();
+    *FEATURES.get_or_init(detect)
+}
+
+#[cold]
+fn detect() -> CpuFeatures {
+    #[cfg(target_arch = "x86_64")]
+    return CpuFeatures {
+        aes: std::is_x86_feature_detected!("aes"),
+        neon: false,
+    };
+
+    #[cfg(target_arch = "aarch64")]
+    return CpuFeatures {
+        aes: std::arch::is_aarch64_feature_detected!("aes"),
+        neon: std::arch::is_aarch64_feature_detected!("neon"),
+    };
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    CpuFeatures::default()
+}
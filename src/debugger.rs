@@ -0,0 +1,159 @@
+use std::hint::black_box;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::master;
+
+/// What to do once a debugger has been detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebuggerResponse {
+    /// Only invoke the callback.
+    #[default]
+    Notify,
+    /// Poison every protected pointer, so further access panics.
+    Poison,
+    /// Irreversibly scramble the master key, then poison.
+    Scramble,
+}
+
+/// Which indicators pointed at a debugger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DebuggerIndicators {
+    /// The OS reports the process as being debugged or traced.
+    pub attached: bool,
+    /// A tight loop took far longer than it should, as when single-stepping.
+    pub timing: bool,
+}
+
+impl DebuggerIndicators {
+    /// Whether any indicator fired.
+    pub fn any(&self) -> bool {
+        self.attached || self.timing
+    }
+}
+
+type Callback = Arc<dyn Fn(&DebuggerIndicators) + Send + Sync>;
+
+struct Settings {
+    response: DebuggerResponse,
+    timing_threshold: Duration,
+    callback: Option<Callback>,
+}
+
+static SETTINGS: RwLock<Settings> = RwLock::new(Settings {
+    response: DebuggerResponse::Notify,
+    timing_threshold: Duration::from_millis(50),
+    callback: None,
+});
+
+/// Set what happens when [`check_debugger`] detects a debugger.
+pub fn set_debugger_response(response: DebuggerResponse) {
+    SETTINGS.write().unwrap_or_else(|e| e.into_inner()).response = response;
+}
+
+/// Set how long the timing probe may take before it counts as a debugger.
+pub fn set_timing_threshold(threshold: Duration) {
+    SETTINGS.write().unwrap_or_else(|e| e.into_inner()).timing_threshold = threshold;
+}
+
+/// Set the callback invoked when [`check_debugger`] detects a debugger.
+///
+/// It runs before the configured response is applied, so it can e.g. report
+/// to a server before keys are scrambled.
+pub fn set_debugger_callback<F>(callback: F)
+where
+    F: Fn(&DebuggerIndicators) + Send + Sync + 'static,
+{
+    SETTINGS.write().unwrap_or_else(|e| e.into_inner()).callback = Some(Arc::new(callback));
+}
+
+/// Probe for a debugger without responding to it.
+pub fn detect_debugger() -> DebuggerIndicators {
+    let threshold = SETTINGS.read().unwrap_or_else(|e| e.into_inner()).timing_threshold;
+
+    DebuggerIndicators {
+        attached: attached(),
+        timing: timing_probe() > threshold,
+    }
+}
+
+/// Probe for a debugger and apply the configured response if one is found.
+///
+/// Returns the indicators so callers can log them.
+pub fn check_debugger() -> DebuggerIndicators {
+    let indicators = detect_debugger();
+    if !indicators.any() {
+        return indicators;
+    }
+
+    // the callback may itself change the settings, so don't hold the lock.
+    let (callback, response) = {
+        let settings = SETTINGS.read().unwrap_or_else(|e| e.into_inner());
+        (settings.callback.clone(), settings.response)
+    };
+    if let Some(callback) = callback {
+        callback(&indicators);
+    }
+
+    match response {
+        DebuggerResponse::Notify => {}
+        DebuggerResponse::Poison => master::poison(),
+        DebuggerResponse::Scramble => master::scramble(),
+    }
+
+    indicators
+}
+
+/// Time a short loop that takes microseconds natively but far longer when stepped.
+fn timing_probe() -> Duration {
+    let start = Instant::now();
+
+    let mut acc = black_box(0x9E3779B97F4A7C15u64);
+    for i in 0..1000u64 {
+        acc = black_box(acc.rotate_left(7) ^ i);
+    }
+    black_box(acc);
+
+    start.elapsed()
+}
+
+#[cfg(target_os = "linux")]
+fn attached() -> bool {
+    // a non-zero TracerPid means something is ptrace-attached to us.
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("TracerPid:"))
+                .and_then(|pid| pid.trim().parse::<u32>().ok())
+        })
+        .is_some_and(|pid| pid != 0)
+}
+
+#[cfg(windows)]
+fn attached() -> bool {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn IsDebuggerPresent() -> i32;
+    }
+
+    unsafe { IsDebuggerPresent() != 0 }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn attached() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn no_debugger_in_tests() {
+        // the test harness isn't run under a debugger or tracer.
+        assert_eq!(detect_debugger(), DebuggerIndicators::default());
+    }
+}
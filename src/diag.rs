@@ -0,0 +1,95 @@
+/// The code `text` is replaced with under the `opaque-diagnostics` feature: its 32-bit FNV-1a hash.
+///
+/// Opaque builds print codes as `#` and eight hex digits. Hashing the
+/// messages and type names of a normal build recovers what they stand for.
+pub const fn diagnostic_code(text: &str) -> u32 {
+    let bytes = text.as_bytes();
+    let mut hash = 0x811C_9DC5u32;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash = (hash ^ bytes[i] as u32).wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash
+}
+
+/// `code` as `#` and eight hex digits.
+#[cfg(any(feature = "opaque-diagnostics", test))]
+pub(crate) const fn render(code: u32) -> [u8; 9] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = [b'#'; 9];
+    let mut i = 0;
+    while i < 8 {
+        out[8 - i] = DIGITS[(code >> (i * 4)) as usize & 0xF];
+        i += 1;
+    }
+    out
+}
+
+/// A diagnostic string, replaced by its code under `opaque-diagnostics`.
+///
+/// The code is computed at compile time, so the original text never reaches
+/// the binary.
+#[cfg(feature = "opaque-diagnostics")]
+macro_rules! diag {
+    ($text:expr) => {{
+        const RENDERED: [u8; 9] = $crate::diag::render($crate::diag::diagnostic_code($text));
+        // only ever hex digits and `#`.
+        unsafe { ::std::str::from_utf8_unchecked(&RENDERED) }
+    }};
+}
+#[cfg(not(feature = "opaque-diagnostics"))]
+macro_rules! diag {
+    ($text:expr) => {
+        $text
+    };
+}
+pub(crate) use diag;
+
+/// `panic!`, with only the message's code under `opaque-diagnostics`.
+#[cfg(feature = "opaque-diagnostics")]
+macro_rules! diag_panic {
+    ($message:literal $(, $arg:expr)* $(,)?) => {{
+        $(let _ = &$arg;)*
+        panic!("{}", $crate::diag::diag!($message))
+    }};
+}
+#[cfg(not(feature = "opaque-diagnostics"))]
+macro_rules! diag_panic {
+    ($($arg:tt)*) => {
+        panic!($($arg)*)
+    };
+}
+pub(crate) use diag_panic;
+
+/// Start a `Debug` impl: under `opaque-diagnostics`, print only the code for `$name` and return.
+macro_rules! opaque_debug {
+    ($f:expr, $name:expr) => {
+        if cfg!(feature = "opaque-diagnostics") {
+            return $f.write_str($crate::diag::diag!($name));
+        }
+    };
+}
+pub(crate) use opaque_debug;
+
+/// `std::any::type_name`, or `"?"` under `opaque-diagnostics`.
+#[inline(always)]
+pub(crate) fn type_name<T: ?Sized>() -> &'static str {
+    if cfg!(feature = "opaque-diagnostics") {
+        "?"
+    } else {
+        std::any::type_name::<T>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn codes_render_as_hex() {
+        assert_eq!(diagnostic_code(""), 0x811C_9DC5);
+        assert_eq!(&render(0x0012_ABCF), b"#0012abcf");
+    }
+}
@@ -0,0 +1,138 @@
+use std::alloc::{self, Layout};
+use std::collections::VecDeque;
+use std::ptr;
+use std::sync::Mutex;
+
+use crate::wipe;
+
+/// How an [`EncryptedPtr`](crate::EncryptedPtr) tears down its pointee.
+///
+/// Chosen per pointer type, e.g. `EncryptedPtr<Secret, Quarantine>`. The
+/// pointer has already checked for poisoning and unregistered itself by the
+/// time the policy runs. Implement it to notify, use a foreign allocator, etc.
+pub trait DropPolicy {
+    /// Drop and release the pointee at `ptr`.
+    ///
+    /// # Safety
+    /// `ptr` points to a live `T` owned by the caller, allocated by the
+    /// global allocator with `Layout::new::<T>()` unless the policy says
+    /// otherwise. It must not be used afterwards.
+    unsafe fn drop_pointee<T>(ptr: *mut T);
+}
+
+/// Drop and deallocate, like `Box`; wipes first if [`Config::wipe_on_drop`](crate::Config::wipe_on_drop) is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dealloc;
+
+impl DropPolicy for Dealloc {
+    #[inline(always)]
+    unsafe fn drop_pointee<T>(ptr: *mut T) {
+        ptr::drop_in_place(ptr);
+        if wipe::enabled() {
+            wipe_memory(ptr);
+        }
+        dealloc(ptr);
+    }
+}
+
+/// Drop, always wipe with random bytes, then deallocate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Wipe;
+
+impl DropPolicy for Wipe {
+    unsafe fn drop_pointee<T>(ptr: *mut T) {
+        ptr::drop_in_place(ptr);
+        wipe_memory(ptr);
+        dealloc(ptr);
+    }
+}
+
+/// Drop and wipe, but hold the memory back from the allocator for a while.
+///
+/// The last [`Quarantine::CAPACITY`] freed objects are kept allocated, so a
+/// dangling copy of the address can't be used to reach whatever the
+/// allocator would have put there next.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quarantine;
+
+impl Quarantine {
+    /// How many freed objects are held back at once.
+    pub const CAPACITY: usize = 64;
+}
+
+static QUARANTINE: Mutex<VecDeque<(usize, Layout)>> = Mutex::new(VecDeque::new());
+
+impl DropPolicy for Quarantine {
+    unsafe fn drop_pointee<T>(ptr: *mut T) {
+        ptr::drop_in_place(ptr);
+        wipe_memory(ptr);
+
+        if size_of::<T>() == 0 {
+            return;
+        }
+
+        let mut quarantine = QUARANTINE.lock().unwrap_or_else(|e| e.into_inner());
+        quarantine.push_back((ptr as usize, Layout::new::<T>()));
+        if quarantine.len() > Self::CAPACITY {
+            let (oldest, layout) = quarantine.pop_front().unwrap();
+            alloc::dealloc(oldest as *mut u8, layout);
+        }
+    }
+}
+
+/// Run the destructor but leave the memory alone, for pointees from a foreign allocator.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DropOnly;
+
+impl DropPolicy for DropOnly {
+    unsafe fn drop_pointee<T>(ptr: *mut T) {
+        ptr::drop_in_place(ptr);
+    }
+}
+
+#[inline(always)]
+unsafe fn wipe_memory<T>(ptr: *mut T) {
+    wipe::wipe(ptr as *mut u8, size_of::<T>());
+}
+
+#[inline(always)]
+unsafe fn dealloc<T>(ptr: *mut T) {
+    // zero-sized pointees were never allocated.
+    if size_of::<T>() != 0 {
+        alloc::dealloc(ptr as *mut u8, Layout::new::<T>());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EncryptedPtr;
+    use pretty_assertions::assert_eq;
+    use std::rc::Rc;
+
+    #[test]
+    fn quarantine_holds_memory() {
+        let shared = Rc::new(100u32);
+        let ptr: EncryptedPtr<Rc<u32>, Quarantine> = shared.clone().into();
+        let addr = unsafe { ptr.as_ptr() } as usize;
+
+        drop(ptr);
+        assert_eq!(Rc::strong_count(&shared), 1);
+
+        let quarantine = QUARANTINE.lock().unwrap();
+        assert!(quarantine.iter().any(|&(held, _)| held == addr));
+    }
+
+    #[test]
+    fn drop_only_keeps_memory() {
+        let shared = Rc::new(100u32);
+        let raw = Box::into_raw(Box::new(shared.clone()));
+        let ptr = EncryptedPtr::new(raw).with_policy::<DropOnly>();
+
+        drop(ptr);
+        assert_eq!(Rc::strong_count(&shared), 1);
+
+        // the memory is still ours to free.
+        unsafe { alloc::dealloc(raw as *mut u8, Layout::new::<Rc<u32>>()) };
+    }
+}
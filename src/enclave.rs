@@ -0,0 +1,122 @@
+use std::fmt;
+
+use crate::{Encrypt, Speck};
+
+/// Key operations performed inside a trusted execution environment, e.g. as ECALLs into an SGX enclave.
+///
+/// The backend combines each per-pointer key with a root key that never
+/// leaves the enclave, so a full dump of the process holds per-pointer keys
+/// but nothing that decrypts them. Plug a backend in with [`Enclave`].
+pub trait EnclaveBackend {
+    /// Encrypt `data` under `key` and the enclave's root key.
+    fn seal(&self, data: u64, key: u64) -> u64;
+    /// Undo [`EnclaveBackend::seal`] for the same `key`.
+    fn unseal(&self, data: u64, key: u64) -> u64;
+}
+
+/// An [`Encrypt`] method that runs entirely inside an [`EnclaveBackend`].
+///
+/// Every dereference crosses into the enclave, so this trades a lot of
+/// latency for keeping the root key out of process memory.
+#[derive(Clone, Copy, Default)]
+pub struct Enclave<B> {
+    backend: B,
+}
+
+impl<B: EnclaveBackend> Enclave<B> {
+    /// Route encryption through `backend`.
+    pub const fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    /// The backend in use.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+}
+
+impl<B: EnclaveBackend> Encrypt for Enclave<B> {
+    #[inline(always)]
+    fn encrypt(&self, data: u64, key: u64) -> u64 {
+        self.backend.seal(data, key)
+    }
+
+    #[inline(always)]
+    fn decrypt(&self, data: u64, key: u64) -> u64 {
+        self.backend.unseal(data, key)
+    }
+}
+
+impl<B> fmt::Debug for Enclave<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "Enclave");
+        f.debug_struct("Enclave").finish_non_exhaustive()
+    }
+}
+
+/// An [`EnclaveBackend`] that keeps its root key in ordinary memory.
+///
+/// A stand-in for tests and platforms without a TEE: the interface is the
+/// same, but the root key is as exposed as any other.
+#[derive(Clone, Copy)]
+pub struct SoftwareEnclave {
+    root: u64,
+    cipher: Speck,
+}
+
+impl SoftwareEnclave {
+    /// Create a backend with a random root key.
+    pub fn new() -> Self {
+        Self {
+            root: rand::random(),
+            cipher: Speck::new(),
+        }
+    }
+}
+
+impl Default for SoftwareEnclave {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnclaveBackend for SoftwareEnclave {
+    fn seal(&self, data: u64, key: u64) -> u64 {
+        self.cipher.encrypt(data, key ^ self.root)
+    }
+
+    fn unseal(&self, data: u64, key: u64) -> u64 {
+        self.cipher.decrypt(data, key ^ self.root)
+    }
+}
+
+impl fmt::Debug for SoftwareEnclave {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "SoftwareEnclave");
+        f.debug_struct("SoftwareEnclave").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EncryptedPtr;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn enclave_method_round_trip() {
+        let enclave = Enclave::new(SoftwareEnclave::new());
+        let player = EncryptedPtr::with_method(Box::into_raw(Box::new(100u32)), enclave);
+
+        assert_eq!(*player, 100);
+    }
+
+    #[test]
+    fn root_key_is_required() {
+        let (a, b) = (SoftwareEnclave::new(), SoftwareEnclave::new());
+        let sealed = a.seal(0xDEADBEEF, 0x1234);
+
+        assert_eq!(a.unseal(sealed, 0x1234), 0xDEADBEEF);
+        assert!(b.unseal(sealed, 0x1234) != 0xDEADBEEF);
+    }
+}
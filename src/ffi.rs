@@ -0,0 +1,251 @@
+use std::ffi::c_void;
+use std::ptr;
+
+use crate::sealed::{self, Sealed};
+use crate::{master, Encrypt};
+
+/// The call succeeded.
+pub const PG_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const PG_ERR_NULL: i32 = -1;
+/// The method index doesn't name a built-in method.
+pub const PG_ERR_METHOD: i32 = -2;
+
+/// Opaque handle to an encrypted pointer, owned by C code.
+pub struct PgPtr {
+    sealed: Sealed,
+    free: Option<unsafe extern "C" fn(*mut c_void)>,
+}
+
+/// Fixed-layout encrypted pointer that can be embedded in C structs.
+///
+/// Unlike [`PgPtr`] this holds no heap allocation, so it can be copied and
+/// passed across the FFI boundary by value. It never owns its pointee.
+/// Being plain data, handles don't keep their master key version from being
+/// forgotten: after [`forget_retired_keys`](crate::forget_retired_keys) a
+/// handle from before a rotation decrypts to null, so make them afresh.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PgHandle {
+    /// The encrypted pointer.
+    pub ciphertext: u64,
+    /// The key it was encrypted under.
+    pub key: u64,
+    /// Index of the built-in method used.
+    pub method: u32,
+    /// The master key version it was encrypted under.
+    pub version: u32,
+}
+
+impl PgHandle {
+    /// Encrypt `ptr` under a fresh key and random built-in method.
+    pub fn new<T>(ptr: *mut T) -> Self {
+        let key = sealed::generate_key();
+        let method = rand::random_range(0..sealed::BUILTIN_METHODS) as u32;
+        let cipher = sealed::builtin_method(method as usize).unwrap();
+        let (version, master) = master::current();
+
+        Self {
+            ciphertext: cipher.encrypt(ptr as u64, key ^ master),
+            key,
+            method,
+            version: version as u32,
+        }
+    }
+
+    /// Decrypt the pointer, or return null if the handle is malformed, its key version forgotten or the process poisoned.
+    pub fn get<T>(&self) -> *mut T {
+        // never panic, this is called straight from C.
+        if master::is_poisoned() {
+            return ptr::null_mut();
+        }
+
+        let master = u8::try_from(self.version)
+            .ok()
+            .and_then(master::for_version);
+        match (sealed::builtin_method(self.method as usize), master) {
+            (Some(cipher), Some(master)) => {
+                cipher.decrypt(self.ciphertext, self.key ^ master) as *mut T
+            }
+            _ => ptr::null_mut(),
+        }
+    }
+}
+
+/// Create a handle for `ptr`.
+#[no_mangle]
+pub extern "C" fn pg_handle_new(ptr: *mut c_void) -> PgHandle {
+    PgHandle::new(ptr)
+}
+
+/// Decrypt the pointer held by `handle`, or return null if it is malformed.
+#[no_mangle]
+pub extern "C" fn pg_handle_deref(handle: PgHandle) -> *mut c_void {
+    handle.get()
+}
+
+/// Encrypt `data` under `key` with built-in method `method`, writing the result to `out`.
+///
+/// # Safety
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pg_encrypt(data: u64, key: u64, method: u32, out: *mut u64) -> i32 {
+    if out.is_null() {
+        return PG_ERR_NULL;
+    }
+
+    match sealed::builtin_method(method as usize) {
+        Some(method) => {
+            *out = method.encrypt(data, key);
+            PG_OK
+        }
+        None => PG_ERR_METHOD,
+    }
+}
+
+/// Decrypt `data` under `key` with built-in method `method`, writing the result to `out`.
+///
+/// # Safety
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pg_decrypt(data: u64, key: u64, method: u32, out: *mut u64) -> i32 {
+    if out.is_null() {
+        return PG_ERR_NULL;
+    }
+
+    match sealed::builtin_method(method as usize) {
+        Some(method) => {
+            *out = method.decrypt(data, key);
+            PG_OK
+        }
+        None => PG_ERR_METHOD,
+    }
+}
+
+/// Wrap `ptr` in a new encrypted handle, or return null if `ptr` is null.
+///
+/// `free` is called with the pointer when the handle is freed; pass null to
+/// keep ownership of the pointee on the C side.
+#[no_mangle]
+pub extern "C" fn pg_ptr_new(
+    ptr: *mut c_void,
+    free: Option<unsafe extern "C" fn(*mut c_void)>,
+) -> *mut PgPtr {
+    if ptr.is_null() {
+        return ptr::null_mut();
+    }
+
+    Box::into_raw(Box::new(PgPtr {
+        sealed: Sealed::new(ptr as u64),
+        free,
+    }))
+}
+
+/// Decrypt the pointer held by `handle`, or return null if `handle` is null or can't be decrypted.
+///
+/// That is once the process is poisoned, or when the pointer fails its MAC.
+///
+/// # Safety
+/// `handle` must be null or a live handle from [`pg_ptr_new`].
+#[no_mangle]
+pub unsafe extern "C" fn pg_ptr_deref(handle: *const PgPtr) -> *mut c_void {
+    match handle.as_ref().and_then(|handle| handle.sealed.try_get()) {
+        Some(addr) => addr as *mut c_void,
+        None => ptr::null_mut(),
+    }
+}
+
+/// Free `handle`, calling its free function on the pointee if it has one.
+///
+/// A pointee that can't be decrypted, see [`pg_ptr_deref`], is leaked instead.
+///
+/// # Safety
+/// `handle` must be null or a live handle from [`pg_ptr_new`], and must not
+/// be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pg_ptr_free(handle: *mut PgPtr) {
+    if handle.is_null() {
+        return;
+    }
+
+    let handle = Box::from_raw(handle);
+    if let (Some(free), Some(addr)) = (handle.free, handle.sealed.try_get()) {
+        free(addr as *mut c_void);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ffi_round_trip() {
+        let mut encrypted = 0;
+        let mut decrypted = 0;
+
+        unsafe {
+            assert_eq!(pg_encrypt(0xFEDCBA09, 0x1234, 1, &mut encrypted), PG_OK);
+            assert_eq!(pg_decrypt(encrypted, 0x1234, 1, &mut decrypted), PG_OK);
+            assert_eq!(pg_encrypt(0, 0, 99, &mut encrypted), PG_ERR_METHOD);
+        }
+
+        assert_eq!(decrypted, 0xFEDCBA09);
+    }
+
+    #[test]
+    fn ffi_handle_by_value() {
+        let mut value = 100u32;
+        let handle = pg_handle_new(&mut value as *mut u32 as *mut c_void);
+
+        // handles are plain data, copies decrypt just the same.
+        let copy = handle;
+
+        assert_eq!(size_of::<PgHandle>(), 24);
+        assert_eq!(pg_handle_deref(copy), &mut value as *mut u32 as *mut c_void);
+    }
+
+    #[test]
+    fn ffi_handle() {
+        unsafe extern "C" fn free_u32(ptr: *mut c_void) {
+            drop(Box::from_raw(ptr as *mut u32));
+        }
+
+        let value = Box::into_raw(Box::new(100u32));
+
+        unsafe {
+            let handle = pg_ptr_new(value as *mut c_void, Some(free_u32));
+            assert_eq!(*(pg_ptr_deref(handle) as *mut u32), 100);
+            pg_ptr_free(handle);
+
+            assert!(pg_ptr_new(ptr::null_mut(), None).is_null());
+        }
+    }
+
+    #[test]
+    fn poisoned_deref_returns_null() {
+        const CHILD: &str = "POINTERGUARD_POISONED_CHILD";
+
+        // poisoning is process-wide, so do it in a child running only this test.
+        if std::env::var_os(CHILD).is_none() {
+            let status = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["--exact", "ffi::tests::poisoned_deref_returns_null"])
+                .env(CHILD, "1")
+                .status()
+                .unwrap();
+            assert!(status.success());
+            return;
+        }
+
+        let mut value = 100u32;
+        let handle = pg_handle_new(&mut value as *mut u32 as *mut c_void);
+        let owned = pg_ptr_new(&mut value as *mut u32 as *mut c_void, None);
+        master::poison();
+
+        assert!(pg_handle_deref(handle).is_null());
+        unsafe {
+            assert!(pg_ptr_deref(owned).is_null());
+            pg_ptr_free(owned);
+        }
+    }
+}
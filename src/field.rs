@@ -0,0 +1,169 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::diag::diag;
+use crate::sealed::Sealed;
+use crate::{DropPolicy, EncryptedOffset, EncryptedPtr};
+
+/// Encrypt the offset of `field` within the `T` at `base`.
+fn seal_offset<T, U>(base: *const T, field: *const U) -> Sealed {
+    let offset = (field as usize).wrapping_sub(base as usize);
+    assert!(
+        offset <= size_of::<T>() && size_of::<U>() <= size_of::<T>() - offset,
+        "{}",
+        diag!("projected reference must point into the pointee")
+    );
+
+    Sealed::new(offset as u64)
+}
+
+#[inline(always)]
+fn field_ptr<U>(base: &Sealed, offset: &Sealed) -> *mut U {
+    (base.get() + offset.get()) as *mut U
+}
+
+impl<T, P: DropPolicy> EncryptedPtr<T, P> {
+    /// Project to a field, e.g. `player.map(|p| &p.health)`, without exposing the whole object's address.
+    ///
+    /// The result stores only the field's encrypted offset and decrypts both
+    /// it and the base address on every access.
+    ///
+    /// # Panics
+    /// If `f` returns a reference outside the pointee itself.
+    pub fn map<U>(&self, f: impl FnOnce(&T) -> &U) -> EncryptedField<'_, U> {
+        let offset = seal_offset(self.decrypt_ptr(), f(self.access()));
+
+        EncryptedField {
+            base: &self.sealed,
+            offset,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Project to the field at `offset`, like [`EncryptedPtr::map`] but without a closure.
+    pub fn project<U>(&self, offset: EncryptedOffset<T, U>) -> EncryptedField<'_, U> {
+        EncryptedField {
+            base: &self.sealed,
+            offset: Sealed::new(offset.offset() as u64),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Mutable counterpart of [`EncryptedPtr::map`].
+    ///
+    /// # Panics
+    /// If `f` returns a reference outside the pointee itself.
+    pub fn map_mut<U>(&mut self, f: impl FnOnce(&mut T) -> &mut U) -> EncryptedFieldMut<'_, U> {
+        let base = self.decrypt_ptr();
+        let offset = seal_offset(base, f(unsafe { &mut *base }));
+
+        EncryptedFieldMut {
+            base: &self.sealed,
+            offset,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A protected reference to one field of an [`EncryptedPtr`]'s pointee, see [`EncryptedPtr::map`].
+pub struct EncryptedField<'a, U> {
+    base: &'a Sealed,
+    offset: Sealed,
+    _marker: PhantomData<&'a U>,
+}
+
+impl<U> Deref for EncryptedField<'_, U> {
+    type Target = U;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*field_ptr(self.base, &self.offset) }
+    }
+}
+
+impl<U: fmt::Debug> fmt::Debug for EncryptedField<'_, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedField");
+        f.debug_struct("EncryptedField")
+            .field(
+                "encrypted_offset",
+                &format!("{:#x}", self.offset.ciphertext()),
+            )
+            .field("pointed_value", &self.deref())
+            .finish()
+    }
+}
+
+/// A protected mutable reference to one field of an [`EncryptedPtr`]'s pointee, see [`EncryptedPtr::map_mut`].
+pub struct EncryptedFieldMut<'a, U> {
+    // shared, but the `&mut` borrow of the pointer it came from makes it exclusive.
+    base: &'a Sealed,
+    offset: Sealed,
+    _marker: PhantomData<&'a mut U>,
+}
+
+impl<U> Deref for EncryptedFieldMut<'_, U> {
+    type Target = U;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*field_ptr(self.base, &self.offset) }
+    }
+}
+
+impl<U> DerefMut for EncryptedFieldMut<'_, U> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *field_ptr(self.base, &self.offset) }
+    }
+}
+
+impl<U: fmt::Debug> fmt::Debug for EncryptedFieldMut<'_, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedFieldMut");
+        f.debug_struct("EncryptedFieldMut")
+            .field(
+                "encrypted_offset",
+                &format!("{:#x}", self.offset.ciphertext()),
+            )
+            .field("pointed_value", &self.deref())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    struct Player {
+        name: [u8; 8],
+        health: u32,
+    }
+
+    #[test]
+    fn map_projects_field() {
+        let mut player: EncryptedPtr<Player> = Player {
+            name: *b"player_1",
+            health: 100,
+        }
+        .into();
+
+        assert_eq!(*player.map(|p| &p.health), 100);
+        *player.map_mut(|p| &mut p.health) -= 25;
+
+        assert_eq!(player.health, 75);
+        assert_eq!(&*player.map(|p| &p.name), b"player_1");
+    }
+
+    #[cfg(not(feature = "opaque-diagnostics"))]
+    #[test]
+    #[should_panic(expected = "projected reference must point into the pointee")]
+    fn map_rejects_outside_reference() {
+        static OTHER: u32 = 0;
+        let player: EncryptedPtr<u32> = 100.into();
+
+        player.map(|_| &OTHER);
+    }
+}
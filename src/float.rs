@@ -0,0 +1,66 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
+};
+
+use crate::int::{binary_ops, encrypted_number, Word};
+
+/// Float wrappers, stored as their raw bits so every NaN payload and signed zero round-trips.
+macro_rules! encrypted_float {
+    ($($name:ident $ty:ident $bits:ident),*) => {$(
+        encrypted_number!(
+            #[doc = concat!("An `", stringify!($ty), "` stored encrypted, re-encrypted under a fresh key on every write.")]
+            ///
+            /// Encrypts the value's bit pattern, so NaNs, infinities and `-0.0`
+            /// come back exactly as stored. Arithmetic, comparison and
+            /// formatting decrypt transiently and follow IEEE semantics like
+            /// the plain float, for positions, speeds and cooldown timers.
+            $name, $ty, |value: $ty| value.to_bits() as u64, |bits: u64| <$ty>::from_bits(bits as $bits)
+        );
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                self.get().partial_cmp(&other.get())
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+
+            fn neg(self) -> $name {
+                $name::new(-self.get())
+            }
+        }
+    )*};
+}
+
+encrypted_float!(EncryptedF32 f32 u32, EncryptedF64 f64 u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn arithmetic_and_comparisons() {
+        let mut speed = EncryptedF32::new(2.5);
+        speed *= 2.0;
+        speed -= EncryptedF32::new(1.0);
+
+        assert_eq!(speed.get(), 4.0);
+        assert!(speed > 3.5 && speed < EncryptedF32::new(4.5));
+        assert_eq!((-EncryptedF64::new(1.5) + 0.25).get(), -1.25);
+    }
+
+    #[test]
+    fn special_values_round_trip() {
+        let nan = f64::from_bits(0x7FF8_0000_DEAD_BEEF);
+        let stored = EncryptedF64::new(nan);
+
+        assert_eq!(stored.get().to_bits(), nan.to_bits());
+        assert!(stored != stored.clone());
+        assert_eq!(EncryptedF32::new(-0.0).get().to_bits(), (-0.0f32).to_bits());
+        assert_eq!(EncryptedF32::new(f32::NEG_INFINITY), f32::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(not(passthrough))]
+use crate::methods;
+use crate::sealed::{self, Sealed};
+use crate::{
+    AesFeistel, Ascon, Encrypt, MethodA, MethodB, MethodC, Prince, SipFeistel, Speck, Xtea,
+};
+
+/// Number of ciphers reachable through [`cipher`].
+pub const CIPHERS: usize = 9;
+
+/// The built-in cipher at `index`, for picking one from fuzz input.
+pub fn cipher(index: usize) -> Option<&'static dyn Encrypt> {
+    static SPECK: Speck = Speck::new();
+    static XTEA: Xtea = Xtea::new();
+    static PRINCE: Prince = Prince::new();
+    static SIP: SipFeistel = SipFeistel::new();
+    static ASCON: Ascon = Ascon::new();
+    static AES: AesFeistel = AesFeistel::new();
+
+    Some(match index {
+        0 => &MethodA,
+        1 => &MethodB,
+        2 => &MethodC,
+        3 => &SPECK,
+        4 => &XTEA,
+        5 => &PRINCE,
+        6 => &SIP,
+        7 => &ASCON,
+        8 => &AES,
+        _ => return None,
+    })
+}
+
+/// Whether cipher `index` decrypts `data` back after encrypting it under `key`.
+pub fn cipher_round_trip(index: usize, data: u64, key: u64) -> bool {
+    cipher(index % CIPHERS)
+        .is_some_and(|cipher| cipher.decrypt(cipher.encrypt(data, key), key) == data)
+}
+
+/// A pointer key word that can't be decoded, see [`validate_word`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidWord {
+    /// A built-in schedule rotating by 64 bits or more.
+    Rotation(u8),
+    /// A shared method index nothing was registered at.
+    Shared(u8),
+    /// An owned method, whose box can't be checked without dereferencing it.
+    Owned,
+}
+
+/// Check that `word` is a key word [`unseal`] can safely decode.
+#[cfg(not(passthrough))]
+pub fn validate_word(word: u64) -> Result<(), InvalidWord> {
+    match word as u8 {
+        sealed::TAG_OWNED => Err(InvalidWord::Owned),
+        tag if tag & sealed::TAG_KEYED == 0 => match tag < 64 {
+            true => Ok(()),
+            false => Err(InvalidWord::Rotation(tag)),
+        },
+        tag => {
+            let index = tag & !sealed::TAG_KEYED;
+            match methods::shared_methods().any(|(registered, _)| registered == index) {
+                true => Ok(()),
+                false => Err(InvalidWord::Shared(index)),
+            }
+        }
+    }
+}
+
+/// Check that `word` is a key word [`unseal`] can safely decode.
+#[cfg(passthrough)]
+pub fn validate_word(_word: u64) -> Result<(), InvalidWord> {
+    Ok(())
+}
+
+/// Encrypt `addr` like a new pointer would, with built-in method `method % 3`.
+///
+/// Returns the ciphertext and key word. With the `mac` feature only the low
+/// 48 bits of `addr` are kept, as only those fit alongside the MAC.
+pub fn seal(addr: u64, method: u8) -> (u64, u64) {
+    let addr = user_address(addr);
+    let method = sealed::builtin_method(method as usize % sealed::BUILTIN_METHODS).unwrap();
+    Sealed::encode(addr, method)
+}
+
+/// Decrypt a ciphertext and key word, or `None` if the word is invalid or the MAC fails.
+pub fn unseal(ciphertext: u64, word: u64) -> Option<u64> {
+    validate_word(word).ok()?;
+    Sealed::try_decode(ciphertext, word)
+}
+
+/// Whether [`seal`] followed by [`unseal`] gives back `addr`.
+pub fn sealed_round_trip(addr: u64, method: u8) -> bool {
+    let (ciphertext, word) = seal(addr, method);
+    let round_trips =
+        validate_word(word).is_ok() && unseal(ciphertext, word) == Some(user_address(addr));
+    Sealed::release(word);
+    round_trips
+}
+
+/// `addr` as a pointer can hold it, see [`seal`].
+fn user_address(addr: u64) -> u64 {
+    match cfg!(all(feature = "mac", not(passthrough))) {
+        true => addr & ((1 << 48) - 1),
+        false => addr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn round_trips_hold_for_random_inputs() {
+        for _ in 0..1000 {
+            let (index, data, key) = (
+                rand::random_range(0..CIPHERS),
+                rand::random(),
+                rand::random(),
+            );
+
+            assert!(cipher_round_trip(index, data, key));
+            let (ciphertext, word) = seal(data, rand::random());
+            assert_eq!(unseal(ciphertext, word), Some(user_address(data)));
+        }
+    }
+
+    #[cfg(not(passthrough))]
+    #[test]
+    fn invalid_words_are_rejected() {
+        assert_eq!(validate_word(0x40), Err(InvalidWord::Rotation(0x40)));
+        assert_eq!(validate_word(0xFF), Err(InvalidWord::Owned));
+        assert_eq!(unseal(0, 0x80 | 126), None);
+    }
+}
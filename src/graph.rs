@@ -0,0 +1,349 @@
+use std::fmt;
+
+use crate::diag::type_name;
+use crate::master::MaskedKey;
+use crate::tamper::{self, TamperEvent, TamperKind};
+use crate::{
+    aes, DropPolicy, EncryptedF32, EncryptedF64, EncryptedI16, EncryptedI32, EncryptedI64,
+    EncryptedI8, EncryptedIsize, EncryptedPtr, EncryptedString, EncryptedU16, EncryptedU32,
+    EncryptedU64, EncryptedU8, EncryptedUsize, EncryptedVec, SendEncryptedPtr,
+};
+
+/// A value whose contents, and everything it points to, can be sealed by [`seal_graph`].
+///
+/// `#[derive(SealGraph)]` visits every field in order; mark a field
+/// `#[seal_graph(skip)]` to leave it out, e.g. a cache that changes between
+/// checks. Protected pointers visit their pointee, so the seal covers the
+/// whole graph reachable from the root. A pointee reachable along two paths
+/// is visited once per path, and cyclic graphs never finish.
+pub trait SealGraph {
+    /// Feed this value's contents to `hasher`.
+    fn visit(&self, hasher: &mut GraphHasher);
+}
+
+/// The keyed MAC an object graph is fed to while [`SealGraph::visit`] walks it.
+pub struct GraphHasher {
+    key: u64,
+    state: u64,
+    words: u64,
+}
+
+impl GraphHasher {
+    fn new(key: u64) -> Self {
+        Self {
+            key,
+            state: 0,
+            words: 0,
+        }
+    }
+
+    /// Absorb `bytes`, prefixed by their length so adjacent values can't run together.
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.write_u64(bytes.len() as u64);
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.write_u64(u64::from_le_bytes(word));
+        }
+    }
+
+    /// Absorb a single word, e.g. a length or an enum discriminant.
+    pub fn write_u64(&mut self, word: u64) {
+        self.state = aes::mac(self.key ^ self.state, word);
+        self.words += 1;
+    }
+
+    fn finish(&self) -> u64 {
+        aes::mac(self.key, self.state ^ self.words.rotate_left(32))
+    }
+}
+
+impl fmt::Debug for GraphHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "GraphHasher");
+        f.debug_struct("GraphHasher")
+            .field("words", &self.words)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Compute a MAC over every protected value reachable from `root`, to [`verify`](GraphSeal::verify) later.
+///
+/// For a check before critical operations such as submitting a score: any
+/// out-of-band write anywhere in the graph, including inside encrypted
+/// pointees, fails verification. Writes made on purpose have to be followed
+/// by a [`reseal`](GraphSeal::reseal).
+///
+/// ```ignore
+/// #[derive(pointerguard::SealGraph)]
+/// struct Player {
+///     score: pointerguard::EncryptedU32,
+///     inventory: pointerguard::EncryptedVec<u16>,
+/// }
+///
+/// let seal = pointerguard::seal_graph(&player);
+/// // ...
+/// assert!(seal.verify(&player));
+/// ```
+pub fn seal_graph<N: SealGraph + ?Sized>(root: &N) -> GraphSeal {
+    let key = rand::random();
+    GraphSeal {
+        tag: graph_mac(key, root),
+        key: MaskedKey::new(key),
+    }
+}
+
+fn graph_mac<N: SealGraph + ?Sized>(key: u64, root: &N) -> u64 {
+    let mut hasher = GraphHasher::new(key);
+    root.visit(&mut hasher);
+    hasher.finish()
+}
+
+/// The MAC of an object graph, from [`seal_graph`].
+pub struct GraphSeal {
+    /// The MAC key, XORed with the master key.
+    key: MaskedKey,
+    tag: u64,
+}
+
+impl GraphSeal {
+    /// Whether the graph reachable from `root` is unchanged since it was sealed.
+    ///
+    /// A mismatch is reported as [`TamperKind::GraphMismatch`].
+    pub fn verify<N: SealGraph + ?Sized>(&self, root: &N) -> bool {
+        let matches = graph_mac(self.key.get(), root) == self.tag;
+        if !matches {
+            tamper::report(TamperEvent::new(
+                TamperKind::GraphMismatch,
+                type_name::<N>(),
+            ));
+        }
+        matches
+    }
+
+    /// Seal the graph again under a fresh key, after writing to it on purpose.
+    pub fn reseal<N: SealGraph + ?Sized>(&mut self, root: &N) {
+        *self = seal_graph(root);
+    }
+}
+
+impl fmt::Debug for GraphSeal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "GraphSeal");
+        f.debug_struct("GraphSeal").finish_non_exhaustive()
+    }
+}
+
+/// Plain numbers, by their little-endian bytes.
+macro_rules! seal_bytes {
+    ($($ty:ty),*) => {$(
+        impl SealGraph for $ty {
+            fn visit(&self, hasher: &mut GraphHasher) {
+                hasher.write(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+seal_bytes!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+/// Encrypted numbers, decrypted transiently.
+macro_rules! seal_encrypted {
+    ($($ty:ty),*) => {$(
+        impl SealGraph for $ty {
+            fn visit(&self, hasher: &mut GraphHasher) {
+                hasher.write(&self.get().to_le_bytes());
+            }
+        }
+    )*};
+}
+
+seal_encrypted!(
+    EncryptedU8,
+    EncryptedU16,
+    EncryptedU32,
+    EncryptedU64,
+    EncryptedUsize,
+    EncryptedI8,
+    EncryptedI16,
+    EncryptedI32,
+    EncryptedI64,
+    EncryptedIsize,
+    EncryptedF32,
+    EncryptedF64
+);
+
+impl SealGraph for bool {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        hasher.write(&[*self as u8]);
+    }
+}
+
+impl SealGraph for char {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        (*self as u32).visit(hasher);
+    }
+}
+
+impl SealGraph for () {
+    fn visit(&self, _hasher: &mut GraphHasher) {}
+}
+
+impl SealGraph for str {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        hasher.write(self.as_bytes());
+    }
+}
+
+impl SealGraph for String {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        self.as_str().visit(hasher);
+    }
+}
+
+impl<T: SealGraph> SealGraph for [T] {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        hasher.write_u64(self.len() as u64);
+        for item in self {
+            item.visit(hasher);
+        }
+    }
+}
+
+impl<T: SealGraph, const N: usize> SealGraph for [T; N] {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        self[..].visit(hasher);
+    }
+}
+
+impl<T: SealGraph> SealGraph for Vec<T> {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        self[..].visit(hasher);
+    }
+}
+
+impl<T: SealGraph + ?Sized> SealGraph for &T {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        (**self).visit(hasher);
+    }
+}
+
+impl<T: SealGraph + ?Sized> SealGraph for Box<T> {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        (**self).visit(hasher);
+    }
+}
+
+impl<T: SealGraph> SealGraph for Option<T> {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        hasher.write_u64(self.is_some() as u64);
+        if let Some(value) = self {
+            value.visit(hasher);
+        }
+    }
+}
+
+impl<T: SealGraph, P: DropPolicy> SealGraph for EncryptedPtr<T, P> {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        self.access().visit(hasher);
+    }
+}
+
+impl<T: SealGraph, P: DropPolicy> SealGraph for SendEncryptedPtr<T, P> {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        self.access().visit(hasher);
+    }
+}
+
+impl<T: SealGraph> SealGraph for EncryptedVec<T> {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        (**self).visit(hasher);
+    }
+}
+
+impl SealGraph for EncryptedString {
+    fn visit(&self, hasher: &mut GraphHasher) {
+        (**self).visit(hasher);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    struct Player {
+        score: EncryptedU32,
+        inventory: EncryptedVec<u16>,
+        pet: Option<EncryptedPtr<(u8, String)>>,
+    }
+
+    impl SealGraph for (u8, String) {
+        fn visit(&self, hasher: &mut GraphHasher) {
+            self.0.visit(hasher);
+            self.1.visit(hasher);
+        }
+    }
+
+    impl SealGraph for Player {
+        fn visit(&self, hasher: &mut GraphHasher) {
+            self.score.visit(hasher);
+            self.inventory.visit(hasher);
+            self.pet.visit(hasher);
+        }
+    }
+
+    fn player() -> Player {
+        Player {
+            score: EncryptedU32::new(1200),
+            inventory: vec![3, 5, 8].into(),
+            pet: Some(EncryptedPtr::from((4, "rex".to_string()))),
+        }
+    }
+
+    #[test]
+    fn out_of_band_writes_anywhere_fail_verification() {
+        let mut player = player();
+        let mut seal = seal_graph(&player);
+        assert!(seal.verify(&player));
+
+        // a write through the pointee, as a memory editor would, is caught.
+        let pet = player.pet.as_ref().unwrap();
+        unsafe { (*pet.as_ptr()).0 = 5 };
+        assert!(!seal.verify(&player));
+
+        seal.reseal(&player);
+        assert!(seal.verify(&player));
+        player.score += 1;
+        assert!(!seal.verify(&player));
+    }
+
+    #[test]
+    fn lengths_keep_adjacent_values_apart() {
+        let key = rand::random();
+        assert!(graph_mac(key, &["ab", "c"]) != graph_mac(key, &["a", "bc"]));
+        assert_eq!(graph_mac(key, &player()), graph_mac(key, &player()));
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn derived_visitor() {
+        #[derive(crate::SealGraph)]
+        enum Pickup {
+            Coins(EncryptedU32),
+            Item {
+                id: u16,
+                #[seal_graph(skip)]
+                _glow: f32,
+            },
+        }
+
+        let mut pickup = Pickup::Item { id: 7, _glow: 0.5 };
+        let seal = seal_graph(&pickup);
+        if let Pickup::Item { _glow, .. } = &mut pickup {
+            *_glow = 1.0;
+        }
+        assert!(seal.verify(&pickup));
+        assert!(!seal.verify(&Pickup::Coins(EncryptedU32::new(7))));
+    }
+}
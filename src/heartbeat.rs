@@ -0,0 +1,162 @@
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::diag::diag;
+use crate::tamper;
+use crate::{honeypot, registry, reporter, self_test, watch, HoneypotState, SelfTestReport};
+
+/// Aggregated results of one [`heartbeat`] pass.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeartbeatReport {
+    /// Live pointers in the [`registry`], zero unless it is enabled.
+    pub registered: usize,
+    /// Watched values checked against their digests.
+    pub watched: usize,
+    /// Type names of watched values that changed without a legitimate write since the last pass.
+    pub modified: Vec<&'static str>,
+    /// Live [`Honeypot`](crate::Honeypot)s checked.
+    pub honeypots: usize,
+    /// Type names and states of honeypots written to or frozen since their last refresh.
+    pub tripped: Vec<(&'static str, HoneypotState)>,
+    /// The cipher self-test, catching patched or hooked methods.
+    pub self_test: SelfTestReport,
+}
+
+impl HeartbeatReport {
+    /// Whether nothing was modified, no honeypot was tripped and every method passed.
+    pub fn is_clean(&self) -> bool {
+        self.modified.is_empty() && self.tripped.is_empty() && self.self_test.is_ok()
+    }
+}
+
+/// Re-verify everything that can be checked without being dereferenced.
+///
+/// Compares every [`WatchedPtr`](crate::WatchedPtr) against the digest of
+/// its last legitimate write, checks the bait of every [`Honeypot`](crate::Honeypot)
+/// and re-runs [`self_test`] on the ciphers, so tampering shows up even in
+/// objects nothing is currently reading. Each newly modified value is also
+/// reported to the tamper callback as
+/// [`TamperKind::ExternalWrite`](crate::tamper::TamperKind::ExternalWrite),
+/// once per change, and each tripped honeypot once per refresh. The report
+/// goes to the [`Reporter`](crate::Reporter) along with a
+/// [`stats`](crate::stats) snapshot. MACs and shadow copies live inside the
+/// pointers themselves, which may move, so they are still verified at their
+/// next access.
+pub fn heartbeat() -> HeartbeatReport {
+    let (watched, events) = watch::check_watches();
+    let modified = events.iter().map(|event| event.type_name).collect();
+    for event in events {
+        tamper::report(event);
+    }
+
+    let (honeypots, baits) = honeypot::check_honeypots();
+    let tripped = baits
+        .iter()
+        .map(|(event, state)| (event.type_name, *state))
+        .collect();
+    for (event, _) in baits {
+        tamper::report(event);
+    }
+
+    let report = HeartbeatReport {
+        registered: registry::len(),
+        watched,
+        modified,
+        honeypots,
+        tripped,
+        self_test: self_test(),
+    };
+    reporter::with_reporter(|reporter| {
+        reporter.on_heartbeat(&report);
+        reporter.on_stats(&crate::stats());
+    });
+    report
+}
+
+/// Handle to the thread started by [`start_heartbeat`]; dropping it stops the thread.
+pub struct Heartbeat {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Heartbeat {
+    /// Stop the heartbeat and wait for its thread to exit.
+    pub fn stop(self) {}
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl fmt::Debug for Heartbeat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "Heartbeat");
+        f.debug_struct("Heartbeat").finish_non_exhaustive()
+    }
+}
+
+/// Start a thread that runs [`heartbeat`] every `interval` and hands each report to `callback`.
+pub fn start_heartbeat<F>(interval: Duration, callback: F) -> Heartbeat
+where
+    F: Fn(&HeartbeatReport) + Send + 'static,
+{
+    let (stop, stopped) = mpsc::channel::<()>();
+    let thread = thread::Builder::new()
+        .name("pointerguard-heartbeat".into())
+        .spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                callback(&heartbeat());
+            }
+        })
+        .expect(diag!("failed to spawn the heartbeat"));
+
+    Heartbeat {
+        stop: Some(stop),
+        thread: Some(thread),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // under opaque-diagnostics every type name is "?", so ours can't be told apart.
+    #[cfg(not(feature = "opaque-diagnostics"))]
+    #[test]
+    fn heartbeat_finds_idle_modifications() {
+        use crate::watch::WatchedPtr;
+        use pretty_assertions::assert_eq;
+
+        let gold = WatchedPtr::new([7u64; 4]);
+        let name = crate::diag::type_name::<[u64; 4]>();
+        let count =
+            |report: HeartbeatReport| report.modified.iter().filter(|&&seen| seen == name).count();
+
+        unsafe { (*gold.ptr.decrypt_ptr())[2] = 9999 };
+
+        // reported once, not on every pass.
+        assert_eq!(count(heartbeat()), 1);
+        assert_eq!(count(heartbeat()), 0);
+    }
+
+    #[test]
+    fn heartbeat_thread_runs_until_stopped() {
+        let (sender, reports) = mpsc::channel();
+        let heartbeat = start_heartbeat(Duration::from_millis(1), move |report| {
+            let _ = sender.send(report.clone());
+        });
+
+        reports.recv().unwrap();
+        heartbeat.stop();
+
+        // the thread dropped its callback, and with it the sender.
+        while reports.recv().is_ok() {}
+    }
+}
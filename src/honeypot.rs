@@ -0,0 +1,264 @@
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+use std::ptr;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::diag::type_name;
+use crate::tamper::{self, TamperEvent, TamperKind};
+use crate::EncryptedPtr;
+
+/// Result of checking a honeypot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoneypotState {
+    /// The bait still holds the value we last wrote.
+    Intact,
+    /// The bait holds a value we never wrote.
+    Modified,
+    /// The bait holds the value from before the last refresh.
+    Frozen,
+}
+
+static HONEYPOTS: Mutex<Option<Honeypots>> = Mutex::new(None);
+
+struct Honeypots {
+    /// Mask applied to every stored address, as in the registry.
+    mask: u64,
+    next_id: u64,
+    entries: HashMap<u64, Entry>,
+}
+
+/// A live honeypot, checked by the heartbeat.
+struct Entry {
+    masked_addr: u64,
+    /// [`Bait::state`] for the honeypot's `T`.
+    state: unsafe fn(*const ()) -> HoneypotState,
+    type_name: &'static str,
+    /// Set once the heartbeat reported the bait, until the next refresh.
+    reported: bool,
+}
+
+fn lock() -> MutexGuard<'static, Option<Honeypots>> {
+    HONEYPOTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl HoneypotState {
+    fn kind(self) -> Option<TamperKind> {
+        match self {
+            HoneypotState::Intact => None,
+            HoneypotState::Modified => Some(TamperKind::HoneypotModified),
+            HoneypotState::Frozen => Some(TamperKind::HoneypotFrozen),
+        }
+    }
+}
+
+/// Check every live honeypot, returning how many there are and the state of
+/// each that was tampered with since it was last checked or refreshed.
+pub(crate) fn check_honeypots() -> (usize, Vec<(TamperEvent, HoneypotState)>) {
+    let mut guard = lock();
+    let Some(honeypots) = guard.as_mut() else {
+        return (0, Vec::new());
+    };
+
+    let mask = honeypots.mask;
+    let tripped = honeypots
+        .entries
+        .values_mut()
+        .filter(|entry| !entry.reported)
+        .filter_map(|entry| {
+            // the owner removes its entry under the lock before freeing the bait.
+            let state = unsafe { (entry.state)((entry.masked_addr ^ mask) as *const ()) };
+            let kind = state.kind()?;
+            entry.reported = true;
+            Some((TamperEvent::new(kind, entry.type_name), state))
+        })
+        .collect();
+    (honeypots.entries.len(), tripped)
+}
+
+/// The values the bait is expected to hold, kept behind an encrypted pointer.
+struct Expected<T> {
+    current: T,
+    previous: Option<T>,
+}
+
+/// The bait and what it should hold, at a fixed address for the heartbeat.
+struct Bait<T> {
+    value: UnsafeCell<T>,
+    expected: EncryptedPtr<Expected<T>>,
+}
+
+impl<T: Copy + PartialEq> Bait<T> {
+    /// Compare the bait against the expected value.
+    ///
+    /// # Safety
+    ///
+    /// `bait` must point to a live `Bait<T>`, and the caller must hold the
+    /// table lock so its owner can't refresh it meanwhile.
+    unsafe fn state(bait: *const ()) -> HoneypotState {
+        let bait = &*(bait as *const Self);
+
+        // read through volatile so the compiler can't assume the value is unchanged.
+        let value = ptr::read_volatile(bait.value.get());
+        let expected = bait.expected.access();
+
+        if value == expected.current {
+            HoneypotState::Intact
+        } else if expected.previous == Some(value) {
+            HoneypotState::Frozen
+        } else {
+            HoneypotState::Modified
+        }
+    }
+}
+
+/// A decoy object left in plain memory for scanners to find.
+///
+/// The bait is stored unencrypted so that it looks like a real value (e.g. a
+/// health counter), while the value it should hold is kept behind an
+/// [`EncryptedPtr`]. Every [`heartbeat`](crate::heartbeat) checks it, and so
+/// does [`Honeypot::check`]; any external write or freeze of the bait is
+/// reported to the tamper callback.
+pub struct Honeypot<T: Copy + PartialEq> {
+    bait: Box<Bait<T>>,
+    id: u64,
+}
+
+impl<T: Copy + PartialEq> Honeypot<T> {
+    /// Create a new honeypot holding `value`.
+    pub fn new(value: T) -> Self {
+        let bait = Box::new(Bait {
+            value: UnsafeCell::new(value),
+            expected: Box::new(Expected {
+                current: value,
+                previous: None,
+            })
+            .into(),
+        });
+        let addr = &*bait as *const Bait<T> as u64;
+
+        let mut guard = lock();
+        let honeypots = guard.get_or_insert_with(|| Honeypots {
+            mask: rand::random(),
+            next_id: 0,
+            entries: HashMap::new(),
+        });
+        let id = honeypots.next_id;
+        honeypots.next_id += 1;
+        honeypots.entries.insert(
+            id,
+            Entry {
+                masked_addr: addr ^ honeypots.mask,
+                state: Bait::<T>::state,
+                type_name: type_name::<T>(),
+                reported: false,
+            },
+        );
+        drop(guard);
+
+        Self { bait, id }
+    }
+
+    /// Write a new bait value, so a frozen value can be told apart from a stale one.
+    pub fn refresh(&mut self, value: T) {
+        let mut guard = lock();
+
+        let expected = self.bait.expected.access_mut();
+        expected.previous = Some(expected.current);
+        expected.current = value;
+        unsafe { ptr::write_volatile(self.bait.value.get(), value) };
+
+        if let Some(entry) = guard.as_mut().unwrap().entries.get_mut(&self.id) {
+            entry.reported = false;
+        }
+    }
+
+    /// Compare the bait against the expected value, reporting any tampering.
+    pub fn check(&self) -> HoneypotState {
+        let guard = lock();
+        let state = unsafe { Bait::<T>::state(&*self.bait as *const Bait<T> as *const ()) };
+        drop(guard);
+
+        if let Some(kind) = state.kind() {
+            tamper::report(TamperEvent::new(kind, type_name::<T>()));
+        }
+        state
+    }
+
+    /// Address of the bait, for handing to code that should look like a real user of it.
+    pub fn bait_ptr(&self) -> *mut T {
+        self.bait.value.get()
+    }
+}
+
+impl<T: Copy + PartialEq> Drop for Honeypot<T> {
+    fn drop(&mut self) {
+        if let Some(honeypots) = lock().as_mut() {
+            honeypots.entries.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn honeypot_intact() {
+        let mut honeypot = Honeypot::new(100u32);
+        assert_eq!(honeypot.check(), HoneypotState::Intact);
+
+        honeypot.refresh(95);
+        assert_eq!(honeypot.check(), HoneypotState::Intact);
+    }
+
+    #[test]
+    fn honeypot_modified() {
+        let honeypot = Honeypot::new(100u32);
+
+        // simulate an external write.
+        unsafe { ptr::write_volatile(honeypot.bait_ptr(), 9999) };
+
+        assert_eq!(honeypot.check(), HoneypotState::Modified);
+    }
+
+    #[test]
+    fn honeypot_frozen() {
+        let mut honeypot = Honeypot::new(100u32);
+        honeypot.refresh(95);
+
+        // simulate a freeze tool restoring the old value.
+        unsafe { ptr::write_volatile(honeypot.bait_ptr(), 100) };
+
+        assert_eq!(honeypot.check(), HoneypotState::Frozen);
+    }
+    #[cfg(not(feature = "opaque-diagnostics"))]
+    #[test]
+    fn heartbeat_reports_tripped_honeypots_once() {
+        #[derive(Clone, Copy, PartialEq)]
+        struct Lives(u32);
+
+        // other tests trip honeypots too, so look for ours only.
+        let tripped = || {
+            let report = crate::heartbeat();
+            let name = type_name::<Lives>();
+            let ours = report
+                .tripped
+                .iter()
+                .filter(|(tripped, _)| *tripped == name);
+            ours.map(|(_, state)| *state).collect::<Vec<_>>()
+        };
+
+        let mut honeypot = Honeypot::new(Lives(3));
+        assert_eq!(tripped(), []);
+
+        honeypot.refresh(Lives(2));
+        unsafe { ptr::write_volatile(honeypot.bait_ptr(), Lives(3)) };
+        assert_eq!(tripped(), [HoneypotState::Frozen]);
+        assert_eq!(tripped(), []);
+
+        honeypot.refresh(Lives(1));
+        unsafe { ptr::write_volatile(honeypot.bait_ptr(), Lives(99)) };
+        assert_eq!(tripped(), [HoneypotState::Modified]);
+    }
+}
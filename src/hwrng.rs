@@ -0,0 +1,42 @@
+/// Retries before giving up on an instruction, as Intel recommends for RDRAND.
+#[cfg(target_arch = "x86_64")]
+const RETRIES: usize = 10;
+
+/// A random `u64` from RDSEED, else RDRAND, else the thread-local CSPRNG.
+pub(crate) fn random() -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::is_x86_feature_detected!("rdseed") {
+            if let Some(value) = unsafe { rdseed() } {
+                return value;
+            }
+        }
+
+        if std::is_x86_feature_detected!("rdrand") {
+            if let Some(value) = unsafe { rdrand() } {
+                return value;
+            }
+        }
+    }
+
+    rand::random()
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "rdseed")]
+unsafe fn rdseed() -> Option<u64> {
+    use std::arch::x86_64::_rdseed64_step;
+
+    // rdseed runs dry under load more readily than rdrand.
+    let mut value = 0;
+    (0..RETRIES).find_map(|_| (_rdseed64_step(&mut value) == 1).then_some(value))
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "rdrand")]
+unsafe fn rdrand() -> Option<u64> {
+    use std::arch::x86_64::_rdrand64_step;
+
+    let mut value = 0;
+    (0..RETRIES).find_map(|_| (_rdrand64_step(&mut value) == 1).then_some(value))
+}
@@ -0,0 +1,344 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{
+    Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Div, DivAssign,
+    Mul, MulAssign, Neg, Not, Rem, RemAssign, Sub, SubAssign,
+};
+
+use crate::diag::diag_panic;
+use crate::master::{self, MaskedKey};
+use crate::sealed::generate_key;
+use crate::{Encrypt, MethodA};
+
+/// A value's bits, encrypted in place under a key that changes on every write.
+pub(crate) struct Word {
+    encrypted: u64,
+    key: MaskedKey,
+}
+
+impl Word {
+    #[inline(always)]
+    pub(crate) fn new(bits: u64) -> Self {
+        let key = MaskedKey::new(generate_key());
+        Self {
+            encrypted: Self::encrypt(bits, &key),
+            key,
+        }
+    }
+
+    #[inline(always)]
+    fn encrypt(bits: u64, key: &MaskedKey) -> u64 {
+        if cfg!(passthrough) {
+            return bits;
+        }
+
+        MethodA.encrypt(bits, key.get())
+    }
+
+    /// The plaintext bits.
+    #[inline(always)]
+    pub(crate) fn get(&self) -> u64 {
+        if master::is_poisoned() {
+            diag_panic!("protected pointers have been poisoned");
+        }
+
+        if cfg!(passthrough) {
+            return self.encrypted;
+        }
+
+        MethodA.decrypt(self.encrypted, self.key.get())
+    }
+
+    /// Replace the bits, under a fresh key.
+    #[inline(always)]
+    pub(crate) fn set(&mut self, bits: u64) {
+        *self = Self::new(bits);
+    }
+
+    /// The stored ciphertext, for diagnostics.
+    pub(crate) fn ciphertext(&self) -> u64 {
+        self.encrypted
+    }
+}
+
+/// Implement a binary operator and its assigning form, against the plain type and itself.
+macro_rules! binary_ops {
+    ($name:ident, $ty:ty, $($op:ident $method:ident $assign:ident $assign_method:ident),*) => {$(
+        impl $op<$ty> for $name {
+            type Output = $name;
+
+            #[inline(always)]
+            fn $method(self, rhs: $ty) -> $name {
+                $name::new(self.get().$method(rhs))
+            }
+        }
+
+        impl $op for $name {
+            type Output = $name;
+
+            #[inline(always)]
+            fn $method(self, rhs: $name) -> $name {
+                $name::new(self.get().$method(rhs.get()))
+            }
+        }
+
+        impl $assign<$ty> for $name {
+            #[inline(always)]
+            fn $assign_method(&mut self, rhs: $ty) {
+                self.set(self.get().$method(rhs));
+            }
+        }
+
+        impl $assign for $name {
+            #[inline(always)]
+            fn $assign_method(&mut self, rhs: $name) {
+                self.set(self.get().$method(rhs.get()));
+            }
+        }
+    )*};
+}
+pub(crate) use binary_ops;
+
+/// Everything an encrypted number shares, given how its bits round-trip through a `u64`.
+macro_rules! encrypted_number {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $to_bits:expr, $from_bits:expr) => {
+        $(#[$attr])*
+        pub struct $name {
+            word: Word,
+        }
+
+        impl $name {
+            /// Encrypt `value`.
+            #[inline(always)]
+            pub fn new(value: $ty) -> Self {
+                Self {
+                    word: Word::new($to_bits(value)),
+                }
+            }
+
+            /// Decrypt the value.
+            #[inline(always)]
+            pub fn get(&self) -> $ty {
+                $from_bits(self.word.get())
+            }
+
+            /// Replace the value, re-encrypting under a fresh key.
+            #[inline(always)]
+            pub fn set(&mut self, value: $ty) {
+                self.word.set($to_bits(value));
+            }
+
+            /// Apply `f` to the value in place, e.g. `ammo.update(|n| n.saturating_sub(1))`.
+            #[inline(always)]
+            pub fn update(&mut self, f: impl FnOnce($ty) -> $ty) {
+                self.set(f(self.get()));
+            }
+
+            /// Replace the value with `new_value` if `predicate` accepts the current one, returning whether it did.
+            ///
+            /// The value is decrypted once, so the check and the write see the same value.
+            #[inline(always)]
+            pub fn update_if(&mut self, predicate: impl FnOnce($ty) -> bool, new_value: $ty) -> bool {
+                let accepted = predicate(self.get());
+                if accepted {
+                    self.set(new_value);
+                }
+                accepted
+            }
+
+            /// Replace the value with `new` if it is bitwise equal to `current`, like an atomic's `compare_exchange`.
+            ///
+            /// Returns the previous value, as `Ok` if it was replaced.
+            #[inline(always)]
+            pub fn compare_exchange(&mut self, current: $ty, new: $ty) -> Result<$ty, $ty> {
+                let bits = self.word.get();
+                if bits != $to_bits(current) {
+                    return Err($from_bits(bits));
+                }
+
+                self.set(new);
+                Ok($from_bits(bits))
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new(<$ty>::default())
+            }
+        }
+
+        impl Clone for $name {
+            /// The clone is encrypted under its own key.
+            fn clone(&self) -> Self {
+                Self::new(self.get())
+            }
+        }
+
+        impl From<$ty> for $name {
+            fn from(value: $ty) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl From<$name> for $ty {
+            fn from(value: $name) -> Self {
+                value.get()
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.get() == other.get()
+            }
+        }
+
+        impl PartialEq<$ty> for $name {
+            fn eq(&self, other: &$ty) -> bool {
+                self.get() == *other
+            }
+        }
+
+        impl PartialOrd<$ty> for $name {
+            fn partial_cmp(&self, other: &$ty) -> Option<Ordering> {
+                self.get().partial_cmp(other)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.get(), f)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                crate::diag::opaque_debug!(f, stringify!($name));
+                f.debug_struct(stringify!($name))
+                    .field("encrypted_value", &format!("{:#x}", self.word.ciphertext()))
+                    .field("value", &self.get())
+                    .finish()
+            }
+        }
+
+        binary_ops!(
+            $name, $ty,
+            Add add AddAssign add_assign,
+            Sub sub SubAssign sub_assign,
+            Mul mul MulAssign mul_assign,
+            Div div DivAssign div_assign,
+            Rem rem RemAssign rem_assign
+        );
+    };
+}
+pub(crate) use encrypted_number;
+
+/// Integer wrappers, with bitwise operators and a total order on top.
+macro_rules! encrypted_int {
+    ($($name:ident $ty:ident),*) => {$(
+        encrypted_number!(
+            #[doc = concat!("A `", stringify!($ty), "` stored encrypted, re-encrypted under a fresh key on every write.")]
+            ///
+            /// Arithmetic, comparison and formatting decrypt transiently and
+            /// behave like the plain integer, overflow checks included, so
+            /// health, currency or score counters switch over with near
+            /// drop-in syntax: `gold += 10`, `if gold >= price`.
+            $name, $ty, |value: $ty| value as u64, |bits: u64| bits as $ty
+        );
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.get().cmp(&other.get())
+            }
+        }
+
+        impl Not for $name {
+            type Output = $name;
+
+            fn not(self) -> $name {
+                $name::new(!self.get())
+            }
+        }
+
+        binary_ops!(
+            $name, $ty,
+            BitAnd bitand BitAndAssign bitand_assign,
+            BitOr bitor BitOrAssign bitor_assign,
+            BitXor bitxor BitXorAssign bitxor_assign
+        );
+    )*};
+}
+
+/// Negation, for the signed wrappers.
+macro_rules! negate {
+    ($($name:ident),*) => {$(
+        impl Neg for $name {
+            type Output = $name;
+
+            fn neg(self) -> $name {
+                $name::new(-self.get())
+            }
+        }
+    )*};
+}
+
+encrypted_int!(
+    EncryptedU8 u8, EncryptedU16 u16, EncryptedU32 u32, EncryptedU64 u64, EncryptedUsize usize,
+    EncryptedI8 i8, EncryptedI16 i16, EncryptedI32 i32, EncryptedI64 i64, EncryptedIsize isize
+);
+negate!(
+    EncryptedI8,
+    EncryptedI16,
+    EncryptedI32,
+    EncryptedI64,
+    EncryptedIsize
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn arithmetic_and_comparisons() {
+        let mut gold = EncryptedU32::new(100);
+        gold += 25;
+        gold -= EncryptedU32::new(5);
+        let doubled = gold.clone() * 2;
+
+        assert_eq!(gold.get(), 120);
+        assert_eq!(doubled, 240);
+        assert!(gold < doubled && gold >= 120);
+        assert_eq!((-EncryptedI64::new(7) % 4).get(), -3);
+        assert_eq!(format!("{gold}"), "120");
+    }
+
+    #[test]
+    fn conditional_updates() {
+        let mut health = EncryptedU32::new(100);
+
+        assert!(!health.update_if(|current| current > 100, 500));
+        assert!(health.update_if(|current| current >= 30, 70));
+        assert_eq!(health.compare_exchange(100, 0), Err(70));
+        assert_eq!(health.compare_exchange(70, 40), Ok(70));
+        assert_eq!(health, 40);
+    }
+
+    #[cfg(not(passthrough))]
+    #[test]
+    fn writes_reencrypt() {
+        let mut score = EncryptedI32::new(-1);
+        let before = score.word.ciphertext();
+        score.set(-1);
+
+        assert_eq!(score, -1);
+        assert!(score.word.ciphertext() != before || score.word.key.get() == 0);
+    }
+}
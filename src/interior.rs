@@ -0,0 +1,247 @@
+use std::cell::{Ref, RefCell, RefMut};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+use crate::diag::diag;
+use crate::sealed::Sealed;
+
+/// A protected allocation shared with [`InteriorPtr`]s into it.
+///
+/// Wraps any protected owner, such as an [`EncryptedPtr`](crate::EncryptedPtr)
+/// or [`EncryptedVec`](crate::EncryptedVec), in a reference-counted cell.
+/// Interior pointers keep it alive and locate their target from its current
+/// address on every access, so they survive
+/// [`relocate`](crate::EncryptedPtr::relocate) and vector growth.
+pub struct EncryptedOwner<O> {
+    owner: Rc<RefCell<O>>,
+}
+
+impl<O: Deref> EncryptedOwner<O> {
+    /// Share `owner`.
+    pub fn new(owner: O) -> Self {
+        Self {
+            owner: Rc::new(RefCell::new(owner)),
+        }
+    }
+
+    /// Borrow the owner.
+    ///
+    /// # Panics
+    /// If it is mutably borrowed, e.g. through an [`InteriorPtr`].
+    pub fn borrow(&self) -> Ref<'_, O> {
+        self.owner.borrow()
+    }
+
+    /// Mutably borrow the owner, e.g. to grow or relocate it.
+    ///
+    /// # Panics
+    /// If it is already borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, O> {
+        self.owner.borrow_mut()
+    }
+
+    /// Create a pointer to the part of the pointee `f` returns, e.g. `owner.interior(|player| &player.health)`.
+    ///
+    /// Only the part's encrypted offset is stored. Accesses only re-check
+    /// that the offset still fits in the pointee, so for slices prefer the
+    /// safe [`EncryptedOwner::element`].
+    ///
+    /// # Safety
+    /// For as long as the interior pointer is used, the bytes at that offset
+    /// must keep holding a valid `U`: writes through the owner may move the
+    /// pointee as a whole, but must not replace or reinterpret the part, e.g.
+    /// by changing the variant of an enum it lies in.
+    ///
+    /// # Panics
+    /// If `f` returns a reference outside the pointee itself.
+    pub unsafe fn interior<U>(&self, f: impl FnOnce(&O::Target) -> &U) -> InteriorPtr<O, U> {
+        let owner = self.owner.borrow();
+        let base = &**owner as *const O::Target as *const u8 as usize;
+        let offset = (f(&owner) as *const U as usize).wrapping_sub(base);
+        assert!(
+            fits::<U>(offset, size_of_val(&**owner)),
+            "{}",
+            diag!("interior reference must point into the pointee")
+        );
+        drop(owner);
+        self.at(offset)
+    }
+
+    /// Create a pointer to the element at `index` of a slice pointee, e.g. an [`EncryptedVec`](crate::EncryptedVec)'s.
+    ///
+    /// Every in-bounds element of a `[U]` is a valid `U`, so re-checking the
+    /// length on access is enough and the pointer is safe to create.
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    pub fn element<U>(&self, index: usize) -> InteriorPtr<O, U>
+    where
+        O: Deref<Target = [U]>,
+    {
+        let len = self.owner.borrow().len();
+        assert!(index < len, "{}", diag!("element index out of bounds"));
+        self.at(index * size_of::<U>())
+    }
+
+    fn at<U>(&self, offset: usize) -> InteriorPtr<O, U> {
+        InteriorPtr {
+            owner: self.owner.clone(),
+            offset: Sealed::new(offset as u64),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<O> Clone for EncryptedOwner<O> {
+    fn clone(&self) -> Self {
+        Self {
+            owner: self.owner.clone(),
+        }
+    }
+}
+
+impl<O: fmt::Debug> fmt::Debug for EncryptedOwner<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.owner.borrow().fmt(f)
+    }
+}
+
+/// Whether a `U` at `offset` lies within `size` bytes.
+fn fits<U>(offset: usize, size: usize) -> bool {
+    offset <= size && size_of::<U>() <= size - offset && offset.is_multiple_of(align_of::<U>())
+}
+
+/// A protected pointer into an [`EncryptedOwner`]'s pointee, see [`EncryptedOwner::element`] and [`EncryptedOwner::interior`].
+///
+/// Revalidated on every access: if the owner has shrunk so the target is
+/// gone, [`InteriorPtr::get`] returns `None` instead of dangling.
+pub struct InteriorPtr<O, U> {
+    owner: Rc<RefCell<O>>,
+    offset: Sealed,
+    _marker: PhantomData<*const U>,
+}
+
+impl<O: Deref, U> InteriorPtr<O, U> {
+    /// The offset into the pointee, decrypted.
+    #[inline(always)]
+    fn offset(&self) -> usize {
+        self.offset.get() as usize
+    }
+
+    /// Borrow the target at the owner's current address, or `None` if it no longer fits.
+    ///
+    /// # Panics
+    /// If the owner is mutably borrowed.
+    pub fn get(&self) -> Option<Ref<'_, U>> {
+        let offset = self.offset();
+        Ref::filter_map(self.owner.borrow(), |owner| {
+            let target = &**owner;
+            fits::<U>(offset, size_of_val(target)).then(|| unsafe {
+                &*(target as *const O::Target as *const u8)
+                    .add(offset)
+                    .cast::<U>()
+            })
+        })
+        .ok()
+    }
+
+    /// Mutably borrow the target, see [`InteriorPtr::get`].
+    ///
+    /// # Panics
+    /// If the owner is already borrowed.
+    pub fn get_mut(&self) -> Option<RefMut<'_, U>>
+    where
+        O: DerefMut,
+    {
+        let offset = self.offset();
+        RefMut::filter_map(self.owner.borrow_mut(), |owner| {
+            let target = &mut **owner;
+            fits::<U>(offset, size_of_val(target)).then(|| unsafe {
+                &mut *(target as *mut O::Target as *mut u8)
+                    .add(offset)
+                    .cast::<U>()
+            })
+        })
+        .ok()
+    }
+
+    /// Re-encrypt the offset under the current key version if it predates a [`rotate_master_key`](crate::rotate_master_key).
+    ///
+    /// Returns whether the offset was migrated.
+    pub fn migrate(&mut self) -> bool {
+        self.offset.migrate()
+    }
+
+    /// The owner this pointer keeps alive.
+    pub fn owner(&self) -> EncryptedOwner<O> {
+        EncryptedOwner {
+            owner: self.owner.clone(),
+        }
+    }
+}
+
+impl<O, U> Clone for InteriorPtr<O, U> {
+    fn clone(&self) -> Self {
+        Self {
+            owner: self.owner.clone(),
+            offset: Sealed::new(self.offset.get()),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<O: Deref, U: fmt::Debug> fmt::Debug for InteriorPtr<O, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "InteriorPtr");
+        f.debug_struct("InteriorPtr")
+            .field(
+                "encrypted_offset",
+                &format!("{:#x}", self.offset.ciphertext()),
+            )
+            .field("pointed_value", &self.get().as_deref())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EncryptedPtr, EncryptedVec};
+    use pretty_assertions::assert_eq;
+
+    struct Player {
+        _id: u64,
+        health: u32,
+    }
+
+    #[test]
+    fn interior_survives_relocation() {
+        let owner = EncryptedOwner::new(EncryptedPtr::from(Player {
+            _id: 7,
+            health: 100,
+        }));
+        let health = unsafe { owner.interior(|player| &player.health) };
+
+        owner.borrow_mut().relocate();
+        *health.get_mut().unwrap() -= 25;
+
+        assert_eq!(owner.borrow().health, 75);
+        drop(owner);
+        assert_eq!(health.get().as_deref(), Some(&75));
+    }
+
+    #[test]
+    fn interior_revalidates_against_vec() {
+        let owner = EncryptedOwner::new(EncryptedVec::from(vec![1u32, 2, 3]));
+        let third = owner.element(2);
+
+        // growing moves the buffer, shrinking drops the element.
+        owner.borrow_mut().with_vec(|items| items.extend(4..1000));
+        assert_eq!(third.get().as_deref(), Some(&3));
+
+        owner.borrow_mut().with_vec(|items| items.truncate(2));
+        assert!(third.get().is_none());
+    }
+}
@@ -0,0 +1,82 @@
+use std::hint::black_box;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{Encrypt, MethodB};
+
+/// Random delays injected into protected accesses, see [`Config::access_jitter`](crate::Config::access_jitter).
+///
+/// On average one access in `one_in` runs between zero and `max_rounds`
+/// rounds of dummy decryption first, so the timing of protected accesses
+/// varies and is harder to fingerprint or use as an oracle against the
+/// cipher. A `one_in` of zero disables jitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AccessJitter {
+    /// Delay roughly one access in this many; zero never delays.
+    pub one_in: u32,
+    /// Upper bound on the rounds of dummy work in a single delay.
+    pub max_rounds: u32,
+}
+
+impl AccessJitter {
+    /// No jitter.
+    pub const OFF: AccessJitter = AccessJitter {
+        one_in: 0,
+        max_rounds: 0,
+    };
+}
+
+// kept outside the config lock, since every access reads them.
+static ONE_IN: AtomicU32 = AtomicU32::new(0);
+static MAX_ROUNDS: AtomicU32 = AtomicU32::new(0);
+
+/// Install `jitter` for subsequent accesses.
+pub(crate) fn set(jitter: AccessJitter) {
+    MAX_ROUNDS.store(jitter.max_rounds, Ordering::Relaxed);
+    ONE_IN.store(jitter.one_in, Ordering::Relaxed);
+}
+
+/// Maybe delay the current access.
+#[inline(always)]
+pub(crate) fn jitter() {
+    let one_in = ONE_IN.load(Ordering::Relaxed);
+    if one_in != 0 {
+        delay(one_in);
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn delay(one_in: u32) {
+    if rand::random_range(0..one_in) != 0 {
+        return;
+    }
+
+    // dummy decryptions, so the delay looks like work rather than a sleep.
+    let rounds = rand::random_range(0..=MAX_ROUNDS.load(Ordering::Relaxed));
+    let mut data = black_box(rand::random::<u64>());
+    for key in 0..rounds {
+        data = MethodB.decrypt(black_box(data), key as u64);
+    }
+    black_box(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EncryptedPtr;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn jittered_accesses_still_decrypt() {
+        let score: EncryptedPtr<u32> = 7.into();
+
+        set(AccessJitter {
+            one_in: 1,
+            max_rounds: 64,
+        });
+        let total: u32 = (0..32).map(|_| *score.access()).sum();
+        set(AccessJitter::OFF);
+
+        assert_eq!(total, 7 * 32);
+    }
+}
@@ -0,0 +1,195 @@
+/// What a decoy field should pass for.
+#[cfg_attr(
+    not(all(feature = "paranoid", not(feature = "diffused"))),
+    allow(dead_code)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Decoy {
+    /// A ciphertext of a nearby address.
+    Ciphertext,
+    /// A key word of the same method and version.
+    Key,
+}
+
+/// Plain layout: just the ciphertext and its key.
+#[cfg(not(any(feature = "paranoid", feature = "diffused")))]
+pub(crate) struct Layout {
+    encrypted_ptr: u64,
+    key: u64,
+}
+
+#[cfg(not(any(feature = "paranoid", feature = "diffused")))]
+impl Layout {
+    #[inline(always)]
+    pub(crate) fn new(encrypted_ptr: u64, key: u64, _decoy: impl FnMut(Decoy) -> u64) -> Self {
+        Self { encrypted_ptr, key }
+    }
+
+    #[inline(always)]
+    pub(crate) fn encrypted_ptr(&self) -> u64 {
+        self.encrypted_ptr
+    }
+
+    #[inline(always)]
+    pub(crate) fn key(&self) -> u64 {
+        self.key
+    }
+}
+
+#[cfg(any(feature = "paranoid", feature = "diffused"))]
+include!(concat!(env!("OUT_DIR"), "/layout_seed.rs"));
+
+/// Number of u64 slots in the paranoid layout, two of which are real.
+#[cfg(all(feature = "paranoid", not(feature = "diffused")))]
+const SLOTS: usize = 6;
+
+/// Slot holding the real ciphertext, chosen per build.
+#[cfg(all(feature = "paranoid", not(feature = "diffused")))]
+const CIPHERTEXT_SLOT: usize = (LAYOUT_SEED % SLOTS as u64) as usize;
+
+/// Slot holding the real key, chosen per build and never equal to the ciphertext slot.
+#[cfg(all(feature = "paranoid", not(feature = "diffused")))]
+const KEY_SLOT: usize =
+    (CIPHERTEXT_SLOT + 1 + ((LAYOUT_SEED >> 8) % (SLOTS as u64 - 1)) as usize) % SLOTS;
+
+/// Paranoid layout: the real ciphertext and key hidden among decoy fields.
+#[cfg(all(feature = "paranoid", not(feature = "diffused")))]
+pub(crate) struct Layout {
+    slots: [u64; SLOTS],
+}
+
+#[cfg(all(feature = "paranoid", not(feature = "diffused")))]
+impl Layout {
+    #[inline(always)]
+    pub(crate) fn new(encrypted_ptr: u64, key: u64, mut decoy: impl FnMut(Decoy) -> u64) -> Self {
+        let mut slots = [0; SLOTS];
+        for (i, slot) in slots.iter_mut().enumerate() {
+            *slot = match i % 2 {
+                0 => decoy(Decoy::Ciphertext),
+                _ => decoy(Decoy::Key),
+            };
+        }
+
+        slots[CIPHERTEXT_SLOT] = encrypted_ptr;
+        slots[KEY_SLOT] = key;
+
+        Self { slots }
+    }
+
+    #[inline(always)]
+    pub(crate) fn encrypted_ptr(&self) -> u64 {
+        self.slots[CIPHERTEXT_SLOT]
+    }
+
+    #[inline(always)]
+    pub(crate) fn key(&self) -> u64 {
+        self.slots[KEY_SLOT]
+    }
+}
+
+/// Number of 16-bit sub-fields in the diffused layout, four of which hold the ciphertext.
+#[cfg(feature = "diffused")]
+const FIELDS: usize = 12;
+
+/// Sub-fields holding the ciphertext, lowest 16 bits first, in an order shuffled per build.
+#[cfg(feature = "diffused")]
+const CIPHERTEXT_FIELDS: [usize; 4] = shuffled_fields();
+
+/// Fisher-Yates over the field indices, driven by the layout seed.
+#[cfg(feature = "diffused")]
+const fn shuffled_fields() -> [usize; 4] {
+    let mut order = [0; FIELDS];
+    let mut i = 0;
+    while i < FIELDS {
+        order[i] = i;
+        i += 1;
+    }
+
+    // splitmix64, so nearby seeds still give unrelated orders.
+    let mut state = LAYOUT_SEED;
+    let mut i = FIELDS - 1;
+    while i > 0 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        let j = (z % (i as u64 + 1)) as usize;
+        let swap = order[i];
+        order[i] = order[j];
+        order[j] = swap;
+        i -= 1;
+    }
+
+    [order[0], order[1], order[2], order[3]]
+}
+
+/// Diffused layout: the ciphertext split into 16-bit sub-fields, scattered among noise.
+///
+/// Takes precedence over the paranoid layout when both features are on.
+#[cfg(feature = "diffused")]
+pub(crate) struct Layout {
+    fields: [u16; FIELDS],
+    key: u64,
+}
+
+#[cfg(feature = "diffused")]
+impl Layout {
+    #[inline(always)]
+    pub(crate) fn new(encrypted_ptr: u64, key: u64, mut decoy: impl FnMut(Decoy) -> u64) -> Self {
+        let mut fields = [0; FIELDS];
+        for chunk in fields.chunks_mut(4) {
+            // the noise sits among ciphertext fields, so it should look like ciphertext.
+            let noise = decoy(Decoy::Ciphertext);
+            for (i, field) in chunk.iter_mut().enumerate() {
+                *field = (noise >> (i * 16)) as u16;
+            }
+        }
+
+        for (i, &field) in CIPHERTEXT_FIELDS.iter().enumerate() {
+            fields[field] = (encrypted_ptr >> (i * 16)) as u16;
+        }
+
+        Self { fields, key }
+    }
+
+    #[inline(always)]
+    pub(crate) fn encrypted_ptr(&self) -> u64 {
+        CIPHERTEXT_FIELDS
+            .iter()
+            .enumerate()
+            .fold(0, |ptr, (i, &field)| {
+                ptr | (self.fields[field] as u64) << (i * 16)
+            })
+    }
+
+    #[inline(always)]
+    pub(crate) fn key(&self) -> u64 {
+        self.key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn layout_round_trip() {
+        let layout = Layout::new(0xDEADBEEF, 0x1234, |_| rand::random());
+
+        assert_eq!(layout.encrypted_ptr(), 0xDEADBEEF);
+        assert_eq!(layout.key(), 0x1234);
+    }
+
+    #[cfg(feature = "diffused")]
+    #[test]
+    fn ciphertext_fields_are_distinct() {
+        let mut fields = CIPHERTEXT_FIELDS;
+        fields.sort();
+
+        assert!(fields.windows(2).all(|pair| pair[0] != pair[1]));
+        assert!(fields.iter().all(|&field| field < FIELDS));
+    }
+}
@@ -0,0 +1,378 @@
+use std::fmt;
+use std::io;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::master::MaskedKey;
+use crate::{aes, wipe};
+
+// the userfaultfd ABI, see linux/userfaultfd.h; these ioctl numbers use the
+// generic _IOC encoding.
+const UFFD_API: u64 = 0xAA;
+const UFFDIO_API: u64 = 0xC018_AA3F;
+const UFFDIO_REGISTER: u64 = 0xC020_AA00;
+const UFFDIO_COPY: u64 = 0xC028_AA03;
+const UFFDIO_REGISTER_MODE_MISSING: u64 = 1;
+const UFFD_EVENT_PAGEFAULT: u8 = 0x12;
+
+/// How long the fault thread waits for an event before checking whether to stop.
+const POLL_MS: i32 = 50;
+
+#[repr(C)]
+struct UffdioApi {
+    api: u64,
+    features: u64,
+    ioctls: u64,
+}
+
+#[repr(C)]
+struct UffdioRegister {
+    start: u64,
+    len: u64,
+    mode: u64,
+    ioctls: u64,
+}
+
+#[repr(C)]
+struct UffdioCopy {
+    dst: u64,
+    src: u64,
+    len: u64,
+    mode: u64,
+    copy: i64,
+}
+
+#[repr(C)]
+struct UffdMsg {
+    event: u8,
+    _reserved: [u8; 7],
+    flags: u64,
+    address: u64,
+    _feat: u64,
+}
+
+/// Encrypted page contents and which pages are currently decrypted in place.
+struct Store {
+    pages: Vec<u8>,
+    /// Bumped every time a page is re-encrypted, so no keystream is reused.
+    generations: Vec<u64>,
+    resident: Vec<bool>,
+}
+
+/// State shared with the fault-handling thread.
+struct Shared {
+    fd: i32,
+    base: u64,
+    page: usize,
+    key: MaskedKey,
+    store: Mutex<Store>,
+    /// Milliseconds from `epoch` to the last fault, so idleness can be measured.
+    last_fault: AtomicU64,
+    epoch: Instant,
+    stop: AtomicBool,
+}
+
+impl Shared {
+    /// XOR page `index` with its keystream for `generation`, encrypting or decrypting it.
+    fn apply_keystream(&self, index: usize, generation: u64, bytes: &mut [u8]) {
+        let key = self.key.get() ^ generation.rotate_left(32);
+        for (word, chunk) in bytes.chunks_exact_mut(8).enumerate() {
+            let stream = aes::mac(key, ((index as u64) << 32) | word as u64);
+            let value = u64::from_ne_bytes(chunk.try_into().unwrap()) ^ stream;
+            chunk.copy_from_slice(&value.to_ne_bytes());
+        }
+    }
+
+    /// Decrypt the page containing `address` into place.
+    fn populate(&self, address: u64) {
+        let index = ((address - self.base) / self.page as u64) as usize;
+        let mut store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+
+        let offset = index * self.page;
+        let mut plain = store.pages[offset..offset + self.page].to_vec();
+        self.apply_keystream(index, store.generations[index], &mut plain);
+
+        let mut copy = UffdioCopy {
+            dst: self.base + offset as u64,
+            src: plain.as_ptr() as u64,
+            len: self.page as u64,
+            mode: 0,
+            copy: 0,
+        };
+        // fails with EEXIST if another thread's fault already filled the page.
+        unsafe { libc::ioctl(self.fd, UFFDIO_COPY as _, &mut copy) };
+        unsafe { wipe::wipe(plain.as_mut_ptr(), plain.len()) };
+
+        store.resident[index] = true;
+        self.last_fault
+            .store(self.epoch.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Serve page faults until asked to stop.
+    fn serve(&self) {
+        let mut poll = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        while !self.stop.load(Ordering::Relaxed) {
+            if unsafe { libc::poll(&mut poll, 1, POLL_MS) } <= 0 {
+                continue;
+            }
+
+            let mut msg = UffdMsg {
+                event: 0,
+                _reserved: [0; 7],
+                flags: 0,
+                address: 0,
+                _feat: 0,
+            };
+            let size = size_of::<UffdMsg>();
+            let read = unsafe { libc::read(self.fd, &mut msg as *mut UffdMsg as *mut _, size) };
+            if read == size as isize && msg.event == UFFD_EVENT_PAGEFAULT {
+                self.populate(msg.address);
+            }
+        }
+    }
+}
+
+/// A byte buffer kept encrypted at rest and decrypted a page at a time on first touch.
+///
+/// The buffer's pages start out unmapped and registered with Linux's
+/// userfaultfd. The first read or write of a page faults to a helper thread,
+/// which decrypts just that page into place; untouched pages never exist in
+/// plaintext. [`LazyBuffer::seal`] re-encrypts the pages touched since and
+/// unmaps them again, [`LazyBuffer::seal_if_idle`] does so after a quiet
+/// period. Meant for large buffers, where page granularity is cheap.
+pub struct LazyBuffer {
+    shared: Arc<Shared>,
+    len: usize,
+    mapped: usize,
+    handler: Option<JoinHandle<()>>,
+}
+
+impl LazyBuffer {
+    /// Encrypt a copy of `bytes` into a new lazily decrypted buffer.
+    ///
+    /// Fails if userfaultfd is unavailable, e.g. because
+    /// `vm.unprivileged_userfaultfd` is off and the process lacks `CAP_SYS_PTRACE`.
+    pub fn new(bytes: &[u8]) -> io::Result<Self> {
+        let page = page_size();
+        let mapped = bytes.len().max(1).next_multiple_of(page);
+
+        let fd = unsafe { libc::syscall(libc::SYS_userfaultfd, libc::O_CLOEXEC | libc::O_NONBLOCK) }
+            as i32;
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let base = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                mapped,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if base == libc::MAP_FAILED {
+            let error = io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(error);
+        }
+
+        let mut api = UffdioApi {
+            api: UFFD_API,
+            features: 0,
+            ioctls: 0,
+        };
+        let mut register = UffdioRegister {
+            start: base as u64,
+            len: mapped as u64,
+            mode: UFFDIO_REGISTER_MODE_MISSING,
+            ioctls: 0,
+        };
+        let registered = unsafe {
+            libc::ioctl(fd, UFFDIO_API as _, &mut api) == 0
+                && libc::ioctl(fd, UFFDIO_REGISTER as _, &mut register) == 0
+        };
+        if !registered {
+            let error = io::Error::last_os_error();
+            unsafe {
+                libc::munmap(base, mapped);
+                libc::close(fd);
+            }
+            return Err(error);
+        }
+
+        let pages = mapped / page;
+        let shared = Arc::new(Shared {
+            fd,
+            base: base as u64,
+            page,
+            key: MaskedKey::new(rand::random()),
+            store: Mutex::new(Store {
+                pages: Vec::new(),
+                generations: vec![0; pages],
+                resident: vec![false; pages],
+            }),
+            last_fault: AtomicU64::new(0),
+            epoch: Instant::now(),
+            stop: AtomicBool::new(false),
+        });
+
+        let mut encrypted = bytes.to_vec();
+        encrypted.resize(mapped, 0);
+        for (index, chunk) in encrypted.chunks_exact_mut(page).enumerate() {
+            shared.apply_keystream(index, 0, chunk);
+        }
+        shared.store.lock().unwrap_or_else(|e| e.into_inner()).pages = encrypted;
+
+        let handler = thread::Builder::new()
+            .name("pointerguard-uffd".into())
+            .spawn({
+                let shared = shared.clone();
+                move || shared.serve()
+            })?;
+
+        Ok(Self {
+            shared,
+            len: bytes.len(),
+            mapped,
+            handler: Some(handler),
+        })
+    }
+
+    /// Length of the buffer in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The buffer's bytes; pages are decrypted as they are first read.
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.shared.base as *const u8, self.len) }
+    }
+
+    /// The buffer's bytes, mutably; writes are kept until the next seal.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.shared.base as *mut u8, self.len) }
+    }
+
+    /// Number of pages currently decrypted in place.
+    pub fn resident_pages(&self) -> usize {
+        let store = self.shared.store.lock().unwrap_or_else(|e| e.into_inner());
+        store.resident.iter().filter(|&&resident| resident).count()
+    }
+
+    /// Re-encrypt every page touched since the last seal and drop its plaintext.
+    ///
+    /// Taking `&mut self` guarantees nothing reads the pages meanwhile; their
+    /// next touch faults and decrypts them again.
+    pub fn seal(&mut self) {
+        let shared = &*self.shared;
+        let mut store = shared.store.lock().unwrap_or_else(|e| e.into_inner());
+
+        for index in 0..store.resident.len() {
+            if !store.resident[index] {
+                continue;
+            }
+
+            let offset = index * shared.page;
+            let page = (shared.base as usize + offset) as *mut u8;
+            let mut cipher = unsafe { slice::from_raw_parts(page, shared.page) }.to_vec();
+            let generation = store.generations[index] + 1;
+            shared.apply_keystream(index, generation, &mut cipher);
+
+            store.pages[offset..offset + shared.page].copy_from_slice(&cipher);
+            store.generations[index] = generation;
+            store.resident[index] = false;
+            unsafe {
+                wipe::wipe(page, shared.page);
+                libc::madvise(page as *mut _, shared.page, libc::MADV_DONTNEED);
+            }
+        }
+    }
+
+    /// [`LazyBuffer::seal`] if no page has been decrypted for at least `quiet`.
+    ///
+    /// Returns whether it sealed anything.
+    pub fn seal_if_idle(&mut self, quiet: Duration) -> bool {
+        let last = Duration::from_millis(self.shared.last_fault.load(Ordering::Relaxed));
+        if self.resident_pages() == 0 || self.shared.epoch.elapsed() - last < quiet {
+            return false;
+        }
+
+        self.seal();
+        true
+    }
+}
+
+/// The system page size.
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+impl Drop for LazyBuffer {
+    fn drop(&mut self) {
+        // wipe what was decrypted while the handler can still serve faults.
+        self.seal();
+
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if let Some(handler) = self.handler.take() {
+            let _ = handler.join();
+        }
+
+        unsafe {
+            libc::munmap(self.shared.base as *mut _, self.mapped);
+            libc::close(self.shared.fd);
+        }
+    }
+}
+
+impl fmt::Debug for LazyBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "LazyBuffer");
+        f.debug_struct("LazyBuffer")
+            .field("len", &self.len)
+            .field("resident_pages", &self.resident_pages())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn pages_decrypt_on_first_touch() {
+        let page = page_size();
+        let mut bytes: Vec<u8> = (0..3 * page).map(|i| i as u8).collect();
+        let Ok(mut buffer) = LazyBuffer::new(&bytes) else {
+            // userfaultfd isn't available in this environment.
+            return;
+        };
+
+        assert_eq!(buffer.resident_pages(), 0);
+        assert_eq!(buffer.as_slice()[page + 7], bytes[page + 7]);
+        assert_eq!(buffer.resident_pages(), 1);
+
+        buffer.as_mut_slice()[5] = 0xAA;
+        bytes[5] = 0xAA;
+        buffer.seal();
+        assert_eq!(buffer.resident_pages(), 0);
+        assert_eq!(buffer.as_slice(), &bytes[..]);
+        assert_eq!(buffer.resident_pages(), 3);
+    }
+}
@@ -1,116 +1,536 @@
+use std::alloc::{self, Layout};
+use std::collections::TryReserveError;
+use std::error::Error;
 use std::fmt;
 use std::marker::PhantomData;
+use std::mem::{ManuallyDrop, MaybeUninit};
+#[cfg(any(not(feature = "explicit-access"), test))]
 use std::ops::{Deref, DerefMut};
-use std::time::{SystemTime, UNIX_EPOCH};
-
-trait Encrypt {
+use std::ptr::{self, NonNull};
+
+use crate::diag::type_name;
+use observer::Access;
+use sealed::Sealed;
+
+// lets macro output name `::pointerguard` from inside this crate too.
+extern crate self as pointerguard;
+
+mod aes;
+mod aliasing;
+mod ascon;
+#[cfg(feature = "atomic")]
+mod atomic;
+mod audit;
+#[cfg(feature = "region")]
+mod backing;
+mod batch;
+#[cfg(feature = "bevy")]
+mod bevy;
+mod bundle;
+mod chained;
+mod chunked;
+mod config;
+mod cow;
+mod cpu;
+#[cfg(feature = "anti-debug")]
+pub mod debugger;
+mod diag;
+mod drop_policy;
+mod enclave;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod field;
+mod float;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzz;
+mod graph;
+mod heartbeat;
+mod honeypot;
+mod hwrng;
+mod int;
+mod interior;
+mod jitter;
+#[cfg(not(passthrough))]
+mod layout;
+#[cfg(all(target_os = "linux", feature = "userfaultfd"))]
+mod lazy;
+mod literal;
+mod manually_dropped;
+#[cfg(all(unix, feature = "mmap"))]
+mod mapped;
+mod masked;
+mod master;
+mod methods;
+mod module_relative;
+mod nan_boxed;
+mod nonced;
+mod obfuscated;
+mod observer;
+mod offset;
+#[cfg(feature = "region")]
+mod page_guarded;
+mod pod;
+mod prince;
+mod protected;
+mod raw;
+pub mod registry;
+mod relocation;
+mod replica;
+mod reporter;
+mod scatter;
+mod sealed;
+mod selftest;
+mod send;
+mod session;
+mod shadow;
+#[cfg(all(unix, feature = "mmap"))]
+mod shared;
+mod siphash;
+mod slot;
+mod speck;
+mod split;
+mod stats;
+pub mod tamper;
+mod thread_bound;
+mod thread_local;
+mod vec;
+#[cfg(all(windows, feature = "veh"))]
+mod veh;
+mod verified;
+mod watch;
+mod wipe;
+mod wrap;
+mod xtea;
+
+pub use aes::AesFeistel;
+pub use ascon::Ascon;
+#[cfg(feature = "atomic")]
+pub use atomic::AtomicEncryptedPtr;
+pub use audit::{
+    self_audit, self_audit_registered, AuditFinding, AuditReport, AuditTarget, FindingKind,
+};
+#[cfg(feature = "region")]
+pub use backing::PageBacking;
+pub use batch::decrypt_many;
+#[cfg(feature = "bevy")]
+pub use bevy::{EncryptedComponent, EncryptedRes};
+pub use bundle::{BundleKey, EncryptedBundle};
+pub use chained::Chained;
+pub use chunked::{ChunkCursor, ChunkGuard, ChunkGuardMut, ChunkedBuffer};
+pub use config::{configure, Config, KeySource, TamperPolicy};
+pub use cow::EncryptedCow;
+pub use cpu::{cpu_features, CpuFeatures};
+pub use diag::diagnostic_code;
+pub use drop_policy::{Dealloc, DropOnly, DropPolicy, Quarantine, Wipe};
+pub use enclave::{Enclave, EnclaveBackend, SoftwareEnclave};
+pub use field::{EncryptedField, EncryptedFieldMut};
+pub use float::{EncryptedF32, EncryptedF64};
+pub use graph::{seal_graph, GraphHasher, GraphSeal, SealGraph};
+pub use heartbeat::{heartbeat, start_heartbeat, Heartbeat, HeartbeatReport};
+pub use honeypot::{Honeypot, HoneypotState};
+pub use int::{
+    EncryptedI16, EncryptedI32, EncryptedI64, EncryptedI8, EncryptedIsize, EncryptedU16,
+    EncryptedU32, EncryptedU64, EncryptedU8, EncryptedUsize,
+};
+pub use interior::{EncryptedOwner, InteriorPtr};
+pub use jitter::AccessJitter;
+#[cfg(all(target_os = "linux", feature = "userfaultfd"))]
+pub use lazy::LazyBuffer;
+pub use literal::{BlobGuard, EncryptedBlob, EncryptedLiteral};
+pub use manually_dropped::ManuallyDroppedEncryptedPtr;
+#[cfg(all(unix, feature = "mmap"))]
+pub use mapped::{MappedBuffer, MappedChunkMut};
+pub use masked::{MaskedConst, MaskedI32, MaskedU32};
+pub use master::{
+    evict_master_key, forget_retired_keys, is_poisoned, rotate_master_key, seal_master_key,
+    KeySealer, SealError,
+};
+pub use methods::{register_method, set_builtin_weight};
+pub use module_relative::ModuleRelativePtr;
+pub use nan_boxed::NanBoxedPtr;
+pub use nonced::NoncedPtr;
+pub use obfuscated::ObfuscatedDyn;
+pub use observer::{clear_access_observer, set_access_observer, AccessObserver};
+pub use offset::EncryptedOffset;
+#[cfg(feature = "region")]
+pub use page_guarded::{PageGuard, PageGuardMut, PageGuardedPtr};
+pub use pod::{AnyBitPattern, NoUninit};
+#[cfg(feature = "macros")]
+pub use pointerguard_macros::{
+    const_key, encrypted_bytes, encrypted_str, protect, shuffle_fields, EncryptedOffsets, Protect,
+    SealGraph,
+};
+pub use prince::Prince;
+pub use protected::{Protect, ProtectedDeref};
+pub use raw::EncryptedRawPtr;
+pub use relocation::{
+    relocate_all, start_relocation_daemon, RelocatingPtr, RelocationDaemon, RelocationGuard,
+};
+pub use replica::{ReplicaGuardMut, ReplicatedPtr};
+pub use reporter::{clear_reporter, report_stats, set_reporter, Reporter};
+pub use scatter::ScatteredPtr;
+pub use selftest::{self_test, SelfTestFailure, SelfTestFinding, SelfTestReport};
+pub use send::{SendEncryptedPtr, SendGuard, SendGuardMut};
+pub use session::{session, Session};
+pub use shadow::{ShadowGuard, ShadowedPtr};
+#[cfg(all(unix, feature = "mmap"))]
+pub use shared::{SharedHandle, SharedRegion};
+pub use siphash::SipFeistel;
+pub use slot::{encrypted_channel, EncryptedReceiver, EncryptedSender, EncryptedSlot};
+pub use speck::Speck;
+pub use split::{SplitGuard, SplitGuardMut, SplitPtr};
+pub use stats::{stats, Stats};
+pub use thread_bound::{init_thread_salt, ThreadBoundPtr};
+pub use thread_local::EncryptedThreadLocal;
+#[doc(hidden)]
+pub use thread_local::Slot as __ThreadLocalSlot;
+pub use vec::{
+    EncryptedCString, EncryptedChunks, EncryptedChunksMut, EncryptedString, EncryptedVec,
+};
+#[cfg(all(windows, feature = "veh"))]
+pub use veh::{install_fault_handler, remove_fault_handler, FaultResponse};
+pub use verified::VerifiedDyn;
+pub use watch::{watch, WatchGuard, WatchedPtr};
+pub use wrap::WrappedHandle;
+pub use xtea::Xtea;
+
+/// A reversible transform used to encrypt pointers.
+pub trait Encrypt {
+    /// Encrypt `data` under `key`.
     fn encrypt(&self, data: u64, key: u64) -> u64;
+    /// Undo [`Encrypt::encrypt`] for the same `key`.
     fn decrypt(&self, data: u64, key: u64) -> u64;
 }
 
+impl<M: Encrypt + ?Sized> Encrypt for &M {
+    #[inline(always)]
+    fn encrypt(&self, data: u64, key: u64) -> u64 {
+        (**self).encrypt(data, key)
+    }
+
+    #[inline(always)]
+    fn decrypt(&self, data: u64, key: u64) -> u64 {
+        (**self).decrypt(data, key)
+    }
+}
+
 /// An encrypted pointer that decrypts when dereferenced.
-pub struct EncryptedPtr<T> {
-    encrypted_ptr: u64,
-    key: u64,
-    method: Box<dyn Encrypt>,
-    _marker: PhantomData<*mut T>,
+///
+/// `P` decides how the pointee is torn down, see [`DropPolicy`].
+pub struct EncryptedPtr<T, P: DropPolicy = Dealloc> {
+    sealed: Sealed,
+    _marker: PhantomData<(*mut T, P)>,
 }
 
 impl<T> EncryptedPtr<T> {
-    /// Generate a random key using the current time.
+    /// Generate a fresh key from the configured [`KeySource`].
     #[inline(always)]
     pub fn generate_key() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as _
+        sealed::generate_key()
     }
 
     /// Create a new encrypted pointer from a raw pointer
     #[inline(always)]
     pub fn new(ptr: *mut T) -> Self {
-        // generate a random key, maybe let user decide.
-        let key = Self::generate_key();
-
-        // here we have a list of all possible encryption methods.
-        let mut methods: Vec<Box<dyn Encrypt>> =
-            vec![Box::new(MethodA), Box::new(MethodB), Box::new(MethodC)];
+        registry::register(ptr);
 
-        // choose a random method to encrypt the pointer with.
-        let method = methods.remove(rand::random_range(0..methods.len()));
+        Self {
+            sealed: Sealed::new(ptr as u64),
+            _marker: PhantomData,
+        }
+    }
 
-        // encrypt the pointer.
-        let encrypted_ptr = method.encrypt(ptr as u64, key);
+    /// Create a new encrypted pointer from a raw pointer, using a specific method.
+    #[inline(always)]
+    pub fn with_method(ptr: *mut T, method: impl Encrypt + 'static) -> Self {
+        registry::register(ptr);
 
         Self {
-            encrypted_ptr,
-            key,
-            method,
+            sealed: Sealed::with_method(ptr as u64, sealed::Method::from_user(method)),
             _marker: PhantomData,
         }
     }
 
+    /// Move `value` to the heap, returning an error instead of aborting if allocation fails.
+    pub fn try_from_value(value: T) -> Result<Self, AllocError> {
+        let layout = Layout::new::<T>();
+        let ptr = match layout.size() {
+            0 => NonNull::dangling().as_ptr(),
+            _ => unsafe { alloc::alloc(layout) as *mut T },
+        };
+        if ptr.is_null() {
+            return Err(AllocError);
+        }
+
+        unsafe { ptr.write(value) };
+        Ok(Self::new(ptr))
+    }
+
+    /// Move the pointee to a fresh allocation, wiping the old one, and re-encrypt.
+    ///
+    /// Any address a memory scanner found goes stale. The new address is
+    /// encrypted under a fresh key and method from the default rotation, even
+    /// if this pointer was created [`with_method`](EncryptedPtr::with_method).
+    pub fn relocate(&mut self) {
+        if size_of::<T>() == 0 {
+            return;
+        }
+
+        let old = self.decrypt_ptr();
+        let new = Box::into_raw(Box::<MaybeUninit<T>>::new_uninit()) as *mut T;
+        unsafe {
+            ptr::copy_nonoverlapping(old, new, 1);
+            wipe::wipe(old as *mut u8, size_of::<T>());
+            alloc::dealloc(old as *mut u8, Layout::new::<T>());
+        }
+
+        registry::unregister(old);
+        registry::register(new);
+        self.sealed = Sealed::new(new as u64);
+    }
+}
+
+impl<T, P: DropPolicy> EncryptedPtr<T, P> {
     /// Get the raw pointer by decrypting.
     #[inline(always)]
     fn decrypt_ptr(&self) -> *mut T {
-        // decrypt the pointer.
-        let ptr_val = self.method.decrypt(self.encrypted_ptr, self.key);
-        ptr_val as *mut T
+        self.sealed.get() as *mut T
     }
-}
-
-impl<T> Deref for EncryptedPtr<T> {
-    type Target = T;
 
+    /// Borrow the pointee, decrypting the address; what `Deref` does, spelled out.
+    ///
+    /// With the `explicit-access` feature this is the only way in, so every
+    /// decryption shows up in the source.
     #[inline(always)]
-    fn deref(&self) -> &Self::Target {
+    pub fn access(&self) -> &T {
+        observer::observe(Access::Deref, type_name::<T>());
+        jitter::jitter();
+
         // here's where the decryption actually happens on each access.
         let ptr = self.decrypt_ptr();
+        #[cfg(all(windows, feature = "veh"))]
+        veh::record(ptr as u64, size_of::<T>(), type_name::<T>());
 
         unsafe { &*ptr }
     }
-}
 
-impl<T> DerefMut for EncryptedPtr<T> {
+    /// Mutable counterpart of [`EncryptedPtr::access`].
+    ///
+    /// Also re-encrypts the address if it predates a [`rotate_master_key`].
     #[inline(always)]
-    fn deref_mut(&mut self) -> &mut Self::Target {
+    pub fn access_mut(&mut self) -> &mut T {
+        observer::observe(Access::DerefMut, type_name::<T>());
+        jitter::jitter();
+        self.sealed.migrate();
+
         // Decrypt on mutable access as well
         let ptr = self.decrypt_ptr();
+        #[cfg(all(windows, feature = "veh"))]
+        veh::record(ptr as u64, size_of::<T>(), type_name::<T>());
         unsafe { &mut *ptr }
     }
+
+    /// Borrow the pointee on a thread with hard deadlines, e.g. audio or physics.
+    ///
+    /// Never allocates, locks, blocks or panics, and runs in constant time:
+    /// it only decrypts the closed-form schedule of a built-in method, skipping
+    /// the access observer and timing jitter. Returns `None` instead when that
+    /// isn't possible: for pointers made [`with_method`](EncryptedPtr::with_method)
+    /// with a method that isn't built in, while a sealed master key is evicted,
+    /// once poisoned, or (with `mac`) on a MAC mismatch, which then goes
+    /// unreported until the next [`access`](EncryptedPtr::access).
+    #[inline(always)]
+    pub fn access_realtime(&self) -> Option<&T> {
+        let ptr = self.sealed.get_realtime()? as *mut T;
+        Some(unsafe { &*ptr })
+    }
+
+    /// Mutable counterpart of [`EncryptedPtr::access_realtime`].
+    #[inline(always)]
+    pub fn access_realtime_mut(&mut self) -> Option<&mut T> {
+        let ptr = self.sealed.get_realtime()? as *mut T;
+        Some(unsafe { &mut *ptr })
+    }
+
+    /// Re-encrypt the address under the current key version if it predates a [`rotate_master_key`].
+    ///
+    /// What [`access_mut`](EncryptedPtr::access_mut) does first, for pointers
+    /// that are only ever read. Returns whether the pointer was migrated.
+    pub fn migrate(&mut self) -> bool {
+        self.sealed.migrate()
+    }
+
+    /// Re-encrypt the address under a fresh key and method, leaving the pointee in place.
+    ///
+    /// Like [`relocate`](EncryptedPtr::relocate), this switches to the default
+    /// rotation even if this pointer was created with a specific method.
+    pub fn rekey(&mut self) {
+        stats::rekeyed();
+        observer::observe(Access::Rekey, type_name::<T>());
+        self.sealed = Sealed::new(self.sealed.get());
+    }
+
+    /// Decrypt the raw pointer, e.g. to hand it to a C API for the duration of a call.
+    ///
+    /// # Safety
+    /// The pointer is only valid while `self` is alive, and the usual aliasing
+    /// rules apply to it. The plaintext address is unprotected, so don't store
+    /// it anywhere.
+    #[inline(always)]
+    pub unsafe fn as_ptr(&self) -> *mut T {
+        self.decrypt_ptr()
+    }
+
+    /// Consume the encrypted pointer without dropping the pointee, like [`Box::into_raw`].
+    ///
+    /// The caller becomes responsible for the memory; re-wrap it with
+    /// [`EncryptedPtr::new`] or free it with [`Box::from_raw`].
+    pub fn into_raw(self) -> *mut T {
+        let mut this = ManuallyDrop::new(self);
+        let ptr = this.decrypt_ptr();
+        registry::unregister(ptr);
+
+        // the sealed address may still own its method.
+        unsafe { ptr::drop_in_place(&mut this.sealed) };
+        ptr
+    }
+
+    /// Give up ownership of the pointee without dropping or freeing it, e.g. once it belongs to C code.
+    ///
+    /// Unlike [`mem::forget`](std::mem::forget), the encrypted pointer's own
+    /// bookkeeping is still released.
+    pub fn forget(self) {
+        self.into_raw();
+    }
+
+    /// Switch to drop policy `Q`, keeping the same ciphertext.
+    pub fn with_policy<Q: DropPolicy>(self) -> EncryptedPtr<T, Q> {
+        let this = ManuallyDrop::new(self);
+
+        EncryptedPtr {
+            sealed: unsafe { ptr::read(&this.sealed) },
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(not(feature = "explicit-access"), test))]
+impl<T, P: DropPolicy> Deref for EncryptedPtr<T, P> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.access()
+    }
+}
+
+#[cfg(any(not(feature = "explicit-access"), test))]
+impl<T, P: DropPolicy> DerefMut for EncryptedPtr<T, P> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.access_mut()
+    }
 }
 
-impl<T> Drop for EncryptedPtr<T> {
+impl<T, P: DropPolicy> Drop for EncryptedPtr<T, P> {
     #[inline(always)]
     fn drop(&mut self) {
+        // leak rather than free memory we may no longer be able to locate.
+        if master::is_poisoned() {
+            return;
+        }
+
+        observer::observe(Access::Drop, type_name::<T>());
+
         // decrypt the pointer.
         let ptr = self.decrypt_ptr();
+        registry::unregister(ptr);
 
-        unsafe {
-            // drop the T from the pointer.
-            std::ptr::drop_in_place(ptr);
-            // deallocate the T from the pointer.
-            std::alloc::dealloc(ptr as *mut u8, std::alloc::Layout::new::<T>());
-        }
+        // drop and release the T per the policy.
+        unsafe { P::drop_pointee(ptr) };
     }
 }
 
-impl<T> From<Box<T>> for EncryptedPtr<T> {
+impl<T, P: DropPolicy> From<Box<T>> for EncryptedPtr<T, P> {
     fn from(value: Box<T>) -> Self {
-        Self::new(Box::into_raw(value))
+        EncryptedPtr::new(Box::into_raw(value)).with_policy()
+    }
+}
+
+impl<T, P: DropPolicy> From<T> for EncryptedPtr<T, P> {
+    fn from(value: T) -> Self {
+        Box::new(value).into()
+    }
+}
+
+impl<T, P: DropPolicy> From<NonNull<T>> for EncryptedPtr<T, P> {
+    fn from(value: NonNull<T>) -> Self {
+        EncryptedPtr::new(value.as_ptr()).with_policy()
+    }
+}
+
+/// Error converting a null raw pointer into an [`EncryptedPtr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NullPointerError;
+
+impl fmt::Display for NullPointerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("pointer is null")
+    }
+}
+
+impl Error for NullPointerError {}
+
+/// The allocator couldn't provide memory for a protected allocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
     }
 }
 
-impl<T: fmt::Debug> fmt::Debug for EncryptedPtr<T> {
+impl Error for AllocError {}
+
+impl From<TryReserveError> for AllocError {
+    fn from(_: TryReserveError) -> Self {
+        AllocError
+    }
+}
+
+impl<T, P: DropPolicy> TryFrom<*mut T> for EncryptedPtr<T, P> {
+    type Error = NullPointerError;
+
+    fn try_from(value: *mut T) -> Result<Self, Self::Error> {
+        NonNull::new(value).map(Self::from).ok_or(NullPointerError)
+    }
+}
+
+impl<T: fmt::Debug, P: DropPolicy> fmt::Debug for EncryptedPtr<T, P> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedPtr");
         f.debug_struct("EncryptedPtr")
-            .field("encrypted_value", &format!("{:#x}", self.encrypted_ptr))
-            .field("pointed_value", &self.deref())
+            .field("encrypted_value", &format!("{:#x}", self.sealed.ciphertext()))
+            .field("pointed_value", self.access())
             .finish()
     }
 }
 
-struct MethodA;
+/// Fast XOR/rotate mixing with a key-dependent rotation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MethodA;
+
+impl MethodA {
+    /// Closed form of [`MethodA`]: decryption is `data.rotate_right(rot) ^ mask`.
+    #[inline(always)]
+    pub(crate) fn schedule(key: u64) -> (u32, u64) {
+        let r = (key & 0xF) as u32;
+        let mask = key.rotate_left(11).rotate_right(7 + r) ^ (key << 3).rotate_right(r) ^ key;
+        (7 + r, mask)
+    }
+}
 impl Encrypt for MethodA {
     #[inline(always)]
     fn encrypt(&self, mut data: u64, key: u64) -> u64 {
@@ -134,7 +554,17 @@ impl Encrypt for MethodA {
     }
 }
 
-struct MethodB;
+/// Fast XOR/rotate mixing with fixed rotations.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MethodB;
+
+impl MethodB {
+    /// Closed form of [`MethodB`]: decryption is `data.rotate_right(rot) ^ mask`.
+    #[inline(always)]
+    pub(crate) fn schedule(key: u64) -> (u32, u64) {
+        (22, key.rotate_right(5) ^ key.rotate_right(8) ^ key)
+    }
+}
 impl Encrypt for MethodB {
     #[inline(always)]
     fn encrypt(&self, mut data: u64, key: u64) -> u64 {
@@ -157,7 +587,19 @@ impl Encrypt for MethodB {
     }
 }
 
-struct MethodC;
+/// Fast XOR/rotate mixing with a key-dependent rotation and shifted key.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MethodC;
+
+impl MethodC {
+    /// Closed form of [`MethodC`]: decryption is `data.rotate_right(rot) ^ mask`.
+    #[inline(always)]
+    pub(crate) fn schedule(key: u64) -> (u32, u64) {
+        // the two whole-key XORs cancel out.
+        let r = (key % 31) as u32;
+        (r, (key ^ (key >> 11)).rotate_right(r))
+    }
+}
 impl Encrypt for MethodC {
     #[inline(always)]
     fn encrypt(&self, mut data: u64, key: u64) -> u64 {
@@ -186,6 +628,46 @@ impl Encrypt for MethodC {
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use std::alloc::{GlobalAlloc, System};
+    use std::cell::Cell;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counts allocations per thread, so tests can assert a path never allocates.
+    struct CountingAllocator;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn realtime_access_never_allocates() {
+        let mut position = EncryptedPtr::with_method(Box::into_raw(Box::new(0u64)), MethodA);
+        let custom = EncryptedPtr::with_method(Box::into_raw(Box::new(0u64)), Speck::default());
+
+        let before = ALLOCATIONS.with(Cell::get);
+        for _ in 0..1000 {
+            *position.access_realtime_mut().unwrap() += 3;
+        }
+        assert_eq!(ALLOCATIONS.with(Cell::get), before);
+        assert_eq!(position.access_realtime(), Some(&3000));
+
+        // full ciphers go through the regular path.
+        assert_eq!(custom.access_realtime().is_none(), !cfg!(passthrough));
+        assert_eq!(*custom, 0);
+    }
 
     #[test]
     fn decrypt_ptr_box() {
@@ -195,11 +677,143 @@ mod tests {
         }
 
         // turn box into encrypted pointer.
-        let player: EncryptedPtr<_> = Box::new(Player { health: 100 }).into();
+        let player: EncryptedPtr<Player> = Box::new(Player { health: 100 }).into();
 
         assert_eq!(player.health, 100);
     }
 
+    #[test]
+    fn decrypt_ptr_value() {
+        let health: EncryptedPtr<u32> = 100.into();
+
+        assert_eq!(*health, 100);
+    }
+
+    #[test]
+    fn try_from_value() {
+        let health = EncryptedPtr::try_from_value(100u32).unwrap();
+        let unit = EncryptedPtr::try_from_value(()).unwrap();
+
+        assert_eq!(*health, 100);
+        assert_eq!(*unit, ());
+    }
+
+    #[test]
+    fn explicit_access() {
+        let mut health: EncryptedPtr<u32> = 100.into();
+        *health.access_mut() -= 25;
+
+        assert_eq!(*health.access(), 75);
+    }
+
+    #[test]
+    fn relocate_moves_pointee() {
+        let mut health: EncryptedPtr<u32> = 100.into();
+        let before = unsafe { health.as_ptr() };
+        health.relocate();
+
+        assert_ne!(unsafe { health.as_ptr() }, before);
+        assert_eq!(*health, 100);
+    }
+
+    #[test]
+    fn decrypt_ptr_with_method() {
+        let health = EncryptedPtr::with_method(Box::into_raw(Box::new(100u32)), Speck::new());
+
+        assert_eq!(*health, 100);
+    }
+
+    #[test]
+    fn into_raw_and_back() {
+        let health: EncryptedPtr<u32> = 100.into();
+        let raw = health.into_raw();
+
+        unsafe { *raw += 1 };
+
+        let health = EncryptedPtr::new(raw);
+        assert_eq!(unsafe { health.as_ptr() }, raw);
+        assert_eq!(*health, 101);
+    }
+
+    #[test]
+    fn try_from_raw() {
+        let health = EncryptedPtr::<u32>::try_from(Box::into_raw(Box::new(100u32))).unwrap();
+
+        assert_eq!(*health, 100);
+        assert_eq!(
+            EncryptedPtr::<u32>::try_from(ptr::null_mut()).unwrap_err(),
+            NullPointerError
+        );
+    }
+
+    #[cfg(not(any(feature = "paranoid", feature = "diffused", passthrough)))]
+    #[test]
+    fn two_words() {
+        assert_eq!(size_of::<EncryptedPtr<u64>>(), 16);
+    }
+
+    #[test]
+    fn owned_method_dropped() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        /// An owned method that counts its drops.
+        struct Counted(MethodB);
+        impl Encrypt for Counted {
+            fn encrypt(&self, data: u64, key: u64) -> u64 {
+                self.0.encrypt(data, key)
+            }
+
+            fn decrypt(&self, data: u64, key: u64) -> u64 {
+                self.0.decrypt(data, key)
+            }
+        }
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let health = EncryptedPtr::with_method(Box::into_raw(Box::new(100u32)), Counted(MethodB));
+        assert_eq!(*health, 100);
+
+        drop(health);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+    }
+
+    #[cfg(passthrough)]
+    #[test]
+    fn passthrough_stores_plain_address() {
+        let ptr = Box::into_raw(Box::new(100u32));
+        let health = EncryptedPtr::new(ptr);
+
+        assert_eq!(health.sealed.ciphertext(), ptr as u64);
+        assert_eq!(*health, 100);
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn decrypt_ptr_protect() {
+        let health = protect!(100u32);
+        let name = protect!(String::from("player"));
+
+        assert_eq!(*health, 100);
+        assert_eq!(*name, "player");
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn const_keys_differ_per_call_site() {
+        const FIRST: u64 = const_key!();
+        const SECOND: u64 = const_key!();
+        // any variable cargo sets while compiling stands in for a build secret.
+        const DERIVED: u64 = const_key!("CARGO_PKG_NAME");
+
+        assert_ne!(FIRST, SECOND);
+        assert_ne!(DERIVED, 0);
+    }
+
     #[test]
     fn decrypt_value_a() {
         let key: u64 = 0x1234567890ABCDEF;
@@ -228,6 +842,25 @@ mod tests {
         assert_eq!(data, decrypted);
     }
 
+    #[test]
+    fn schedules_match_methods() {
+        type Schedule = fn(u64) -> (u32, u64);
+        let methods: [(&dyn Encrypt, Schedule); 3] = [
+            (&MethodA, MethodA::schedule),
+            (&MethodB, MethodB::schedule),
+            (&MethodC, MethodC::schedule),
+        ];
+
+        for _ in 0..1000 {
+            let (key, data) = (rand::random::<u64>(), rand::random::<u64>());
+
+            for (method, schedule) in methods {
+                let (rot, mask) = schedule(key);
+                assert_eq!(data.rotate_right(rot) ^ mask, method.decrypt(data, key));
+            }
+        }
+    }
+
     #[test]
     fn decrypt_value_c() {
         let key: u64 = 0x1234567890ABCDEF;
@@ -0,0 +1,185 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+use crate::diag::diag;
+use crate::{wipe, EncryptedString};
+
+/// Word `index` of the keystream literals are embedded under; `pointerguard-macros` mirrors it.
+#[inline(always)]
+fn keystream(key: u64, index: usize) -> u64 {
+    // splitmix64 over the word index.
+    let mut x = key.wrapping_add((index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Decrypt embedded `ciphertext` into a fresh buffer.
+pub(crate) fn decrypt(ciphertext: &[u8], key: u64) -> Vec<u8> {
+    ciphertext
+        .chunks(8)
+        .enumerate()
+        .flat_map(|(index, chunk)| {
+            let stream = keystream(key, index).to_le_bytes();
+            chunk.iter().zip(stream).map(|(byte, stream)| byte ^ stream)
+        })
+        .collect()
+}
+
+/// A decrypted literal, shareable from a `static`.
+struct Decrypted(EncryptedString);
+
+// made by `From<String>`, which only picks built-in and registered methods,
+// and those are all `Send + Sync`.
+unsafe impl Send for Decrypted {}
+unsafe impl Sync for Decrypted {}
+
+/// A string literal embedded encrypted by [`encrypted_str!`](crate::encrypted_str), decrypted on first use.
+///
+/// The binary only holds the ciphertext and its per-build key, so the
+/// literal doesn't show up in `strings` output; once decrypted it lives in an
+/// [`EncryptedString`] for the rest of the process.
+pub struct EncryptedLiteral {
+    ciphertext: &'static [u8],
+    key: u64,
+    decrypted: OnceLock<Decrypted>,
+}
+
+impl EncryptedLiteral {
+    #[doc(hidden)]
+    pub const fn new(ciphertext: &'static [u8], key: u64) -> Self {
+        Self {
+            ciphertext,
+            key,
+            decrypted: OnceLock::new(),
+        }
+    }
+
+    /// The literal, decrypting it on the first call.
+    pub fn get(&'static self) -> &'static EncryptedString {
+        let decrypted = self.decrypted.get_or_init(|| {
+            let literal = String::from_utf8(decrypt(self.ciphertext, self.key))
+                .expect(diag!("encrypted literal isn't UTF-8"));
+            Decrypted(literal.into())
+        });
+        &decrypted.0
+    }
+}
+
+impl fmt::Debug for EncryptedLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedLiteral");
+        f.debug_struct("EncryptedLiteral")
+            .field("len", &self.ciphertext.len())
+            .field("decrypted", &self.decrypted.get().is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A byte array embedded encrypted by [`encrypted_bytes!`](crate::encrypted_bytes).
+///
+/// Only readable through [`EncryptedBlob::access`], whose guard holds a
+/// decrypted copy and wipes it when dropped; the binary and the blob itself
+/// only ever hold the ciphertext, under a key that changes every build.
+///
+/// ```
+/// static SIGNATURES: pointerguard::EncryptedBlob =
+///     pointerguard::encrypted_bytes!([0x48, 0x8B, 0x05, 0x90]);
+///
+/// assert_eq!(&*SIGNATURES.access(), &[0x48, 0x8B, 0x05, 0x90]);
+/// ```
+#[derive(Clone, Copy)]
+pub struct EncryptedBlob {
+    ciphertext: &'static [u8],
+    key: u64,
+}
+
+impl EncryptedBlob {
+    #[doc(hidden)]
+    pub const fn new(ciphertext: &'static [u8], key: u64) -> Self {
+        Self { ciphertext, key }
+    }
+
+    /// Length of the blob in bytes.
+    pub const fn len(&self) -> usize {
+        self.ciphertext.len()
+    }
+
+    /// Whether the blob is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.ciphertext.is_empty()
+    }
+
+    /// Decrypt a copy of the blob, wiped when the guard drops.
+    pub fn access(&self) -> BlobGuard {
+        BlobGuard {
+            bytes: decrypt(self.ciphertext, self.key),
+        }
+    }
+}
+
+impl fmt::Debug for EncryptedBlob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedBlob");
+        f.debug_struct("EncryptedBlob")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A decrypted copy of an [`EncryptedBlob`], wiped on drop.
+pub struct BlobGuard {
+    bytes: Vec<u8>,
+}
+
+impl Deref for BlobGuard {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Drop for BlobGuard {
+    fn drop(&mut self) {
+        unsafe { wipe::wipe(self.bytes.as_mut_ptr(), self.bytes.len()) };
+    }
+}
+
+impl fmt::Debug for BlobGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "BlobGuard");
+        f.debug_struct("BlobGuard")
+            .field("len", &self.bytes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "macros"))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn literals_decrypt_once() {
+        let url = || crate::encrypted_str!("https://auth.example.com/v1/session");
+
+        assert_eq!(&**url(), "https://auth.example.com/v1/session");
+        assert!(std::ptr::eq(url(), url()));
+        assert_eq!(&**crate::encrypted_str!(""), "");
+        assert_eq!(decrypt(&decrypt(b"key name", 7), 7), b"key name");
+    }
+
+    #[test]
+    fn blobs_decrypt_through_guards() {
+        static CONFIG: EncryptedBlob = crate::encrypted_bytes!(b"fov=90\nsens=2.5");
+        static LICENSE: EncryptedBlob = crate::encrypted_bytes!(include_bytes!("LICENSE"));
+
+        assert_eq!(&*CONFIG.access(), b"fov=90\nsens=2.5");
+        assert!(CONFIG.ciphertext != b"fov=90\nsens=2.5");
+        assert_eq!(&*LICENSE.access(), include_bytes!("../LICENSE"));
+        assert_eq!(crate::encrypted_bytes!([1, 2, 0xFF]).len(), 3);
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::mem::ManuallyDrop;
+#[cfg(any(not(feature = "explicit-access"), test))]
+use std::ops::{Deref, DerefMut};
+
+use crate::{Dealloc, DropPolicy, EncryptedPtr};
+
+/// An [`EncryptedPtr`] that never drops or frees its pointee, like [`ManuallyDrop`] for a `Box`.
+///
+/// For pointees whose ownership has moved elsewhere (e.g. through FFI) but
+/// which should stay protected while still in use here. Dropping the wrapper
+/// only releases the encrypted pointer's own bookkeeping.
+pub struct ManuallyDroppedEncryptedPtr<T, P: DropPolicy = Dealloc> {
+    inner: ManuallyDrop<EncryptedPtr<T, P>>,
+}
+
+impl<T, P: DropPolicy> ManuallyDroppedEncryptedPtr<T, P> {
+    /// Stop `ptr` from dropping its pointee.
+    pub fn new(ptr: EncryptedPtr<T, P>) -> Self {
+        Self {
+            inner: ManuallyDrop::new(ptr),
+        }
+    }
+
+    /// Take ownership of the pointee again.
+    pub fn into_inner(self) -> EncryptedPtr<T, P> {
+        let mut this = ManuallyDrop::new(self);
+        unsafe { ManuallyDrop::take(&mut this.inner) }
+    }
+
+    /// Borrow the pointee, see [`EncryptedPtr::access`].
+    pub fn access(&self) -> &T {
+        self.inner.access()
+    }
+
+    /// Mutably borrow the pointee, see [`EncryptedPtr::access_mut`].
+    pub fn access_mut(&mut self) -> &mut T {
+        self.inner.access_mut()
+    }
+}
+
+impl<T, P: DropPolicy> From<EncryptedPtr<T, P>> for ManuallyDroppedEncryptedPtr<T, P> {
+    fn from(value: EncryptedPtr<T, P>) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(any(not(feature = "explicit-access"), test))]
+impl<T, P: DropPolicy> Deref for ManuallyDroppedEncryptedPtr<T, P> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.access()
+    }
+}
+
+#[cfg(any(not(feature = "explicit-access"), test))]
+impl<T, P: DropPolicy> DerefMut for ManuallyDroppedEncryptedPtr<T, P> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.access_mut()
+    }
+}
+
+impl<T, P: DropPolicy> Drop for ManuallyDroppedEncryptedPtr<T, P> {
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::take(&mut self.inner) }.forget();
+    }
+}
+
+impl<T: fmt::Debug, P: DropPolicy> fmt::Debug for ManuallyDroppedEncryptedPtr<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ManuallyDroppedEncryptedPtr");
+        f.debug_tuple("ManuallyDroppedEncryptedPtr")
+            .field(&*self.inner)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::rc::Rc;
+
+    #[test]
+    fn pointee_not_dropped() {
+        let shared = Rc::new(100u32);
+        let ptr: EncryptedPtr<Rc<u32>> = shared.clone().into();
+
+        let raw = unsafe { ptr.as_ptr() };
+        let manual = ManuallyDroppedEncryptedPtr::new(ptr);
+        assert_eq!(**manual, 100);
+
+        drop(manual);
+        assert_eq!(Rc::strong_count(&shared), 2);
+
+        // the caller still owns the pointee.
+        drop(unsafe { Box::from_raw(raw) });
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn forget_keeps_pointee() {
+        let shared = Rc::new(100u32);
+        let ptr: EncryptedPtr<Rc<u32>> = shared.clone().into();
+        let raw = unsafe { ptr.as_ptr() };
+
+        ptr.forget();
+        assert_eq!(Rc::strong_count(&shared), 2);
+
+        drop(unsafe { Box::from_raw(raw) });
+    }
+}
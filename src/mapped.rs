@@ -0,0 +1,360 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::ops::{Deref, DerefMut};
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::ptr;
+
+use crate::aes;
+use crate::chunked::{apply_keystream, ChunkGuard};
+use crate::diag::diag;
+use crate::master::MaskedKey;
+
+/// First word of every mapped buffer file.
+const MAGIC: u64 = u64::from_le_bytes(*b"PGMAPPED");
+
+/// Header words before the per-chunk generations: magic, key check, salt, length, chunk size.
+const HEADER_WORDS: usize = 5;
+
+/// Proof of the key stored in the header, so opening with the wrong one fails instead of decrypting garbage.
+fn key_check(key: u64, salt: u64) -> u64 {
+    aes::mac(key ^ salt, MAGIC)
+}
+
+/// The keystream key of a chunk in `generation`.
+fn chunk_key(key: u64, salt: u64, generation: u64) -> u64 {
+    key ^ salt ^ generation.rotate_left(32)
+}
+
+pub(crate) fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A whole file mapped shared and writable, unmapped on drop.
+pub(crate) struct Mapping {
+    pub(crate) ptr: *mut u8,
+    pub(crate) len: usize,
+    _file: File,
+}
+
+// owns the mapping; its users only write to it through `&mut self`, or atomically.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    /// Map `file`, which must hold at least `header_words` header words.
+    pub(crate) fn new(file: File, header_words: usize) -> io::Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        if len < header_words * 8 {
+            return Err(invalid(diag!("file too short for its header")));
+        }
+
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            ptr: ptr as *mut u8,
+            len,
+            _file: file,
+        })
+    }
+
+    /// Header word `index`.
+    pub(crate) fn word(&self, index: usize) -> u64 {
+        // the mapping is page-aligned, so every header word is aligned.
+        u64::from_le(unsafe { (self.ptr as *const u64).add(index).read() })
+    }
+
+    pub(crate) fn set_word(&mut self, index: usize, value: u64) {
+        unsafe { (self.ptr as *mut u64).add(index).write(value.to_le()) };
+    }
+
+    /// Write the mapping back to the file and wait for the disk.
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        match unsafe { libc::msync(self.ptr as *mut _, self.len, libc::MS_SYNC) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr as *mut _, self.len) };
+    }
+}
+
+/// A persistent byte buffer in a memory-mapped file, encrypted both on disk and in the mapping.
+///
+/// For large lookup tables that outlive the process: neither the file nor
+/// the mapped pages ever hold plaintext, and [`MappedBuffer::chunk`]
+/// decrypts a wiped copy of one chunk at a time, like a [`ChunkedBuffer`](crate::ChunkedBuffer).
+/// Writes through [`MappedBuffer::chunk_mut`] reseal their chunk in the
+/// mapping under its next generation; [`MappedBuffer::flush`] makes them
+/// durable.
+///
+/// The file is encrypted under a caller-supplied key rather than the
+/// per-process master key so it can be reopened, e.g. one from
+/// [`const_key!`](crate::const_key) or unsealed from a TPM. Changing the
+/// file while it is mapped, from this or another process, changes what
+/// later chunks decrypt to.
+pub struct MappedBuffer {
+    mapping: Mapping,
+    len: usize,
+    chunk_size: usize,
+    /// The file key, XORed with the master key.
+    key: MaskedKey,
+}
+
+impl MappedBuffer {
+    /// Write `bytes` encrypted under `key` to a new file at `path`, in chunks of `chunk_size` bytes, and map it.
+    ///
+    /// # Panics
+    /// If `chunk_size` is zero.
+    pub fn create(
+        path: impl AsRef<Path>,
+        bytes: &[u8],
+        chunk_size: usize,
+        key: u64,
+    ) -> io::Result<Self> {
+        assert!(chunk_size != 0, "{}", diag!("chunk size must be non-zero"));
+
+        let salt = rand::random();
+        let mut header = vec![
+            MAGIC,
+            key_check(key, salt),
+            salt,
+            bytes.len() as u64,
+            chunk_size as u64,
+        ];
+        header.resize(HEADER_WORDS + bytes.len().div_ceil(chunk_size), 0);
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        let header: Vec<u8> = header.iter().flat_map(|word| word.to_le_bytes()).collect();
+        file.write_all(&header)?;
+        for (index, chunk) in bytes.chunks(chunk_size).enumerate() {
+            let mut sealed = chunk.to_vec();
+            apply_keystream(chunk_key(key, salt, 0), index, &mut sealed);
+            file.write_all(&sealed)?;
+        }
+
+        Self::map(file, key)
+    }
+
+    /// Map an existing file made by [`MappedBuffer::create`].
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if it isn't one, or `key` isn't the key it was created with.
+    pub fn open(path: impl AsRef<Path>, key: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Self::map(file, key)
+    }
+
+    fn map(file: File, key: u64) -> io::Result<Self> {
+        let mapping = Mapping::new(file, HEADER_WORDS)?;
+        let (len, chunk_size) = (mapping.word(3) as usize, mapping.word(4) as usize);
+        let expected_len = (chunk_size != 0)
+            .then(|| len.div_ceil(chunk_size))
+            .and_then(|chunks| chunks.checked_add(HEADER_WORDS)?.checked_mul(8))
+            .and_then(|header| header.checked_add(len));
+        if mapping.word(0) != MAGIC || expected_len != Some(mapping.len) {
+            return Err(invalid(diag!("not a mapped buffer")));
+        }
+        if mapping.word(1) != key_check(key, mapping.word(2)) {
+            return Err(invalid(diag!("wrong key for this mapped buffer")));
+        }
+
+        Ok(Self {
+            mapping,
+            len,
+            chunk_size,
+            key: MaskedKey::new(key),
+        })
+    }
+
+    /// Length of the buffer in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Size of every chunk but possibly the last.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Number of chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.len.div_ceil(self.chunk_size)
+    }
+
+    /// Decrypt a copy of chunk `index`, wiped when the guard drops.
+    pub fn chunk(&self, index: usize) -> Option<ChunkGuard> {
+        (index < self.chunk_count()).then(|| ChunkGuard {
+            bytes: self.decrypt(index),
+        })
+    }
+
+    /// Decrypt chunk `index` for writing; it is resealed in the mapping when the guard drops.
+    pub fn chunk_mut(&mut self, index: usize) -> Option<MappedChunkMut<'_>> {
+        self.key.migrate();
+        (index < self.chunk_count()).then(|| MappedChunkMut {
+            bytes: self.decrypt(index),
+            buffer: self,
+            index,
+        })
+    }
+
+    /// Write resealed chunks back to the file and wait for the disk.
+    pub fn flush(&self) -> io::Result<()> {
+        self.mapping.flush()
+    }
+
+    /// Where chunk `index`'s ciphertext starts in the mapping, and its length.
+    fn span(&self, index: usize) -> (*mut u8, usize) {
+        let data = (HEADER_WORDS + self.chunk_count()) * 8;
+        let start = index * self.chunk_size;
+        let len = self.chunk_size.min(self.len - start);
+        (unsafe { self.mapping.ptr.add(data + start) }, len)
+    }
+
+    fn chunk_key(&self, index: usize) -> u64 {
+        let generation = self.mapping.word(HEADER_WORDS + index);
+        chunk_key(self.key.get(), self.mapping.word(2), generation)
+    }
+
+    fn decrypt(&self, index: usize) -> Vec<u8> {
+        let (start, len) = self.span(index);
+
+        // the mapping can change under us, so copy it out rather than borrow it.
+        let mut bytes = vec![0; len];
+        unsafe { ptr::copy_nonoverlapping(start, bytes.as_mut_ptr(), len) };
+        apply_keystream(self.chunk_key(index), index, &mut bytes);
+        bytes
+    }
+}
+
+impl fmt::Debug for MappedBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "MappedBuffer");
+        f.debug_struct("MappedBuffer")
+            .field("len", &self.len)
+            .field("chunk_size", &self.chunk_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Write access to one chunk of a [`MappedBuffer`], resealed on drop.
+pub struct MappedChunkMut<'a> {
+    buffer: &'a mut MappedBuffer,
+    index: usize,
+    bytes: Vec<u8>,
+}
+
+impl Deref for MappedChunkMut<'_> {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl DerefMut for MappedChunkMut<'_> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+}
+
+impl Drop for MappedChunkMut<'_> {
+    fn drop(&mut self) {
+        let generations = HEADER_WORDS + self.index;
+        let generation = self.buffer.mapping.word(generations) + 1;
+        self.buffer.mapping.set_word(generations, generation);
+
+        // sealing in place leaves no plaintext behind, then copy in without borrowing the mapping.
+        let key = self.buffer.chunk_key(self.index);
+        let (start, len) = self.buffer.span(self.index);
+        apply_keystream(key, self.index, &mut self.bytes);
+        unsafe { ptr::copy_nonoverlapping(self.bytes.as_ptr(), start, len) };
+    }
+}
+
+impl fmt::Debug for MappedChunkMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "MappedChunkMut");
+        f.debug_struct("MappedChunkMut")
+            .field("index", &self.index)
+            .field("len", &self.bytes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    const KEY: u64 = 0x7AB1_E5EE_D0F0_0001;
+
+    fn table() -> Vec<u8> {
+        (0..5000u32).map(|i| (i * 13 % 251) as u8).collect()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pointerguard-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn persists_encrypted() {
+        let path = temp_path("persists");
+        let mut buffer = MappedBuffer::create(&path, &table(), 1024, KEY).unwrap();
+        assert_eq!(&buffer.chunk(1).unwrap()[..], &table()[1024..2048]);
+
+        buffer.chunk_mut(4).unwrap()[..5].copy_from_slice(b"route");
+        buffer.flush().unwrap();
+        drop(buffer);
+
+        let (file, table) = (std::fs::read(&path).unwrap(), table());
+        assert!(!file.windows(64).any(|window| window == &table[2048..2112]));
+        assert!(!file.windows(5).any(|window| window == b"route"));
+
+        let reopened = MappedBuffer::open(&path, KEY).unwrap();
+        assert_eq!(reopened.chunk_count(), 5);
+        assert_eq!(&reopened.chunk(4).unwrap()[..5], b"route");
+        assert_eq!(&reopened.chunk(4).unwrap()[5..], &table[4101..]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wrong_key_fails_to_open() {
+        let path = temp_path("wrong-key");
+        drop(MappedBuffer::create(&path, b"lookup", 4, KEY).unwrap());
+
+        let error = MappedBuffer::open(&path, KEY ^ 1).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{AddAssign, SubAssign};
+
+use crate::diag::{diag, diag_panic};
+use crate::master::{self, MaskedKey};
+use crate::sealed::generate_key;
+
+/// A constant masked for comparison against one particular masked counter.
+///
+/// Made with e.g. [`MaskedU32::constant`]; only that counter can compare
+/// against it, since it shares the counter's mask. Comparing it to another
+/// counter panics.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct MaskedConst {
+    masked: u64,
+    owner: u64,
+}
+
+impl fmt::Debug for MaskedConst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "MaskedConst");
+        f.debug_struct("MaskedConst")
+            .field("masked_value", &format!("{:#x}", self.masked))
+            .finish_non_exhaustive()
+    }
+}
+
+macro_rules! masked_int {
+    ($($name:ident $ty:ident),*) => {$(
+        #[doc = concat!("A `", stringify!($ty), "` counter kept additively masked, that never decrypts to update or compare.")]
+        ///
+        /// Stores `value + mask` in 64 bits, with the random mask itself kept
+        /// XORed with the master key. Increments and decrements add to the
+        /// masked word directly, and comparisons subtract a [`MaskedConst`]
+        /// carrying the same mask, which leaves only the difference, so the
+        /// plaintext exists only when [`get`](Self::get) is called. Meant for
+        /// the hottest counters (ammo, currency), where even the transient
+        /// plaintext of [`EncryptedU32`](crate::EncryptedU32) is too much.
+        ///
+        /// Unlike the plain integer, the counter doesn't overflow at the
+        /// type's bounds: comparisons keep seeing e.g. `-1` after decrementing
+        /// past zero, while `get` wraps it.
+        pub struct $name {
+            masked: u64,
+            mask: MaskedKey,
+        }
+
+        impl $name {
+            /// Mask `value` under a fresh random mask.
+            pub fn new(value: $ty) -> Self {
+                let mask = if cfg!(passthrough) { 0 } else { generate_key() };
+                Self {
+                    masked: (value as i64 as u64).wrapping_add(mask),
+                    mask: MaskedKey::new(mask),
+                }
+            }
+
+            #[inline(always)]
+            fn mask(&self) -> u64 {
+                self.mask.get()
+            }
+
+            /// Unmask the value.
+            pub fn get(&self) -> $ty {
+                if master::is_poisoned() {
+                    diag_panic!("protected pointers have been poisoned");
+                }
+
+                self.masked.wrapping_sub(self.mask()) as $ty
+            }
+
+            /// Replace the value, keeping the mask so existing constants still compare.
+            pub fn set(&mut self, value: $ty) {
+                self.masked = (value as i64 as u64).wrapping_add(self.mask());
+            }
+
+            /// Add `amount` without unmasking.
+            #[inline(always)]
+            pub fn increment_by(&mut self, amount: $ty) {
+                self.masked = self.masked.wrapping_add(amount as i64 as u64);
+            }
+
+            /// Subtract `amount` without unmasking.
+            #[inline(always)]
+            pub fn decrement_by(&mut self, amount: $ty) {
+                self.masked = self.masked.wrapping_sub(amount as i64 as u64);
+            }
+
+            /// Add one without unmasking.
+            #[inline(always)]
+            pub fn increment(&mut self) {
+                self.increment_by(1);
+            }
+
+            /// Subtract one without unmasking.
+            #[inline(always)]
+            pub fn decrement(&mut self) {
+                self.decrement_by(1);
+            }
+
+            /// Mask `value` for comparing against this counter, e.g. a price or zero.
+            ///
+            /// Build these once and keep them; they stay valid across updates
+            /// and [`set`](Self::set).
+            pub fn constant(&self, value: $ty) -> MaskedConst {
+                MaskedConst {
+                    masked: (value as i64 as u64).wrapping_add(self.mask()),
+                    owner: self.mask.masked(),
+                }
+            }
+
+            /// Order the counter against `constant`, seeing only their difference.
+            ///
+            /// # Panics
+            /// If `constant` was made by another counter.
+            #[inline(always)]
+            pub fn compare(&self, constant: &MaskedConst) -> Ordering {
+                assert!(
+                    constant.owner == self.mask.masked(),
+                    "{}",
+                    diag!("masked constant belongs to another counter")
+                );
+
+                (self.masked.wrapping_sub(constant.masked) as i64).cmp(&0)
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new(0)
+            }
+        }
+
+        impl Clone for $name {
+            /// The clone has its own mask, so this counter's constants don't apply to it.
+            fn clone(&self) -> Self {
+                Self::new(self.get())
+            }
+        }
+
+        impl From<$ty> for $name {
+            fn from(value: $ty) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl AddAssign<$ty> for $name {
+            #[inline(always)]
+            fn add_assign(&mut self, amount: $ty) {
+                self.increment_by(amount);
+            }
+        }
+
+        impl SubAssign<$ty> for $name {
+            #[inline(always)]
+            fn sub_assign(&mut self, amount: $ty) {
+                self.decrement_by(amount);
+            }
+        }
+
+        impl PartialEq<MaskedConst> for $name {
+            fn eq(&self, constant: &MaskedConst) -> bool {
+                self.compare(constant) == Ordering::Equal
+            }
+        }
+
+        impl PartialOrd<MaskedConst> for $name {
+            fn partial_cmp(&self, constant: &MaskedConst) -> Option<Ordering> {
+                Some(self.compare(constant))
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                crate::diag::opaque_debug!(f, stringify!($name));
+                f.debug_struct(stringify!($name))
+                    .field("masked_value", &format!("{:#x}", self.masked))
+                    .finish_non_exhaustive()
+            }
+        }
+    )*};
+}
+
+masked_int!(MaskedU32 u32, MaskedI32 i32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn updates_and_compares_while_masked() {
+        let mut ammo = MaskedU32::new(2);
+        let empty = ammo.constant(0);
+        let full = ammo.constant(30);
+
+        ammo += 28;
+        assert!(ammo == full);
+        ammo.decrement();
+        assert!(ammo < full && ammo > empty);
+
+        ammo.set(0);
+        ammo.decrement();
+        assert!(ammo < empty);
+        assert_eq!(ammo.get(), u32::MAX);
+
+        let mut balance = MaskedI32::new(-5);
+        balance -= 10;
+        assert_eq!(balance.compare(&balance.constant(-15)), Ordering::Equal);
+        assert_eq!(balance.get(), -15);
+    }
+
+    #[cfg(not(any(passthrough, feature = "opaque-diagnostics")))]
+    #[test]
+    #[should_panic(expected = "another counter")]
+    fn constants_are_per_counter() {
+        let gold = MaskedU32::new(10);
+        let _ = MaskedU32::new(10).compare(&gold.constant(10));
+    }
+}
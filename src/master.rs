@@ -0,0 +1,521 @@
+use crate::diag::diag_panic;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Process-wide secret of every key version still in memory, mixed into every pointer key.
+///
+/// Zero for versions never used or forgotten, and for the current one until
+/// first use or while it is evicted, see [`seal_master_key`].
+static KEYS: [AtomicU64; 256] = [const { AtomicU64::new(0) }; 256];
+
+/// The key version new pointers are encrypted under, see [`rotate_master_key`].
+static VERSION: AtomicU8 = AtomicU8::new(0);
+
+/// Pointers and keys currently encrypted under each version.
+///
+/// A version is only forgotten or reused once nothing is left under it.
+static LIVE: [AtomicUsize; 256] = [const { AtomicUsize::new(0) }; 256];
+
+/// Set once keys have been scrambled or pointers poisoned; never cleared.
+static POISONED: AtomicBool = AtomicBool::new(false);
+
+/// Set once the master key has been sealed with [`seal_master_key`]; never cleared.
+static SEALED: AtomicBool = AtomicBool::new(false);
+
+/// The sealer and sealed master key, once [`seal_master_key`] has run.
+///
+/// Also held while changing versions, so rotation never races unsealing.
+static SEALER: Mutex<Option<SealedKey>> = Mutex::new(None);
+
+struct SealedKey {
+    sealer: Box<dyn KeySealer>,
+    blob: Vec<u8>,
+}
+
+/// The current key version, and its master key, generated or unsealed if it isn't in memory.
+///
+/// With the `image-bound` feature the stored key is combined with
+/// [`image_key`] first, see there.
+#[cfg(any(not(passthrough), feature = "ffi", test))]
+#[inline(always)]
+pub(crate) fn current() -> (u8, u64) {
+    let version = version();
+    (version, stored(version) ^ image_key())
+}
+
+/// The key version new pointers are encrypted under.
+#[inline(always)]
+pub(crate) fn version() -> u8 {
+    VERSION.load(Ordering::Acquire)
+}
+
+/// The master key of `version`, or `None` once it is forgotten.
+#[inline(always)]
+pub(crate) fn for_version(version: u8) -> Option<u64> {
+    match KEYS[version as usize].load(Ordering::Relaxed) {
+        // the current key may just be evicted.
+        0 if version == self::version() => Some(stored(version) ^ image_key()),
+        0 => None,
+        master => Some(master ^ image_key()),
+    }
+}
+
+/// The master key of `version`, for something counted as live under it.
+///
+/// # Panics
+/// If `version` was forgotten anyway, which only a leaked count can cause.
+#[inline(always)]
+pub(crate) fn for_live_version(version: u8) -> u64 {
+    match for_version(version) {
+        Some(master) => master,
+        None => diag_panic!("key was encrypted under a forgotten master key"),
+    }
+}
+
+/// The master key of `version` if it is in memory, without generating or unsealing it.
+///
+/// Only an atomic load, for callers that can't block, see
+/// [`EncryptedPtr::access_realtime`](crate::EncryptedPtr::access_realtime).
+#[cfg(not(passthrough))]
+#[inline(always)]
+pub(crate) fn resident(version: u8) -> Option<u64> {
+    match KEYS[version as usize].load(Ordering::Relaxed) {
+        0 => None,
+        master => Some(master ^ image_key()),
+    }
+}
+
+/// The current key version and its master key, counting something encrypted under it.
+///
+/// The count lasts until [`release`]d. It is taken before the key is read,
+/// so [`forget_retired_keys`] can't wipe the key in between; if a rotation
+/// got in first, the count moves on to the new version.
+#[inline(always)]
+pub(crate) fn acquire_current() -> (u8, u64) {
+    loop {
+        let version = version();
+        acquire(version);
+
+        // seqcst pairs with rotation: either forgetting sees the count, or this sees the new version.
+        if VERSION.load(Ordering::SeqCst) == version {
+            return (version, stored(version) ^ image_key());
+        }
+        release(version);
+    }
+}
+
+#[inline(always)]
+fn acquire(version: u8) {
+    LIVE[version as usize].fetch_add(1, Ordering::SeqCst);
+}
+
+/// Stop counting something encrypted under `version`, once dropped or re-encrypted.
+#[inline(always)]
+pub(crate) fn release(version: u8) {
+    LIVE[version as usize].fetch_sub(1, Ordering::Release);
+}
+
+/// A secret stored XORed with a master key, remembering its version so it survives rotation.
+///
+/// Counts as live under that version until dropped or migrated, like a
+/// protected pointer.
+pub(crate) struct MaskedKey {
+    masked: u64,
+    version: u8,
+}
+
+impl MaskedKey {
+    /// Mask `key` under the current version.
+    pub(crate) fn new(key: u64) -> Self {
+        let (version, master) = acquire_current();
+        Self {
+            masked: key ^ master,
+            version,
+        }
+    }
+
+    /// The key, unmasked.
+    #[inline(always)]
+    pub(crate) fn get(&self) -> u64 {
+        self.masked ^ for_live_version(self.version)
+    }
+
+    /// The masked word, which tells keys apart without unmasking them.
+    #[inline(always)]
+    pub(crate) fn masked(&self) -> u64 {
+        self.masked
+    }
+
+    /// Whether this was masked under the current version.
+    #[inline(always)]
+    pub(crate) fn is_current(&self) -> bool {
+        self.version == version()
+    }
+
+    /// Re-mask under the current version if this was masked under an older one.
+    pub(crate) fn migrate(&mut self) {
+        if !self.is_current() {
+            *self = Self::new(self.get());
+        }
+    }
+}
+
+impl Drop for MaskedKey {
+    fn drop(&mut self) {
+        release(self.version);
+    }
+}
+
+/// The stored key of `version`, before binding it to the image.
+#[inline(always)]
+fn stored(version: u8) -> u64 {
+    match KEYS[version as usize].load(Ordering::Relaxed) {
+        0 => init(version),
+        master => master,
+    }
+}
+
+/// Never called, only its address matters: it moves with the loaded image under ASLR.
+#[cfg(feature = "image-bound")]
+#[inline(never)]
+fn anchor() {}
+
+/// A key component derived from where this image was loaded.
+///
+/// Mixing it into the master key binds every key to this exact process
+/// image, so a dumped master key (or persisted sealed blob) and pointer pairs
+/// can't be replayed in another instance of the program.
+#[inline(always)]
+fn image_key() -> u64 {
+    #[cfg(feature = "image-bound")]
+    return (anchor as fn() as usize as u64)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        .rotate_left(29);
+
+    #[cfg(not(feature = "image-bound"))]
+    0
+}
+
+#[cold]
+fn init(version: u8) -> u64 {
+    if SEALED.load(Ordering::SeqCst) {
+        return unseal(version);
+    }
+
+    let fresh = rand::random::<u64>() | 1;
+    match KEYS[version as usize].compare_exchange(0, fresh, Ordering::Relaxed, Ordering::Relaxed) {
+        Ok(_) => fresh,
+        Err(current) => current,
+    }
+}
+
+/// Binds the master key to hardware, e.g. a TPM, so it can be kept out of memory.
+///
+/// Install one with [`seal_master_key`].
+pub trait KeySealer: Send {
+    /// Seal `key`, returning a blob only this machine can unseal.
+    fn seal(&self, key: u64) -> Result<Vec<u8>, SealError>;
+    /// Recover the key from a blob made by [`KeySealer::seal`].
+    fn unseal(&self, blob: &[u8]) -> Result<u64, SealError>;
+}
+
+/// A [`KeySealer`] failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SealError {
+    message: String,
+}
+
+impl SealError {
+    /// Create an error describing what failed.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SealError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key sealing failed: {}", self.message)
+    }
+}
+
+impl Error for SealError {}
+
+/// Seal the master key with `sealer` and evict it from memory.
+///
+/// Call once at startup. Afterwards the key is unsealed again on demand by
+/// the next pointer access, and [`evict_master_key`] drops the in-memory
+/// copy whenever the process goes idle, so a dump taken in between holds
+/// only the sealed blob. A later failure to unseal poisons the process.
+/// Keys retired by [`rotate_master_key`] stay in memory until forgotten.
+pub fn seal_master_key(sealer: impl KeySealer + 'static) -> Result<(), SealError> {
+    let mut slot = SEALER.lock().unwrap_or_else(|e| e.into_inner());
+    if slot.is_some() {
+        return Err(SealError::new("the master key is already sealed"));
+    }
+
+    let version = VERSION.load(Ordering::Relaxed);
+    let blob = sealer.seal(stored(version))?;
+    *slot = Some(SealedKey {
+        sealer: Box::new(sealer),
+        blob,
+    });
+    SEALED.store(true, Ordering::SeqCst);
+    KEYS[version as usize].store(0, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Drop the in-memory copy of a sealed master key until the next pointer access.
+///
+/// Does nothing unless [`seal_master_key`] has run.
+pub fn evict_master_key() {
+    if SEALED.load(Ordering::SeqCst) {
+        let _slot = SEALER.lock().unwrap_or_else(|e| e.into_inner());
+        KEYS[VERSION.load(Ordering::Relaxed) as usize].store(0, Ordering::SeqCst);
+    }
+}
+
+/// Switch protected pointers to a freshly generated master key, for zero-downtime key rotation.
+///
+/// New pointers are encrypted under the new key. Existing ones keep
+/// decrypting under the retired key they were encrypted with, and pointers
+/// are re-encrypted under the current one on their next mutable access, e.g.
+/// `access_mut`, or [`EncryptedPtr::migrate`](crate::EncryptedPtr::migrate).
+/// A sealed master key is resealed under the new one.
+///
+/// # Panics
+/// Keys are numbered by a version that wraps after 256 rotations. If the
+/// version to reuse still has pointers encrypted under it, rotating panics
+/// instead of letting them decrypt under the wrong key.
+pub fn rotate_master_key() {
+    let mut slot = SEALER.lock().unwrap_or_else(|e| e.into_inner());
+    let version = VERSION.load(Ordering::Relaxed);
+    let next = version.wrapping_add(1);
+    let live = LIVE[next as usize].load(Ordering::Acquire);
+    if live != 0 {
+        diag_panic!(
+            "master key version {} still has {} unmigrated pointers",
+            next,
+            live
+        );
+    }
+
+    // the outgoing key has to be in memory to be retired.
+    match slot.as_ref() {
+        Some(sealed) => drop(unseal_with(sealed, version)),
+        None => drop(stored(version)),
+    }
+
+    let fresh = rand::random::<u64>() | 1;
+    if let Some(sealed) = slot.as_mut() {
+        match sealed.sealer.seal(fresh) {
+            Ok(blob) => sealed.blob = blob,
+            Err(error) => {
+                poison();
+                diag_panic!("{}", error);
+            }
+        }
+    }
+
+    // published after the key, so a reader that sees the new version sees its key.
+    KEYS[next as usize].store(fresh, Ordering::Relaxed);
+    VERSION.store(next, Ordering::SeqCst);
+}
+
+/// Wipe every key retired by [`rotate_master_key`] that nothing is encrypted under anymore.
+///
+/// Returns how many pointers are still encrypted under retired keys; their
+/// keys are kept until those pointers are migrated or dropped.
+pub fn forget_retired_keys() -> usize {
+    let _slot = SEALER.lock().unwrap_or_else(|e| e.into_inner());
+    let current = VERSION.load(Ordering::Relaxed);
+
+    let mut unmigrated = 0;
+    for version in (0..=u8::MAX).filter(|&version| version != current) {
+        match LIVE[version as usize].load(Ordering::SeqCst) {
+            0 => KEYS[version as usize].store(0, Ordering::SeqCst),
+            live => unmigrated += live,
+        }
+    }
+    unmigrated
+}
+
+#[cold]
+fn unseal(version: u8) -> u64 {
+    let slot = SEALER.lock().unwrap_or_else(|e| e.into_inner());
+    unseal_with(slot.as_ref().unwrap(), version)
+}
+
+/// Unseal the key of `version` into memory, with [`SEALER`] held.
+fn unseal_with(sealed: &SealedKey, version: u8) -> u64 {
+    // another thread may have unsealed it while we waited for the lock.
+    if let master @ 1.. = KEYS[version as usize].load(Ordering::SeqCst) {
+        return master;
+    }
+
+    match sealed.sealer.unseal(&sealed.blob) {
+        Ok(master) => {
+            KEYS[version as usize].store(master, Ordering::SeqCst);
+            master
+        }
+        Err(error) => {
+            poison();
+            diag_panic!("{}", error);
+        }
+    }
+}
+
+/// Irreversibly replace the master key, so every live pointer decrypts to garbage.
+///
+/// Also poisons the process, since nothing encrypted under the old key can be
+/// safely dereferenced or freed afterwards.
+#[cfg_attr(not(feature = "anti-debug"), allow(dead_code))]
+pub(crate) fn scramble() {
+    poison();
+    KEYS[VERSION.load(Ordering::Relaxed) as usize]
+        .store(rand::random::<u64>() | 1, Ordering::Relaxed);
+}
+
+/// Poison every protected pointer in the process.
+pub(crate) fn poison() {
+    POISONED.store(true, Ordering::SeqCst);
+}
+
+/// Whether protected pointers in this process have been poisoned.
+///
+/// Once poisoned, dereferencing any protected pointer panics and dropping one
+/// leaks its allocation instead of freeing possibly-garbage memory.
+#[inline(always)]
+pub fn is_poisoned() -> bool {
+    POISONED.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decrypt_many, EncryptedPtr, MethodA, Speck};
+    use pretty_assertions::assert_eq;
+
+    struct XorSealer;
+
+    impl KeySealer for XorSealer {
+        fn seal(&self, key: u64) -> Result<Vec<u8>, SealError> {
+            Ok((key ^ 0x5EA1).to_le_bytes().to_vec())
+        }
+
+        fn unseal(&self, blob: &[u8]) -> Result<u64, SealError> {
+            let bytes = blob.try_into().map_err(|_| SealError::new("bad blob"))?;
+            Ok(u64::from_le_bytes(bytes) ^ 0x5EA1)
+        }
+    }
+
+    /// Held by tests that change the key version, so they don't race each other.
+    static ROTATION: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn sealed_master_key_unseals_on_demand() {
+        let _rotation = ROTATION.lock().unwrap_or_else(|e| e.into_inner());
+        let player: EncryptedPtr<u32> = 100.into();
+        let master = current();
+
+        seal_master_key(XorSealer).unwrap();
+        assert_eq!(*player, 100);
+
+        evict_master_key();
+        assert_eq!(*player, 100);
+        assert_eq!(current(), master);
+        assert!(seal_master_key(XorSealer).is_err());
+    }
+
+    #[test]
+    fn rotation_migrates_old_pointers() {
+        let _rotation = ROTATION.lock().unwrap_or_else(|e| e.into_inner());
+        let mut healths: Vec<EncryptedPtr<u32>> = vec![100.into()];
+        let mut position = EncryptedPtr::with_method(Box::into_raw(Box::new(3u64)), MethodA);
+        let mut custom = EncryptedPtr::with_method(Box::into_raw(Box::new(7u32)), Speck::new());
+        let before = current();
+
+        rotate_master_key();
+        assert!(current().0 != before.0 && current().1 != before.1);
+        healths.push(5.into());
+        assert_eq!(
+            decrypt_many(&healths, |values| *values[0] + *values[1]),
+            105
+        );
+        assert_eq!(position.access_realtime(), Some(&3));
+
+        // old pointers keep decrypting until they are migrated, once.
+        let before = healths[0].sealed.ciphertext();
+        assert_eq!(healths[0].migrate(), !cfg!(passthrough));
+        assert!(!healths[0].migrate() && !healths[1].migrate());
+        assert_eq!(healths[0].sealed.ciphertext() != before, !cfg!(passthrough));
+        *healths[0].access_mut() += 1;
+        *custom.access_mut() += 1;
+        assert_eq!((*healths[0], *custom, *position), (101, 8, 3));
+        assert_eq!(position.migrate(), !cfg!(passthrough));
+    }
+
+    #[test]
+    fn live_versions_are_neither_forgotten_nor_reused() {
+        let _rotation = ROTATION.lock().unwrap_or_else(|e| e.into_inner());
+        let pinned = MaskedKey::new(0x5EED);
+        let score = crate::EncryptedU32::new(1200);
+        rotate_master_key();
+        assert!(forget_retired_keys() >= 2);
+        assert_eq!((pinned.get(), score.get()), (0x5EED, 1200));
+
+        // as if the version had wrapped around onto a live key.
+        let next = version().wrapping_add(1);
+        acquire(next);
+        assert!(std::panic::catch_unwind(rotate_master_key).is_err());
+        assert!(version() != next);
+        release(next);
+    }
+
+    #[test]
+    fn pointers_made_during_rotation_stay_readable() {
+        let _rotation = ROTATION.lock().unwrap_or_else(|e| e.into_inner());
+        let stop = std::sync::atomic::AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            let makers: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut made = 0;
+                        while !stop.load(Ordering::Relaxed) {
+                            let health: EncryptedPtr<u32> = made.into();
+                            forget_retired_keys();
+                            assert_eq!(*health, made);
+                            made += 1;
+                        }
+                    })
+                })
+                .collect();
+
+            // few enough rotations that versions never wrap around.
+            for _ in 0..64 {
+                rotate_master_key();
+                forget_retired_keys();
+                std::thread::sleep(std::time::Duration::from_micros(100));
+            }
+            stop.store(true, Ordering::Relaxed);
+            for maker in makers {
+                maker.join().unwrap();
+            }
+        });
+    }
+
+    #[cfg(feature = "image-bound")]
+    #[test]
+    fn master_key_is_bound_to_the_image() {
+        let health: EncryptedPtr<u32> = 100.into();
+
+        let (version, master) = current();
+        assert!(image_key() != 0);
+        assert_eq!(master ^ stored(version), image_key());
+        assert_eq!(*health, 100);
+    }
+}
@@ -0,0 +1,217 @@
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+use crate::diag::{diag, diag_panic};
+use crate::sealed::{builtin_method, Method, BUILTIN_METHODS};
+use crate::Encrypt;
+
+/// Most methods that can live in the shared table, so an index fits in 7 bits.
+pub(crate) const MAX_SHARED: usize = 127;
+
+type SharedMethod = &'static (dyn Encrypt + Send + Sync);
+
+/// Methods that live for the whole process, referred to by index from keys.
+static SHARED: [OnceLock<SharedMethod>; MAX_SHARED] = [const { OnceLock::new() }; MAX_SHARED];
+
+/// Number of slots of [`SHARED`] filled, published only once the slot is set.
+static SHARED_LEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Held while filling a slot of [`SHARED`], so no two methods race for one.
+static INTERNING: Mutex<()> = Mutex::new(());
+
+/// A user method in the rotation.
+struct Registered {
+    index: u8,
+    weight: u32,
+}
+
+struct Rotation {
+    builtin_weight: u32,
+    defaults: Vec<u8>,
+    user: Vec<Registered>,
+}
+
+static ROTATION: RwLock<Rotation> = RwLock::new(Rotation {
+    builtin_weight: 1,
+    defaults: Vec::new(),
+    user: Vec::new(),
+});
+
+/// Put `method` in the shared table, reusing its slot if it is already there.
+pub(crate) fn intern(method: SharedMethod) -> u8 {
+    let _interning = INTERNING.lock().unwrap_or_else(|e| e.into_inner());
+    let len = SHARED_LEN.load(Ordering::Relaxed);
+    #[allow(ambiguous_wide_pointer_comparisons)]
+    let existing = SHARED[..len]
+        .iter()
+        .position(|slot| slot.get().is_some_and(|m| ptr::eq(*m, method)));
+    if let Some(index) = existing {
+        return index as u8;
+    }
+
+    assert!(
+        len < MAX_SHARED,
+        "{}",
+        diag!("at most 127 shared methods can be registered")
+    );
+    SHARED[len].set(method).ok();
+
+    // readers only look at slots below the published length, which are all set.
+    SHARED_LEN.store(len + 1, Ordering::Release);
+    len as u8
+}
+
+/// The shared method at `index`.
+#[inline(always)]
+pub(crate) fn shared(index: u8) -> SharedMethod {
+    *SHARED[index as usize]
+        .get()
+        .expect(diag!("shared method index out of range"))
+}
+
+/// Every shared method registered so far, with its index.
+pub(crate) fn shared_methods() -> impl Iterator<Item = (u8, SharedMethod)> {
+    let len = SHARED_LEN.load(Ordering::Acquire).min(MAX_SHARED);
+    SHARED[..len]
+        .iter()
+        .enumerate()
+        .filter_map(|(index, slot)| slot.get().map(|method| (index as u8, *method)))
+}
+
+/// Add `method` to the random rotation used by [`EncryptedPtr::new`](crate::EncryptedPtr::new).
+///
+/// Each method is picked with probability proportional to its `weight`; the
+/// built-in methods have a weight of 1 each unless changed with
+/// [`set_builtin_weight`]. Methods are meant to be registered once at
+/// startup and stay registered for the life of the process; at most 127 can
+/// be registered or configured in total.
+pub fn register_method<M>(method: M, weight: u32)
+where
+    M: Encrypt + Send + Sync + 'static,
+{
+    let index = intern(Box::leak(Box::new(method)));
+    ROTATION
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .user
+        .push(Registered { index, weight });
+}
+
+/// Set the weight of each built-in method in the rotation; 0 removes them.
+///
+/// Applies to the configured [`default_methods`](crate::Config::default_methods)
+/// instead, when there are any.
+pub fn set_builtin_weight(weight: u32) {
+    ROTATION
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .builtin_weight = weight;
+}
+
+/// Replace the built-ins in the rotation with `defaults`, or restore them if empty.
+pub(crate) fn set_defaults(defaults: &[SharedMethod]) {
+    let defaults = defaults.iter().map(|method| intern(*method)).collect();
+    ROTATION.write().unwrap_or_else(|e| e.into_inner()).defaults = defaults;
+}
+
+/// Choose a method from the rotation at random, honouring weights.
+pub(crate) fn random_method() -> Method {
+    if cfg!(passthrough) {
+        return Method::Plain;
+    }
+
+    let rotation = ROTATION.read().unwrap_or_else(|e| e.into_inner());
+
+    // configured default methods take the built-ins' place.
+    let defaults = &rotation.defaults;
+    let default_count = match defaults.len() {
+        0 => BUILTIN_METHODS,
+        len => len,
+    };
+    let default_method = |index: usize| match defaults.get(index) {
+        Some(index) => Method::Shared(*index),
+        None => builtin_method(index).unwrap(),
+    };
+
+    let builtin_total = rotation.builtin_weight as u64 * default_count as u64;
+    let total = builtin_total + rotation.user.iter().map(|m| m.weight as u64).sum::<u64>();
+
+    // with every weight at zero, fall back to the built-ins.
+    if total == 0 {
+        return default_method(rand::random_range(0..default_count));
+    }
+
+    let mut pick = rand::random_range(0..total);
+    if pick < builtin_total {
+        return default_method((pick / rotation.builtin_weight as u64) as usize);
+    }
+    pick -= builtin_total;
+
+    for registered in &rotation.user {
+        if pick < registered.weight as u64 {
+            return Method::Shared(registered.index);
+        }
+        pick -= registered.weight as u64;
+    }
+
+    diag_panic!("pick is always below the total weight")
+}
+
+#[cfg(all(test, not(passthrough)))]
+mod tests {
+    use super::*;
+    use crate::EncryptedPtr;
+    use pretty_assertions::assert_eq;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Plain XOR that counts how often it is used.
+    struct Counting;
+    impl Encrypt for Counting {
+        fn encrypt(&self, data: u64, key: u64) -> u64 {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            data ^ key
+        }
+
+        fn decrypt(&self, data: u64, key: u64) -> u64 {
+            data ^ key
+        }
+    }
+
+    #[test]
+    fn registered_method_joins_rotation() {
+        // the rotation is process-wide, so leave the built-ins half of it.
+        register_method(Counting, BUILTIN_METHODS as u32);
+
+        // missing it in all 128 picks is a 2^-128 chance.
+        let pointers: Vec<EncryptedPtr<u32>> = (0..128).map(|i| Box::new(i).into()).collect();
+
+        assert!(CALLS.load(Ordering::Relaxed) > 0);
+        for (i, pointer) in pointers.iter().enumerate() {
+            assert_eq!(**pointer, i as u32);
+        }
+    }
+    #[test]
+    fn concurrent_interning_fills_one_slot() {
+        static PLAIN: Counting = Counting;
+
+        let indices: Vec<u8> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let index = intern(&PLAIN);
+                        let len = SHARED_LEN.load(Ordering::Acquire);
+                        assert!(SHARED[..len].iter().all(|slot| slot.get().is_some()));
+                        index
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+
+        assert!(indices.iter().all(|&index| index == indices[0]));
+        assert!(ptr::addr_eq(shared(indices[0]), &PLAIN));
+    }
+}
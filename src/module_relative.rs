@@ -0,0 +1,169 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::OnceLock;
+
+use crate::sealed::Sealed;
+use crate::{master, registry};
+
+static MODULE_BASE: OnceLock<u64> = OnceLock::new();
+
+/// Load address of the main module, queried once.
+#[inline(always)]
+fn module_base() -> u64 {
+    if cfg!(passthrough) {
+        return 0;
+    }
+
+    *MODULE_BASE.get_or_init(query_module_base)
+}
+
+#[cfg(target_os = "linux")]
+fn query_module_base() -> u64 {
+    // the first mapping of the executable, at file offset zero, is its load base.
+    let exe = std::env::current_exe().ok();
+    let maps = std::fs::read_to_string("/proc/self/maps").unwrap_or_default();
+
+    maps.lines()
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let range = fields.next()?;
+            let offset = fields.nth(1)?;
+            let path = fields.nth(2)?;
+
+            let is_exe = exe.as_deref().is_some_and(|exe| exe.as_os_str() == path);
+            if !is_exe || u64::from_str_radix(offset, 16).ok()? != 0 {
+                return None;
+            }
+            u64::from_str_radix(range.split('-').next()?, 16).ok()
+        })
+        .unwrap_or_else(anchor)
+}
+
+#[cfg(windows)]
+fn query_module_base() -> u64 {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleW(name: *const u16) -> *mut u8;
+    }
+
+    match unsafe { GetModuleHandleW(std::ptr::null()) } as u64 {
+        0 => anchor(),
+        base => base,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn query_module_base() -> u64 {
+    anchor()
+}
+
+/// An address inside this module, which moves with it under ASLR.
+fn anchor() -> u64 {
+    static ANCHOR: u8 = 0;
+    &ANCHOR as *const u8 as u64
+}
+
+/// An encrypted pointer stored as an offset from the main module's load base.
+///
+/// The base moves with every run under ASLR, so even a ciphertext and key
+/// recovered in one run point nowhere in the next. Like
+/// [`EncryptedPtr`](crate::EncryptedPtr) it owns its pointee.
+pub struct ModuleRelativePtr<T> {
+    sealed: Sealed,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> ModuleRelativePtr<T> {
+    /// Create a new module-relative pointer from a raw pointer.
+    pub fn new(ptr: *mut T) -> Self {
+        registry::register(ptr);
+
+        Self {
+            sealed: Sealed::new((ptr as u64).wrapping_sub(module_base())),
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    fn decrypt_ptr(&self) -> *mut T {
+        self.sealed.get().wrapping_add(module_base()) as *mut T
+    }
+}
+
+impl<T> From<Box<T>> for ModuleRelativePtr<T> {
+    fn from(value: Box<T>) -> Self {
+        Self::new(Box::into_raw(value))
+    }
+}
+
+impl<T> From<T> for ModuleRelativePtr<T> {
+    fn from(value: T) -> Self {
+        Box::new(value).into()
+    }
+}
+
+impl<T> Deref for ModuleRelativePtr<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.decrypt_ptr() }
+    }
+}
+
+impl<T> DerefMut for ModuleRelativePtr<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.decrypt_ptr() }
+    }
+}
+
+impl<T> Drop for ModuleRelativePtr<T> {
+    fn drop(&mut self) {
+        // leak rather than free memory we may no longer be able to locate.
+        if master::is_poisoned() {
+            return;
+        }
+
+        let ptr = self.decrypt_ptr();
+        registry::unregister(ptr);
+        drop(unsafe { Box::from_raw(ptr) });
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ModuleRelativePtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ModuleRelativePtr");
+        f.debug_struct("ModuleRelativePtr")
+            .field(
+                "encrypted_value",
+                &format!("{:#x}", self.sealed.ciphertext()),
+            )
+            .field("pointed_value", &self.deref())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn module_relative_round_trip() {
+        let mut health: ModuleRelativePtr<u32> = 100.into();
+        *health -= 25;
+
+        assert_eq!(*health, 75);
+    }
+
+    #[cfg(not(passthrough))]
+    #[test]
+    fn module_base_precedes_module_code() {
+        let base = module_base();
+
+        assert!(base != 0 && base.is_multiple_of(4096));
+        assert!(base <= anchor());
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::diag::{diag, diag_panic};
+use crate::{master, registry};
+
+/// Bits of a quiet NaN, leaving the sign and 51 payload bits free.
+const QUIET_NAN: u64 = 0x7FF8_0000_0000_0000;
+
+/// Bits of the address, and so of the ciphertext, carried in the payload.
+const ADDRESS_BITS: u32 = 48;
+const ADDRESS_MASK: u64 = (1 << ADDRESS_BITS) - 1;
+
+/// Bits of each Feistel half.
+const HALF_MASK: u64 = (1 << (ADDRESS_BITS / 2)) - 1;
+const HALF_BITS: u32 = ADDRESS_BITS / 2;
+
+const ROUNDS: u32 = 4;
+
+/// Mantissa of the key field; the exponent is only there to look plausible.
+const MANTISSA_MASK: u64 = (1 << 52) - 1;
+
+/// The top byte of the mantissa holds the master key version, the rest is the key.
+const VERSION_SHIFT: u32 = 44;
+const KEY_MASK: u64 = (1 << VERSION_SHIFT) - 1;
+
+/// An encrypted pointer disguised as two `f64` fields.
+///
+/// The address is encrypted with a 48-bit Feistel cipher and packed into the
+/// payload of a quiet NaN, next to a key that reads as an ordinary finite
+/// float. In a struct dump it looks like floating-point data, not like a
+/// pointer and key. Owns its pointee like [`EncryptedPtr`](crate::EncryptedPtr).
+///
+/// Only addresses that fit in 48 bits can be boxed, which covers user space
+/// on x86-64 and aarch64 with 4-level paging.
+#[repr(C)]
+pub struct NanBoxedPtr<T> {
+    value: f64,
+    key: f64,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> NanBoxedPtr<T> {
+    /// Create a new NaN-boxed pointer from a raw pointer.
+    ///
+    /// # Panics
+    /// If the address doesn't fit in 48 bits.
+    pub fn new(ptr: *mut T) -> Self {
+        let addr = ptr as u64;
+        assert!(
+            addr & !ADDRESS_MASK == 0,
+            "{}",
+            diag!("address doesn't fit in a NaN payload")
+        );
+        registry::register(ptr);
+
+        let (value, key) = seal(addr);
+        Self {
+            value,
+            key,
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    fn decrypt_ptr(&self) -> *mut T {
+        if master::is_poisoned() {
+            diag_panic!("protected pointers have been poisoned");
+        }
+
+        let key = self.key.to_bits();
+        let ciphertext = self.value.to_bits() & ADDRESS_MASK;
+        let master = master::for_live_version(version_of(key));
+        decrypt(ciphertext, key_of(key, master)) as *mut T
+    }
+}
+
+/// Encrypt `addr` under a fresh key and the current master key, as the value and key fields.
+fn seal(addr: u64) -> (f64, f64) {
+    let (version, master) = master::acquire_current();
+
+    // a normal float between 1 and 65536, with a random mantissa.
+    let exponent = 0x3FF + rand::random_range(0..16u64);
+    let key = exponent << 52 | (version as u64) << VERSION_SHIFT | rand::random::<u64>() & KEY_MASK;
+
+    // the sign and spare payload bits are noise.
+    let noise = rand::random::<u64>() & (1 << 63 | 0b111 << ADDRESS_BITS);
+    let ciphertext = encrypt(addr, key_of(key, master));
+    (
+        f64::from_bits(QUIET_NAN | noise | ciphertext),
+        f64::from_bits(key),
+    )
+}
+
+/// The master key version a key field was sealed under.
+#[inline(always)]
+fn version_of(bits: u64) -> u8 {
+    ((bits & MANTISSA_MASK) >> VERSION_SHIFT) as u8
+}
+
+/// The cipher key behind a key field, bound to the master key.
+#[inline(always)]
+fn key_of(bits: u64, master: u64) -> u64 {
+    (bits & KEY_MASK) ^ master
+}
+
+/// The Feistel round function.
+#[inline(always)]
+fn round(half: u64, key: u64, round: u32) -> u64 {
+    let mixed = (half ^ key.rotate_left(round * 16)).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    mixed >> (64 - HALF_BITS)
+}
+
+#[inline(always)]
+fn encrypt(addr: u64, key: u64) -> u64 {
+    if cfg!(passthrough) {
+        return addr;
+    }
+
+    let (mut left, mut right) = (addr >> HALF_BITS, addr & HALF_MASK);
+    for i in 0..ROUNDS {
+        (left, right) = (right, left ^ round(right, key, i));
+    }
+    left << HALF_BITS | right
+}
+
+#[inline(always)]
+fn decrypt(ciphertext: u64, key: u64) -> u64 {
+    if cfg!(passthrough) {
+        return ciphertext;
+    }
+
+    let (mut left, mut right) = (ciphertext >> HALF_BITS, ciphertext & HALF_MASK);
+    for i in (0..ROUNDS).rev() {
+        (left, right) = (right ^ round(left, key, i), left);
+    }
+    left << HALF_BITS | right
+}
+
+impl<T> From<Box<T>> for NanBoxedPtr<T> {
+    fn from(value: Box<T>) -> Self {
+        Self::new(Box::into_raw(value))
+    }
+}
+
+impl<T> From<T> for NanBoxedPtr<T> {
+    fn from(value: T) -> Self {
+        Box::new(value).into()
+    }
+}
+
+impl<T> Deref for NanBoxedPtr<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.decrypt_ptr() }
+    }
+}
+
+impl<T> DerefMut for NanBoxedPtr<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let ptr = self.decrypt_ptr();
+
+        // re-encrypt under the current master key after a rotation.
+        let version = version_of(self.key.to_bits());
+        if version != master::version() {
+            (self.value, self.key) = seal(ptr as u64);
+            master::release(version);
+        }
+        unsafe { &mut *ptr }
+    }
+}
+
+impl<T> Drop for NanBoxedPtr<T> {
+    fn drop(&mut self) {
+        // leak rather than free memory we may no longer be able to locate.
+        if master::is_poisoned() {
+            return;
+        }
+
+        let ptr = self.decrypt_ptr();
+        registry::unregister(ptr);
+        drop(unsafe { Box::from_raw(ptr) });
+        master::release(version_of(self.key.to_bits()));
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for NanBoxedPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "NanBoxedPtr");
+        f.debug_struct("NanBoxedPtr")
+            .field("encrypted_value", &format!("{:#x}", self.value.to_bits()))
+            .field("pointed_value", &self.deref())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn nan_boxed_round_trip() {
+        let mut health: NanBoxedPtr<u32> = 100.into();
+        *health -= 25;
+
+        assert_eq!(*health, 75);
+        assert!(health.value.is_nan());
+        assert!(health.key.is_normal() && (1.0..65536.0).contains(&health.key));
+    }
+
+    #[test]
+    fn feistel_round_trips() {
+        for _ in 0..1000 {
+            let (addr, key) = (rand::random::<u64>() & ADDRESS_MASK, rand::random());
+
+            assert_eq!(encrypt(addr, key) & !ADDRESS_MASK, 0);
+            assert_eq!(decrypt(encrypt(addr, key), key), addr);
+        }
+    }
+}
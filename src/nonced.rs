@@ -0,0 +1,220 @@
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::diag::{diag, type_name};
+use crate::tamper::{self, Poisoned, TamperEvent, TamperKind};
+use crate::{aes, master, registry};
+
+/// The splitmix64 finalizer, a cheap mix for nonce keys.
+#[inline(always)]
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Encrypt `addr` for access number `nonce`, returning the ciphertext and its tag.
+#[inline(always)]
+fn seal(addr: u64, nonce: u64, seed: u64, master: u64) -> (u64, u64) {
+    let key = mix(seed ^ master ^ nonce);
+    let ciphertext = (addr ^ key).rotate_left(key as u32 & 63);
+    (ciphertext, aes::mac(key, ciphertext))
+}
+
+/// Recover the address sealed for access number `nonce`, or `None` if the tag doesn't match.
+#[inline(always)]
+fn open(ciphertext: u64, tag: u64, nonce: u64, seed: u64, master: u64) -> Option<u64> {
+    let key = mix(seed ^ master ^ nonce);
+    (aes::mac(key, ciphertext) == tag).then(|| ciphertext.rotate_right(key as u32 & 63) ^ key)
+}
+
+/// An encrypted pointer re-encrypted under a fresh nonce on every access.
+///
+/// The nonce is a counter kept out of band, in its own allocation rather
+/// than in the struct, and every ciphertext carries a tag binding it to the
+/// nonce it was made for. A snapshot of the struct copied out and pasted
+/// back later no longer matches the counter: the access is reported to the
+/// tamper callback as [`TamperKind::SnapshotReplay`] and the pointer is
+/// poisoned instead of decrypting to an attacker-chosen address.
+pub struct NoncedPtr<T> {
+    ciphertext: Cell<u64>,
+    tag: Cell<u64>,
+    /// The master key version the ciphertext is sealed under.
+    version: Cell<u8>,
+    seed: u64,
+    /// The counter's address, masked like the registry masks addresses.
+    counter: u64,
+    poisoned: Cell<bool>,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> NoncedPtr<T> {
+    /// Create a new nonced pointer from a raw pointer.
+    pub fn new(ptr: *mut T) -> Self {
+        registry::register(ptr);
+
+        let seed = rand::random();
+        let counter = Box::into_raw(Box::new(Cell::new(0u64)));
+        let (version, master) = master::acquire_current();
+        let (ciphertext, tag) = seal(ptr as u64, 0, seed, master);
+
+        Self {
+            ciphertext: Cell::new(ciphertext),
+            tag: Cell::new(tag),
+            version: Cell::new(version),
+            seed,
+            counter: counter as u64 ^ mix(seed),
+            poisoned: Cell::new(false),
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    fn counter(&self) -> &Cell<u64> {
+        unsafe { &*((self.counter ^ mix(self.seed)) as *const Cell<u64>) }
+    }
+
+    /// Whether a replayed snapshot has poisoned this pointer.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.get()
+    }
+
+    /// Decrypt under the current nonce, then re-encrypt under the next one.
+    fn advance(&self) -> Result<*mut T, Poisoned> {
+        if self.is_poisoned() {
+            return Err(Poisoned);
+        }
+
+        let counter = self.counter();
+        let nonce = counter.get();
+        let master = master::for_live_version(self.version.get());
+        let Some(addr) = open(
+            self.ciphertext.get(),
+            self.tag.get(),
+            nonce,
+            self.seed,
+            master,
+        ) else {
+            self.poisoned.set(true);
+            tamper::report(TamperEvent::new(
+                TamperKind::SnapshotReplay,
+                type_name::<T>(),
+            ));
+            return Err(Poisoned);
+        };
+
+        // sealed under the current key, which migrates it after a rotation.
+        let master = match master::version() == self.version.get() {
+            true => master,
+            false => {
+                let (version, current) = master::acquire_current();
+                master::release(self.version.replace(version));
+                current
+            }
+        };
+        let (ciphertext, tag) = seal(addr, nonce + 1, self.seed, master);
+        self.ciphertext.set(ciphertext);
+        self.tag.set(tag);
+        counter.set(nonce + 1);
+        Ok(addr as *mut T)
+    }
+
+    /// Borrow the pointee, or [`Poisoned`] if a replayed snapshot was detected.
+    pub fn try_get(&self) -> Result<&T, Poisoned> {
+        self.advance().map(|ptr| unsafe { &*ptr })
+    }
+
+    /// Mutable counterpart of [`NoncedPtr::try_get`].
+    pub fn try_get_mut(&mut self) -> Result<&mut T, Poisoned> {
+        self.advance().map(|ptr| unsafe { &mut *ptr })
+    }
+}
+
+impl<T> From<Box<T>> for NoncedPtr<T> {
+    fn from(value: Box<T>) -> Self {
+        Self::new(Box::into_raw(value))
+    }
+}
+
+impl<T> From<T> for NoncedPtr<T> {
+    fn from(value: T) -> Self {
+        Box::new(value).into()
+    }
+}
+
+impl<T> Deref for NoncedPtr<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.try_get().expect(diag!("snapshot replay detected"))
+    }
+}
+
+impl<T> DerefMut for NoncedPtr<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.try_get_mut().expect(diag!("snapshot replay detected"))
+    }
+}
+
+impl<T> Drop for NoncedPtr<T> {
+    fn drop(&mut self) {
+        // leak rather than free memory we may no longer be able to locate.
+        if master::is_poisoned() {
+            return;
+        }
+
+        if let Ok(ptr) = self.advance() {
+            registry::unregister(ptr);
+            drop(unsafe { Box::from_raw(ptr) });
+        }
+        drop(unsafe { Box::from_raw(self.counter() as *const Cell<u64> as *mut Cell<u64>) });
+        master::release(self.version.get());
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for NoncedPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "NoncedPtr");
+        f.debug_struct("NoncedPtr")
+            .field("encrypted_value", &format!("{:#x}", self.ciphertext.get()))
+            .field("pointed_value", &self.try_get().ok())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::mem::ManuallyDrop;
+    use std::ptr;
+
+    #[test]
+    fn nonced_round_trip() {
+        let mut health: NoncedPtr<u32> = 100.into();
+        let first = health.ciphertext.get();
+        *health -= 25;
+
+        assert_eq!(*health, 75);
+        assert!(health.ciphertext.get() != first);
+    }
+
+    #[test]
+    fn replayed_snapshot_is_detected() {
+        let health: NoncedPtr<u32> = 100.into();
+
+        // a cheat copies the struct's bytes out, then pastes them back later.
+        let snapshot = ManuallyDrop::new(unsafe { ptr::read(&health) });
+        assert_eq!(*health, 100);
+        unsafe {
+            ptr::copy_nonoverlapping(&*snapshot, &health as *const _ as *mut NoncedPtr<u32>, 1)
+        };
+
+        assert_eq!(health.try_get(), Err(Poisoned));
+        assert!(health.is_poisoned());
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use crate::diag::diag;
+use crate::sealed::Sealed;
+
+/// A boxed trait object whose data and vtable pointers are both encrypted.
+///
+/// The fat pointer is only rebuilt transiently on access, so neither the
+/// object's address nor its vtable (and through it the method addresses)
+/// can be read out of the wrapper in a memory dump.
+pub struct ObfuscatedDyn<T: ?Sized> {
+    data: Sealed,
+    vtable: Sealed,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T: ?Sized> ObfuscatedDyn<T> {
+    /// Take ownership of a boxed trait object.
+    pub fn new(inner: Box<T>) -> Self {
+        assert!(
+            size_of::<*mut T>() == 2 * size_of::<usize>(),
+            "{}",
+            diag!("ObfuscatedDyn requires a trait object")
+        );
+
+        let raw = Box::into_raw(inner);
+        let [data, vtable]: [usize; 2] = unsafe { mem::transmute_copy(&raw) };
+
+        Self {
+            data: Sealed::new(data as u64),
+            vtable: Sealed::new(vtable as u64),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Rebuild the fat pointer by decrypting both halves.
+    #[inline(always)]
+    fn decrypt_ptr(&self) -> *mut T {
+        let words = [self.data.get() as usize, self.vtable.get() as usize];
+        unsafe { mem::transmute_copy(&words) }
+    }
+}
+
+impl<T: ?Sized> Deref for ObfuscatedDyn<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        unsafe { &*self.decrypt_ptr() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for ObfuscatedDyn<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.decrypt_ptr() }
+    }
+}
+
+impl<T: ?Sized> Drop for ObfuscatedDyn<T> {
+    fn drop(&mut self) {
+        if crate::is_poisoned() {
+            return;
+        }
+
+        drop(unsafe { Box::from_raw(self.decrypt_ptr()) });
+    }
+}
+
+impl<T: ?Sized> From<Box<T>> for ObfuscatedDyn<T> {
+    fn from(value: Box<T>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ObfuscatedDyn<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ObfuscatedDyn");
+        f.debug_struct("ObfuscatedDyn")
+            .field(
+                "encrypted_vtable",
+                &format!("{:#x}", self.vtable.ciphertext()),
+            )
+            .field("pointed_value", &&**self)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    trait Entity {
+        fn health(&self) -> u32;
+        fn damage(&mut self, amount: u32);
+    }
+
+    struct Player {
+        health: u32,
+    }
+
+    impl Entity for Player {
+        fn health(&self) -> u32 {
+            self.health
+        }
+
+        fn damage(&mut self, amount: u32) {
+            self.health -= amount;
+        }
+    }
+
+    #[test]
+    fn obfuscated_dyn_dispatch() {
+        let mut entity: ObfuscatedDyn<dyn Entity> =
+            ObfuscatedDyn::new(Box::new(Player { health: 100 }));
+        entity.damage(30);
+
+        assert_eq!(entity.health(), 70);
+    }
+
+    #[cfg(not(passthrough))]
+    #[test]
+    fn obfuscated_dyn_hides_vtable() {
+        let entity: ObfuscatedDyn<dyn Entity> =
+            ObfuscatedDyn::new(Box::new(Player { health: 100 }));
+
+        let raw: *const dyn Entity = &*entity;
+        let [_, vtable]: [usize; 2] = unsafe { mem::transmute(raw) };
+        let words = unsafe {
+            std::slice::from_raw_parts(
+                &entity as *const _ as *const usize,
+                size_of_val(&entity) / size_of::<usize>(),
+            )
+        };
+
+        assert!(!words.contains(&vtable));
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Hooks into accesses of protected pointers, for telemetry, rate analysis or custom honeypots.
+///
+/// Install one with [`set_access_observer`]. Every method has an empty
+/// default, and receives the type name of the pointee. Observers run on the
+/// accessing thread, inside the access, so keep them cheap.
+pub trait AccessObserver: Send + Sync {
+    /// An [`EncryptedPtr`](crate::EncryptedPtr) was dereferenced.
+    fn on_deref(&self, _type_name: &'static str) {}
+    /// An [`EncryptedPtr`](crate::EncryptedPtr) was mutably dereferenced.
+    fn on_deref_mut(&self, _type_name: &'static str) {}
+    /// A key was rolled, e.g. by [`EncryptedBundle::rekey`](crate::EncryptedBundle::rekey).
+    fn on_rekey(&self, _type_name: &'static str) {}
+    /// An [`EncryptedPtr`](crate::EncryptedPtr) dropped its pointee.
+    fn on_drop(&self, _type_name: &'static str) {}
+}
+
+/// What an [`AccessObserver`] is told about.
+#[derive(Clone, Copy)]
+pub(crate) enum Access {
+    Deref,
+    DerefMut,
+    Rekey,
+    Drop,
+}
+
+/// Whether an observer is installed, so the access paths skip the lock otherwise.
+static OBSERVING: AtomicBool = AtomicBool::new(false);
+
+static OBSERVER: RwLock<Option<Arc<dyn AccessObserver>>> = RwLock::new(None);
+
+/// Install `observer`, replacing any previous one.
+pub fn set_access_observer(observer: impl AccessObserver + 'static) {
+    *OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(observer));
+    OBSERVING.store(true, Ordering::Release);
+}
+
+/// Remove the access observer, if any.
+pub fn clear_access_observer() {
+    OBSERVING.store(false, Ordering::Release);
+    *OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Tell the observer, if any, about an access to a `type_name`.
+#[inline(always)]
+pub(crate) fn observe(access: Access, type_name: &'static str) {
+    if OBSERVING.load(Ordering::Relaxed) {
+        notify(access, type_name);
+    }
+}
+
+#[cold]
+fn notify(access: Access, type_name: &'static str) {
+    // observers may access protected pointers or replace themselves, so don't hold the lock.
+    let observer = OBSERVER.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(observer) = observer {
+        match access {
+            Access::Deref => observer.on_deref(type_name),
+            Access::DerefMut => observer.on_deref_mut(type_name),
+            Access::Rekey => observer.on_rekey(type_name),
+            Access::Drop => observer.on_drop(type_name),
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "opaque-diagnostics")))]
+mod tests {
+    use super::*;
+    use crate::EncryptedPtr;
+    use pretty_assertions::assert_eq;
+    use std::any::type_name;
+    use std::sync::atomic::AtomicUsize;
+
+    struct Probe(u32);
+
+    #[derive(Default)]
+    struct Counts {
+        derefs: AtomicUsize,
+        derefs_mut: AtomicUsize,
+        drops: AtomicUsize,
+    }
+
+    struct Counter(Arc<Counts>);
+
+    impl AccessObserver for Counter {
+        fn on_deref(&self, name: &'static str) {
+            if name == type_name::<Probe>() {
+                self.0.derefs.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        fn on_deref_mut(&self, name: &'static str) {
+            if name == type_name::<Probe>() {
+                self.0.derefs_mut.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        fn on_drop(&self, name: &'static str) {
+            if name == type_name::<Probe>() {
+                self.0.drops.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Touches a protected pointer and removes itself, as a honeypot might.
+    struct Tripwire(Arc<Counts>);
+
+    impl AccessObserver for Tripwire {
+        fn on_deref(&self, name: &'static str) {
+            if name == type_name::<Probe>() {
+                let bait: EncryptedPtr<u32> = 7.into();
+                assert_eq!(*bait, 7);
+                self.0.derefs.fetch_add(1, Ordering::Relaxed);
+                clear_access_observer();
+            }
+        }
+    }
+
+    #[test]
+    fn observer_sees_accesses() {
+        let counts = Arc::new(Counts::default());
+        set_access_observer(Counter(counts.clone()));
+
+        let mut probe: EncryptedPtr<Probe> = Probe(1).into();
+        probe.0 += 1;
+        assert_eq!(probe.0, 2);
+        drop(probe);
+        clear_access_observer();
+        let unobserved: EncryptedPtr<Probe> = Probe(3).into();
+        assert_eq!(unobserved.0, 3);
+
+        assert_eq!(counts.derefs.load(Ordering::Relaxed), 1);
+        assert_eq!(counts.derefs_mut.load(Ordering::Relaxed), 1);
+        assert_eq!(counts.drops.load(Ordering::Relaxed), 1);
+
+        // in the same test, since the observer is process-wide.
+        let tripped = Arc::new(Counts::default());
+        set_access_observer(Tripwire(tripped.clone()));
+        let probe: EncryptedPtr<Probe> = Probe(4).into();
+        assert_eq!((probe.0, probe.0), (4, 4));
+        assert_eq!(tripped.derefs.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+use std::hint::black_box;
+use std::marker::PhantomData;
+
+/// The offset of a `U` field within a `T`, stored encrypted and decrypted on use.
+///
+/// Usually generated by `#[derive(EncryptedOffsets)]`, which gives every
+/// field its own key, so the binary holds no plain field offsets to
+/// pattern-match against.
+pub struct EncryptedOffset<T, U> {
+    encrypted: u64,
+    key: u64,
+    _marker: PhantomData<fn(&T) -> &U>,
+}
+
+impl<T, U> EncryptedOffset<T, U> {
+    /// Encrypt `offset` under `key`.
+    ///
+    /// # Safety
+    /// There must be a `U` at `offset` bytes into every `T`, e.g. from
+    /// `offset_of!(T, field)`.
+    pub const unsafe fn new(offset: usize, key: u64) -> Self {
+        Self {
+            encrypted: (offset as u64 ^ key).rotate_left((key >> 58) as u32),
+            key,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Decrypt the offset in bytes.
+    #[inline(always)]
+    pub fn offset(&self) -> usize {
+        // keep the compiler from folding the constant offset back in.
+        let key = black_box(self.key);
+        (self.encrypted.rotate_right((key >> 58) as u32) ^ key) as usize
+    }
+
+    /// Borrow the field in `base`.
+    #[inline(always)]
+    pub fn get<'a>(&self, base: &'a T) -> &'a U {
+        unsafe { &*(base as *const T).byte_add(self.offset()).cast() }
+    }
+
+    /// Mutably borrow the field in `base`.
+    #[inline(always)]
+    pub fn get_mut<'a>(&self, base: &'a mut T) -> &'a mut U {
+        unsafe { &mut *(base as *mut T).byte_add(self.offset()).cast() }
+    }
+}
+
+impl<T, U> Clone for EncryptedOffset<T, U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, U> Copy for EncryptedOffset<T, U> {}
+
+impl<T, U> fmt::Debug for EncryptedOffset<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedOffset");
+        f.debug_struct("EncryptedOffset")
+            .field("encrypted_value", &format!("{:#x}", self.encrypted))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::mem::offset_of;
+
+    struct Player {
+        name: [u8; 8],
+        health: u32,
+    }
+
+    #[test]
+    fn offset_accesses_field() {
+        const HEALTH: EncryptedOffset<Player, u32> =
+            unsafe { EncryptedOffset::new(offset_of!(Player, health), 0xF00D_CAFE_1234_5678) };
+
+        let mut player = Player {
+            name: *b"player_1",
+            health: 100,
+        };
+        *HEALTH.get_mut(&mut player) -= 25;
+
+        assert_eq!(HEALTH.offset(), offset_of!(Player, health));
+        assert_eq!(*HEALTH.get(&player), 75);
+        assert_eq!(&player.name, b"player_1");
+
+        let player: crate::EncryptedPtr<Player> = player.into();
+        assert_eq!(*player.project(HEALTH), 75);
+    }
+
+    #[cfg(feature = "macros")]
+    #[derive(crate::EncryptedOffsets)]
+    struct Enemy {
+        level: u8,
+        health: u32,
+    }
+
+    #[cfg(feature = "macros")]
+    #[crate::shuffle_fields]
+    struct Shuffled {
+        a: u64,
+        b: u32,
+        c: u16,
+        d: u8,
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn shuffled_offsets() {
+        let shuffled = Shuffled {
+            a: 1,
+            b: 2,
+            c: 3,
+            d: 4,
+        };
+
+        assert_eq!(*shuffled_offsets::a(&shuffled), 1);
+        assert_eq!(*shuffled_offsets::d(&shuffled), 4);
+        assert_eq!(shuffled_offsets::C.offset(), offset_of!(Shuffled, c));
+        assert_eq!(shuffled.b, 2);
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn derived_offsets() {
+        let mut enemy = Enemy {
+            level: 3,
+            health: 100,
+        };
+        *enemy_offsets::health_mut(&mut enemy) += 1;
+
+        assert_eq!(*enemy_offsets::level(&enemy), 3);
+        assert_eq!(enemy.health, 101);
+        assert_eq!(enemy_offsets::HEALTH.offset(), offset_of!(Enemy, health));
+    }
+}
@@ -0,0 +1,242 @@
+use std::alloc::Layout;
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+
+use region::Protection;
+
+use crate::backing::{self, PageBacking};
+use crate::diag::diag;
+use crate::sealed::Sealed;
+use crate::{config, master, registry, wipe};
+
+/// An encrypted pointer to pages the OS only lets you touch inside an access guard.
+///
+/// The value gets pages of its own, kept `PROT_NONE` (via the `region`
+/// crate) except while a guard from [`PageGuardedPtr::access`] or
+/// [`PageGuardedPtr::access_mut`] is alive, so a stray or injected read
+/// outside that window faults even if the address leaks.
+///
+/// The pages come from [`Config::page_backing`](crate::Config::page_backing),
+/// or from the [`PageBacking`] given to [`PageGuardedPtr::with_backing`];
+/// every backing is locked the same way.
+pub struct PageGuardedPtr<T> {
+    sealed: Sealed,
+    backing: PageBacking,
+    // number of live read guards; pages are readable while non-zero.
+    readers: Cell<usize>,
+    _marker: PhantomData<*mut T>,
+}
+
+/// Page-aligned layout covering whole pages for a `T`.
+fn page_layout<T>() -> Layout {
+    let page = region::page::size();
+    let align = page.max(align_of::<T>());
+    let size = size_of::<T>().max(1).next_multiple_of(align);
+    Layout::from_size_align(size, align).unwrap()
+}
+
+impl<T> PageGuardedPtr<T> {
+    /// Move `value` onto fresh pages from the configured backing and lock them.
+    pub fn new(value: T) -> Result<Self, region::Error> {
+        let backing = config::read(|config| config.page_backing);
+        Self::with_backing(value, backing)
+    }
+
+    /// Move `value` onto fresh pages from `backing` and lock them.
+    pub fn with_backing(value: T, backing: PageBacking) -> Result<Self, region::Error> {
+        let layout = page_layout::<T>();
+        let ptr = backing::allocate(layout, backing)? as *mut T;
+
+        unsafe {
+            ptr.write(value);
+            if let Err(error) = region::protect(ptr, layout.size(), Protection::NONE) {
+                ptr::drop_in_place(ptr);
+                backing::release(ptr as *mut u8, layout, backing);
+                return Err(error);
+            }
+        }
+        registry::register(ptr);
+
+        Ok(Self {
+            sealed: Sealed::new(ptr as u64),
+            backing,
+            readers: Cell::new(0),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Unlock the pages for reading until the guard drops.
+    ///
+    /// # Panics
+    /// If the OS refuses to change the protection.
+    pub fn access(&self) -> PageGuard<'_, T> {
+        if self.readers.get() == 0 {
+            self.protect(Protection::READ);
+        }
+        self.readers.set(self.readers.get() + 1);
+
+        PageGuard { ptr: self }
+    }
+
+    /// Unlock the pages for reading and writing until the guard drops.
+    ///
+    /// # Panics
+    /// If the OS refuses to change the protection.
+    pub fn access_mut(&mut self) -> PageGuardMut<'_, T> {
+        self.protect(Protection::READ_WRITE);
+
+        PageGuardMut { ptr: self }
+    }
+
+    fn protect(&self, protection: Protection) {
+        let layout = page_layout::<T>();
+        unsafe { region::protect(self.decrypt_ptr(), layout.size(), protection) }
+            .expect(diag!("failed to change page protection"));
+    }
+
+    #[inline(always)]
+    fn decrypt_ptr(&self) -> *mut T {
+        self.sealed.get() as *mut T
+    }
+}
+
+impl<T> Drop for PageGuardedPtr<T> {
+    fn drop(&mut self) {
+        // leak rather than free memory we may no longer be able to locate.
+        if master::is_poisoned() {
+            return;
+        }
+
+        let ptr = self.decrypt_ptr();
+        registry::unregister(ptr);
+
+        // the allocator needs its pages back in a usable state.
+        self.protect(Protection::READ_WRITE);
+        let layout = page_layout::<T>();
+        unsafe {
+            ptr::drop_in_place(ptr);
+            if wipe::enabled() {
+                wipe::wipe(ptr as *mut u8, size_of::<T>());
+            }
+            backing::release(ptr as *mut u8, layout, self.backing);
+        }
+    }
+}
+
+impl<T> fmt::Debug for PageGuardedPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "PageGuardedPtr");
+        // reading the value would need a guard, so only show the ciphertext.
+        f.debug_struct("PageGuardedPtr")
+            .field(
+                "encrypted_value",
+                &format!("{:#x}", self.sealed.ciphertext()),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// Read access to a [`PageGuardedPtr`], see [`PageGuardedPtr::access`].
+pub struct PageGuard<'a, T> {
+    ptr: &'a PageGuardedPtr<T>,
+}
+
+impl<T> Deref for PageGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr.decrypt_ptr() }
+    }
+}
+
+impl<T> Drop for PageGuard<'_, T> {
+    fn drop(&mut self) {
+        let readers = self.ptr.readers.get() - 1;
+        self.ptr.readers.set(readers);
+        if readers == 0 {
+            self.ptr.protect(Protection::NONE);
+        }
+    }
+}
+
+/// Read-write access to a [`PageGuardedPtr`], see [`PageGuardedPtr::access_mut`].
+pub struct PageGuardMut<'a, T> {
+    ptr: &'a mut PageGuardedPtr<T>,
+}
+
+impl<T> Deref for PageGuardMut<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr.decrypt_ptr() }
+    }
+}
+
+impl<T> DerefMut for PageGuardMut<'_, T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.ptr.decrypt_ptr() }
+    }
+}
+
+impl<T> Drop for PageGuardMut<'_, T> {
+    fn drop(&mut self) {
+        self.ptr.protect(Protection::NONE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn locked<T>(ptr: &PageGuardedPtr<T>) -> bool {
+        let info = region::query(ptr.decrypt_ptr() as *const u8).unwrap();
+        info.protection() == Protection::NONE
+    }
+
+    #[test]
+    fn pages_unlock_inside_guards() {
+        let mut health = PageGuardedPtr::new(100u32).unwrap();
+        *health.access_mut() -= 25;
+
+        let (a, b) = (health.access(), health.access());
+        assert_eq!(*a + *b, 150);
+        drop((a, b));
+
+        assert!(locked(&health));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn secret_backing_round_trip() {
+        let Ok(mut health) = PageGuardedPtr::with_backing(100u32, PageBacking::Secret) else {
+            // memfd_secret is disabled on this kernel.
+            return;
+        };
+        *health.access_mut() -= 25;
+        assert!(locked(&health));
+
+        let guard = health.access();
+        assert!(!locked(&health));
+        assert_eq!(*guard, 75);
+        drop(guard);
+        assert!(locked(&health));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
+    #[test]
+    fn concealed_backing_round_trip() {
+        let mut name = PageGuardedPtr::with_backing([0u8; 16], PageBacking::Concealed).unwrap();
+        name.access_mut()[..5].copy_from_slice(b"alice");
+        assert!(locked(&name));
+
+        assert_eq!(&name.access()[..5], b"alice");
+        assert!(locked(&name));
+    }
+}
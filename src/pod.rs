@@ -0,0 +1,52 @@
+/// Types whose every byte is initialized, i.e. that contain no padding.
+///
+/// Storage modes that work on the raw bytes of a value (splitting, masking)
+/// require this, since reading padding bytes is undefined behavior.
+///
+/// # Safety
+/// Implementors must not contain any padding or otherwise uninitialized bytes.
+pub unsafe trait NoUninit: Sized {}
+
+macro_rules! impl_no_uninit {
+    ($($ty:ty),* $(,)?) => {
+        $(unsafe impl NoUninit for $ty {})*
+    };
+}
+
+impl_no_uninit!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char);
+
+unsafe impl<T: NoUninit, const N: usize> NoUninit for [T; N] {}
+
+/// Types for which every bit pattern is a valid value, e.g. integers but not `bool` or `char`.
+///
+/// Memory another process can write to may hold any bytes, so values read
+/// from it have to satisfy this.
+///
+/// # Safety
+/// Implementors must have no padding and no invalid bit patterns.
+pub unsafe trait AnyBitPattern: NoUninit {}
+
+macro_rules! impl_any_bit_pattern {
+    ($($ty:ty),* $(,)?) => {
+        $(unsafe impl AnyBitPattern for $ty {})*
+    };
+}
+
+impl_any_bit_pattern!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: AnyBitPattern, const N: usize> AnyBitPattern for [T; N] {}
+
+/// View the bytes of a value.
+#[inline(always)]
+pub(crate) fn bytes_of<T: NoUninit>(value: &T) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+}
+
+/// Overwrite a value's bytes with zeros in a way the compiler can't elide.
+#[inline(always)]
+pub(crate) fn wipe<T>(value: *mut T) {
+    let bytes = value as *mut u8;
+    for i in 0..size_of::<T>() {
+        unsafe { std::ptr::write_volatile(bytes.add(i), 0) };
+    }
+}
@@ -0,0 +1,238 @@
+use crate::diag::diag;
+use crate::Encrypt;
+
+/// Round constants RC0..RC11.
+const RC: [u64; 12] = [
+    0x0000000000000000,
+    0x13198a2e03707344,
+    0xa4093822299f31d0,
+    0x082efa98ec4e6c89,
+    0x452821e638d01377,
+    0xbe5466cf34e90c6c,
+    0x7ef84f78fd955cb1,
+    0x85840851f1ac43aa,
+    0xc882d32f25323c54,
+    0x64a51195e0e3610d,
+    0xd3b5a399ca0c2399,
+    0xc0ac29b7c97c50dd,
+];
+
+/// Decryption is encryption with `k1 ^ ALPHA` and the whitening keys swapped.
+const ALPHA: u64 = RC[11];
+
+const SBOX: [u8; 16] = [
+    0xB, 0xF, 0x3, 0x2, 0xA, 0xC, 0x9, 0x1, 0x6, 0x7, 0x8, 0x0, 0xE, 0x5, 0xD, 0x4,
+];
+
+const SBOX_INV: [u8; 16] = [
+    0xB, 0x7, 0x3, 0x2, 0xF, 0xD, 0x8, 0x9, 0xA, 0x6, 0x4, 0x0, 0x5, 0xE, 0xC, 0x1,
+];
+
+/// Nibble permutation of the shift-rows step, nibble 0 being the most significant.
+const SHIFT_ROWS: [usize; 16] = [0, 5, 10, 15, 4, 9, 14, 3, 8, 13, 2, 7, 12, 1, 6, 11];
+
+/// PRINCE, a block cipher designed for low-latency 64-bit encryption.
+///
+/// It was built to fully unroll into a short critical path, which suits a
+/// decrypt on every dereference. Unlike most ciphers, decryption is the same
+/// circuit as encryption under a related key. The 64-bit pointer key is
+/// stretched to the 128-bit PRINCE key.
+///
+/// Reduced-round variants drop rounds symmetrically from both halves, which
+/// keeps the decryption property intact.
+#[derive(Debug, Clone, Copy)]
+pub struct Prince {
+    rounds: usize,
+}
+
+impl Default for Prince {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Prince {
+    /// Standard number of rounds on each side of the middle layer.
+    pub const DEFAULT_ROUNDS: usize = 5;
+
+    /// PRINCE with the standard five forward and five backward rounds.
+    pub const fn new() -> Self {
+        Self::with_rounds(Self::DEFAULT_ROUNDS)
+    }
+
+    /// PRINCE with `rounds` forward and `rounds` backward rounds, at most five.
+    pub const fn with_rounds(rounds: usize) -> Self {
+        assert!(
+            rounds <= Self::DEFAULT_ROUNDS,
+            "{}",
+            diag!("PRINCE has at most 5 rounds per side")
+        );
+        Self { rounds }
+    }
+
+    /// Number of rounds on each side of the middle layer.
+    pub const fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    /// Stretch a 64-bit key into `(k0, k1)`.
+    #[inline(always)]
+    fn expand_key(key: u64) -> (u64, u64) {
+        let mut z = key ^ 0x6A09E667F3BCC909;
+        z = (z ^ (z >> 32)).wrapping_mul(0xD6E8FEB86659FD93);
+        z = (z ^ (z >> 32)).wrapping_mul(0xD6E8FEB86659FD93);
+
+        (key, z ^ (z >> 32))
+    }
+
+    /// Encrypt one block under the 128-bit key `(k0, k1)`.
+    #[inline(always)]
+    fn encrypt_block(&self, block: u64, k0: u64, k1: u64) -> u64 {
+        core(block ^ k0, k1, self.rounds) ^ whiten(k0)
+    }
+
+    /// Decrypt one block under the 128-bit key `(k0, k1)`.
+    #[inline(always)]
+    fn decrypt_block(&self, block: u64, k0: u64, k1: u64) -> u64 {
+        core(block ^ whiten(k0), k1 ^ ALPHA, self.rounds) ^ k0
+    }
+}
+
+/// The derived whitening key `k0' = (k0 >>> 1) ^ (k0 >> 63)`.
+#[inline(always)]
+fn whiten(k0: u64) -> u64 {
+    k0.rotate_right(1) ^ (k0 >> 63)
+}
+
+/// PRINCEcore: forward rounds, the middle involution, backward rounds.
+///
+/// Round `i` is paired with round `11 - i`, so reduced variants take the
+/// constants from both ends inwards.
+#[inline(always)]
+fn core(mut state: u64, k1: u64, rounds: usize) -> u64 {
+    state ^= k1 ^ RC[0];
+
+    for rc in &RC[1..1 + rounds] {
+        state = shift_rows(m_prime(substitute(state, &SBOX)));
+        state ^= rc ^ k1;
+    }
+
+    state = substitute(m_prime(substitute(state, &SBOX)), &SBOX_INV);
+
+    for rc in &RC[11 - rounds..11] {
+        state ^= rc ^ k1;
+        state = substitute(m_prime(inverse_shift_rows(state)), &SBOX_INV);
+    }
+
+    state ^ RC[11] ^ k1
+}
+
+#[inline(always)]
+fn nibbles(state: u64) -> [u8; 16] {
+    std::array::from_fn(|i| (state >> (60 - 4 * i)) as u8 & 0xF)
+}
+
+#[inline(always)]
+fn from_nibbles(nibbles: [u8; 16]) -> u64 {
+    nibbles.iter().fold(0, |acc, &n| acc << 4 | n as u64)
+}
+
+#[inline(always)]
+fn substitute(state: u64, sbox: &[u8; 16]) -> u64 {
+    from_nibbles(nibbles(state).map(|n| sbox[n as usize]))
+}
+
+#[inline(always)]
+fn shift_rows(state: u64) -> u64 {
+    let n = nibbles(state);
+    from_nibbles(SHIFT_ROWS.map(|i| n[i]))
+}
+
+#[inline(always)]
+fn inverse_shift_rows(state: u64) -> u64 {
+    let n = nibbles(state);
+    let mut out = [0; 16];
+    for (i, &from) in SHIFT_ROWS.iter().enumerate() {
+        out[from] = n[i];
+    }
+    from_nibbles(out)
+}
+
+/// The linear layer M', an involution built from the `M̂0` and `M̂1` blocks.
+#[inline(always)]
+fn m_prime(state: u64) -> u64 {
+    let n = nibbles(state);
+    let mut out = [0; 16];
+
+    for (chunk, offset) in [0, 1, 1, 0].into_iter().enumerate() {
+        let input = &n[chunk * 4..chunk * 4 + 4];
+        let total = input[0] ^ input[1] ^ input[2] ^ input[3];
+
+        // output bit b of nibble r is the XOR of bit b over all input nibbles
+        // except nibble (b - r - offset) mod 4; bit 0 is the most significant.
+        for r in 0..4 {
+            let mut excluded = 0;
+            for b in 0..4 {
+                excluded |= input[(b + 8 - r - offset) % 4] & (0x8 >> b);
+            }
+            out[chunk * 4 + r] = total ^ excluded;
+        }
+    }
+
+    from_nibbles(out)
+}
+
+impl Encrypt for Prince {
+    #[inline(always)]
+    fn encrypt(&self, data: u64, key: u64) -> u64 {
+        let (k0, k1) = Self::expand_key(key);
+        self.encrypt_block(data, k0, k1)
+    }
+
+    #[inline(always)]
+    fn decrypt(&self, data: u64, key: u64) -> u64 {
+        let (k0, k1) = Self::expand_key(key);
+        self.decrypt_block(data, k0, k1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn prince_known_answer() {
+        // test vectors from the PRINCE paper: (plaintext, k0, k1, ciphertext).
+        let vectors = [
+            (0, 0, 0, 0x818665aa0d02dfda),
+            (u64::MAX, 0, 0, 0x604ae6ca03c20ada),
+            (0, u64::MAX, 0, 0x9fb51935fc3df524),
+            (0, 0, u64::MAX, 0x78a54cbe737bb7ef),
+            (
+                0x0123456789abcdef,
+                0,
+                0xfedcba9876543210,
+                0xae25ad3ca8fa9ccf,
+            ),
+        ];
+
+        for (plaintext, k0, k1, ciphertext) in vectors {
+            assert_eq!(Prince::new().encrypt_block(plaintext, k0, k1), ciphertext);
+            assert_eq!(Prince::new().decrypt_block(ciphertext, k0, k1), plaintext);
+        }
+    }
+
+    #[test]
+    fn decrypt_value_prince() {
+        let key: u64 = 0x1234567890ABCDEF;
+        let data: u64 = 0xFEDCBA0987654321;
+
+        for prince in [Prince::new(), Prince::with_rounds(2)] {
+            let encrypted = prince.encrypt(data, key);
+            let decrypted = prince.decrypt(encrypted, key);
+
+            assert_eq!(data, decrypted);
+        }
+    }
+}
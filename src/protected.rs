@@ -0,0 +1,227 @@
+use std::ffi::{CStr, CString};
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[cfg(any(not(feature = "explicit-access"), test))]
+use crate::{DropPolicy, SendEncryptedPtr};
+use crate::{EncryptedCString, EncryptedPtr, EncryptedString, EncryptedVec};
+
+/// Convert a value or container into its protected counterpart with `.protected()`.
+///
+/// `Box<T>` becomes an [`EncryptedPtr<T>`], `Vec<T>` an [`EncryptedVec<T>`],
+/// strings an [`EncryptedString`] or [`EncryptedCString`], and primitives and
+/// arrays an `EncryptedPtr` to themselves. `#[derive(Protect)]` does the
+/// same for your own types.
+pub trait Protect {
+    /// The protected type this converts into.
+    type Protected;
+
+    /// Move `self` into its protected counterpart.
+    fn protected(self) -> Self::Protected;
+}
+
+impl<T> Protect for Box<T> {
+    type Protected = EncryptedPtr<T>;
+
+    fn protected(self) -> Self::Protected {
+        self.into()
+    }
+}
+
+impl<T> Protect for Vec<T> {
+    type Protected = EncryptedVec<T>;
+
+    fn protected(self) -> Self::Protected {
+        self.into()
+    }
+}
+
+impl Protect for String {
+    type Protected = EncryptedString;
+
+    fn protected(self) -> Self::Protected {
+        self.into()
+    }
+}
+
+impl Protect for &str {
+    type Protected = EncryptedString;
+
+    fn protected(self) -> Self::Protected {
+        self.into()
+    }
+}
+
+impl Protect for CString {
+    type Protected = EncryptedCString;
+
+    fn protected(self) -> Self::Protected {
+        self.into()
+    }
+}
+
+impl Protect for &CStr {
+    type Protected = EncryptedCString;
+
+    fn protected(self) -> Self::Protected {
+        self.into()
+    }
+}
+
+impl<T, const N: usize> Protect for [T; N] {
+    type Protected = EncryptedPtr<[T; N]>;
+
+    fn protected(self) -> Self::Protected {
+        self.into()
+    }
+}
+
+macro_rules! protect_values {
+    ($($ty:ty),*) => {$(
+        impl Protect for $ty {
+            type Protected = EncryptedPtr<$ty>;
+
+            fn protected(self) -> Self::Protected {
+                self.into()
+            }
+        }
+    )*};
+}
+
+protect_values!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char
+);
+
+/// An owning pointer to a `T` that may or may not be protected.
+///
+/// Implemented by the protected pointers as well as `Box`, `Rc` and `Arc`,
+/// so library code can be generic over the pointer and an application can
+/// pick protected or plain pointers per build, e.g. with a `cfg`'d type
+/// alias, without touching the code that uses them.
+pub trait ProtectedDeref<T>: std::ops::Deref<Target = T> {
+    /// Whether this pointer encrypts its address.
+    const PROTECTED: bool;
+
+    /// Move `value` behind a new pointer.
+    fn from_value(value: T) -> Self;
+
+    /// Whether this pointer encrypts its address, see [`ProtectedDeref::PROTECTED`].
+    fn is_protected(&self) -> bool {
+        Self::PROTECTED
+    }
+}
+
+#[cfg(any(not(feature = "explicit-access"), test))]
+impl<T, P: DropPolicy> ProtectedDeref<T> for EncryptedPtr<T, P> {
+    const PROTECTED: bool = !cfg!(passthrough);
+
+    fn from_value(value: T) -> Self {
+        value.into()
+    }
+}
+
+#[cfg(any(not(feature = "explicit-access"), test))]
+impl<T, P: DropPolicy> ProtectedDeref<T> for SendEncryptedPtr<T, P> {
+    const PROTECTED: bool = !cfg!(passthrough);
+
+    fn from_value(value: T) -> Self {
+        value.into()
+    }
+}
+
+impl<T> ProtectedDeref<T> for Box<T> {
+    const PROTECTED: bool = false;
+
+    fn from_value(value: T) -> Self {
+        Box::new(value)
+    }
+}
+
+impl<T> ProtectedDeref<T> for Rc<T> {
+    const PROTECTED: bool = false;
+
+    fn from_value(value: T) -> Self {
+        Rc::new(value)
+    }
+}
+
+impl<T> ProtectedDeref<T> for Arc<T> {
+    const PROTECTED: bool = false;
+
+    fn from_value(value: T) -> Self {
+        Arc::new(value)
+    }
+}
+
+// the pointee only moves through `&mut self` methods such as
+// `EncryptedPtr::relocate` or growing a vector, which crates relying on
+// `StableDeref` never call while borrowing from the owner; the same holds for
+// `Vec` and `String`.
+#[cfg(all(feature = "stable_deref", any(not(feature = "explicit-access"), test)))]
+unsafe impl<T, P: DropPolicy> stable_deref_trait::StableDeref for EncryptedPtr<T, P> {}
+#[cfg(all(feature = "stable_deref", any(not(feature = "explicit-access"), test)))]
+unsafe impl<T, P: DropPolicy> stable_deref_trait::StableDeref for SendEncryptedPtr<T, P> {}
+#[cfg(feature = "stable_deref")]
+unsafe impl<T> stable_deref_trait::StableDeref for EncryptedVec<T> {}
+#[cfg(feature = "stable_deref")]
+unsafe impl stable_deref_trait::StableDeref for EncryptedString {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Library code that doesn't care how its data is held.
+    fn total_health<P: ProtectedDeref<u32>>(values: &[u32]) -> u32 {
+        let pointers: Vec<P> = values.iter().copied().map(P::from_value).collect();
+        pointers.iter().map(|health| **health).sum()
+    }
+
+    #[test]
+    fn protected_conversions() {
+        let health = 100u32.protected();
+        let player = Box::new([1u8, 2]).protected();
+        let inventory = vec![1, 2, 3].protected();
+        let name = "player".protected();
+
+        assert_eq!((*health, *player), (100, [1, 2]));
+        assert_eq!((&*inventory, &*name), (&[1, 2, 3][..], "player"));
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn derived_protect() {
+        #[derive(crate::Protect)]
+        struct Player {
+            health: u32,
+        }
+
+        assert_eq!(Player { health: 100 }.protected().health, 100);
+    }
+
+    #[cfg(feature = "stable_deref")]
+    #[test]
+    fn stable_deref_survives_moves() {
+        fn address<P: stable_deref_trait::StableDeref<Target = u32>>(ptr: &P) -> *const u32 {
+            &**ptr
+        }
+
+        let health: EncryptedPtr<u32> = 100.into();
+        let before = address(&health);
+        let moved = Box::new(health);
+
+        assert_eq!(address(&*moved), before);
+    }
+
+    #[test]
+    fn generic_over_protection() {
+        assert_eq!(total_health::<Box<u32>>(&[100, 50]), 150);
+        assert_eq!(total_health::<EncryptedPtr<u32>>(&[100, 50]), 150);
+
+        assert!(!Arc::from_value(1).is_protected());
+        assert_eq!(
+            EncryptedPtr::<u32>::from_value(1).is_protected(),
+            !cfg!(passthrough)
+        );
+    }
+}
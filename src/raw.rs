@@ -0,0 +1,145 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+
+use crate::sealed::Sealed;
+
+/// An encrypted raw pointer that doesn't own its pointee, the protected counterpart of `*mut T`.
+///
+/// Pointer arithmetic decrypts, adjusts and re-encrypts under a fresh key
+/// within one call, so walking a buffer never leaves a plaintext pointer in
+/// a local between statements.
+pub struct EncryptedRawPtr<T> {
+    sealed: Sealed,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> EncryptedRawPtr<T> {
+    /// Encrypt `ptr`.
+    #[inline(always)]
+    pub fn new(ptr: *mut T) -> Self {
+        Self {
+            sealed: Sealed::new(ptr as u64),
+            _marker: PhantomData,
+        }
+    }
+
+    /// An encrypted null pointer.
+    pub fn null() -> Self {
+        Self::new(ptr::null_mut())
+    }
+
+    /// Whether the pointer is null.
+    pub fn is_null(&self) -> bool {
+        self.as_ptr().is_null()
+    }
+
+    /// Decrypt the pointer.
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *mut T {
+        self.sealed.get() as *mut T
+    }
+
+    /// Offset by `count` elements, like [`pointer::offset`].
+    ///
+    /// # Safety
+    /// Same as [`pointer::offset`]: the result must stay within the same allocation.
+    #[inline(always)]
+    pub unsafe fn offset(&self, count: isize) -> Self {
+        Self::new(self.as_ptr().offset(count))
+    }
+
+    /// Offset forward by `count` elements, like [`pointer::add`].
+    ///
+    /// # Safety
+    /// Same as [`pointer::add`]: the result must stay within the same allocation.
+    #[inline(always)]
+    pub unsafe fn add(&self, count: usize) -> Self {
+        Self::new(self.as_ptr().add(count))
+    }
+
+    /// Offset forward by `count` bytes, like [`pointer::byte_add`].
+    ///
+    /// # Safety
+    /// Same as [`pointer::byte_add`]: the result must stay within the same allocation.
+    #[inline(always)]
+    pub unsafe fn byte_add(&self, count: usize) -> Self {
+        Self::new(self.as_ptr().byte_add(count))
+    }
+
+    /// Read the pointee, like [`pointer::read`].
+    ///
+    /// # Safety
+    /// Same as [`ptr::read`].
+    #[inline(always)]
+    pub unsafe fn read(&self) -> T {
+        self.as_ptr().read()
+    }
+
+    /// Overwrite the pointee without dropping the old value, like [`pointer::write`].
+    ///
+    /// # Safety
+    /// Same as [`ptr::write`].
+    #[inline(always)]
+    pub unsafe fn write(&self, value: T) {
+        self.as_ptr().write(value)
+    }
+}
+
+impl<T> Clone for EncryptedRawPtr<T> {
+    /// Re-encrypts under a fresh key, so the copy shares no ciphertext.
+    fn clone(&self) -> Self {
+        Self::new(self.as_ptr())
+    }
+}
+
+impl<T> Default for EncryptedRawPtr<T> {
+    fn default() -> Self {
+        Self::null()
+    }
+}
+
+impl<T> From<*mut T> for EncryptedRawPtr<T> {
+    fn from(value: *mut T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> fmt::Debug for EncryptedRawPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedRawPtr");
+        f.debug_struct("EncryptedRawPtr")
+            .field(
+                "encrypted_value",
+                &format!("{:#x}", self.sealed.ciphertext()),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn raw_pointer_arithmetic() {
+        let mut buffer = [1u32, 2, 3, 4];
+        let mut cursor = EncryptedRawPtr::new(buffer.as_mut_ptr());
+
+        let mut sum = 0;
+        for _ in 0..buffer.len() {
+            unsafe {
+                sum += cursor.read();
+                cursor = cursor.add(1);
+            }
+        }
+        assert_eq!(sum, 10);
+
+        unsafe {
+            cursor.offset(-1).write(40);
+            assert_eq!(cursor.offset(-4).byte_add(4).read(), 2);
+        }
+        assert_eq!(buffer, [1, 2, 3, 40]);
+    }
+}
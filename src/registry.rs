@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::audit::AuditTarget;
+use crate::diag::type_name;
+use crate::stats;
+
+/// Whether new pointers should be registered.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+struct Registry {
+    /// Mask applied to every stored address, so the registry itself never
+    /// holds a plaintext pointer. Kept here only, never next to an address.
+    mask: u64,
+    next_id: u64,
+    entries: HashMap<u64, RegistryEntry>,
+}
+
+/// A live protected pointer known to the registry.
+///
+/// Entries are weak: they describe the pointer without keeping it alive, and
+/// disappear when it is dropped.
+#[derive(Debug, Clone)]
+pub struct RegistryEntry {
+    /// Unique id, assigned in registration order.
+    pub id: u64,
+    /// Type name of the protected object.
+    pub type_name: &'static str,
+    /// Size of the protected object in bytes.
+    pub size: usize,
+    masked_addr: u64,
+}
+
+impl RegistryEntry {
+    /// Describe this pointer for a [`self_audit`](crate::self_audit) scan.
+    pub fn audit_target(&self) -> AuditTarget {
+        AuditTarget::from_raw(self.masked_addr ^ mask(), self.size, self.type_name)
+    }
+}
+
+/// Start registering every newly created protected pointer.
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Stop registering pointers and forget the ones registered so far.
+pub fn disable() {
+    ENABLED.store(false, Ordering::SeqCst);
+
+    // the mask stays, so entries taken earlier still unmask.
+    if let Some(registry) = lock().as_mut() {
+        registry.entries.clear();
+    }
+}
+
+/// Whether the registry is enabled.
+#[inline(always)]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Snapshot of every registered pointer that is still alive.
+pub fn entries() -> Vec<RegistryEntry> {
+    let mut entries: Vec<_> = lock()
+        .as_ref()
+        .map(|registry| registry.entries.values().cloned().collect())
+        .unwrap_or_default();
+
+    entries.sort_by_key(|entry| entry.id);
+    entries
+}
+
+/// Number of registered pointers that are still alive.
+pub fn len() -> usize {
+    lock().as_ref().map_or(0, |registry| registry.entries.len())
+}
+
+/// Audit targets for every registered pointer.
+pub fn audit_targets() -> Vec<AuditTarget> {
+    entries().iter().map(RegistryEntry::audit_target).collect()
+}
+
+/// Id of the registered pointer to `addr`, if any.
+pub(crate) fn id_of(addr: u64) -> Option<u64> {
+    if !is_enabled() {
+        return None;
+    }
+
+    let guard = lock();
+    let registry = guard.as_ref()?;
+    registry
+        .entries
+        .get(&(addr ^ registry.mask))
+        .map(|entry| entry.id)
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Registry>> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The address mask; entries only exist once the registry does.
+fn mask() -> u64 {
+    lock().as_ref().map_or(0, |registry| registry.mask)
+}
+
+/// Record a new pointer, if the registry is enabled.
+#[inline(always)]
+pub(crate) fn register<T>(ptr: *mut T) {
+    // every owning pointer passes through here, so count it for `stats` too.
+    stats::allocated(size_of::<T>());
+
+    // zero-sized pointees all share the dangling address, so can't be told apart.
+    if is_enabled() && size_of::<T>() != 0 {
+        insert(ptr as u64, size_of::<T>(), type_name::<T>());
+    }
+}
+
+#[cold]
+fn insert(addr: u64, size: usize, type_name: &'static str) {
+    let mut guard = lock();
+    let registry = guard.get_or_insert_with(|| Registry {
+        mask: rand::random(),
+        next_id: 0,
+        entries: HashMap::new(),
+    });
+
+    let id = registry.next_id;
+    registry.next_id += 1;
+
+    let masked_addr = addr ^ registry.mask;
+    registry.entries.insert(
+        masked_addr,
+        RegistryEntry {
+            id,
+            type_name,
+            size,
+            masked_addr,
+        },
+    );
+}
+
+/// Forget a pointer that is about to be freed.
+#[inline(always)]
+pub(crate) fn unregister<T>(ptr: *mut T) {
+    stats::freed(size_of::<T>());
+
+    if is_enabled() && size_of::<T>() != 0 {
+        remove(ptr as u64);
+    }
+}
+
+#[cold]
+fn remove(addr: u64) {
+    if let Some(registry) = lock().as_mut() {
+        let mask = registry.mask;
+        registry.entries.remove(&(addr ^ mask));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EncryptedPtr;
+
+    #[test]
+    fn registry_tracks_live_pointers() {
+        enable();
+
+        let player: EncryptedPtr<u32> = Box::new(100).into();
+        let target = player.decrypt_ptr() as u64;
+
+        // other tests may register pointers concurrently, so look for ours only.
+        let registered = |addr: u64| {
+            entries()
+                .iter()
+                .any(|entry| entry.masked_addr ^ mask() == addr)
+        };
+
+        assert!(registered(target));
+        drop(player);
+        assert!(!registered(target));
+    }
+
+    #[test]
+    fn zero_sized_pointees_are_not_registered() {
+        enable();
+
+        let marker: EncryptedPtr<()> = Box::new(()).into();
+        let target = marker.decrypt_ptr() as u64;
+
+        assert!(entries()
+            .iter()
+            .all(|entry| entry.masked_addr ^ mask() != target));
+    }
+}
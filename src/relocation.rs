@@ -0,0 +1,213 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::diag::diag;
+use crate::{master, SendEncryptedPtr};
+
+/// Something the relocation daemon can move.
+trait Relocate: Send + Sync {
+    /// Relocate unless currently borrowed, returning whether it moved.
+    fn try_relocate(&self) -> bool;
+}
+
+struct Slot<T> {
+    ptr: Mutex<SendEncryptedPtr<T>>,
+}
+
+impl<T: Send> Relocate for Slot<T> {
+    fn try_relocate(&self) -> bool {
+        let mut ptr = match self.ptr.try_lock() {
+            Ok(ptr) => ptr,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return false,
+        };
+
+        ptr.relocate();
+        true
+    }
+}
+
+/// Every live [`RelocatingPtr`], weakly.
+static SLOTS: Mutex<Vec<Weak<dyn Relocate>>> = Mutex::new(Vec::new());
+
+/// An encrypted pointer whose pointee the relocation daemon moves periodically.
+///
+/// Accesses go through [`RelocatingPtr::access`], whose guard marks the
+/// pointer as borrowed: [`relocate_all`] and the daemon skip borrowed
+/// pointers and catch them on the next pass.
+pub struct RelocatingPtr<T: Send + 'static> {
+    slot: Arc<Slot<T>>,
+}
+
+impl<T: Send + 'static> RelocatingPtr<T> {
+    /// Move `value` to the heap and enroll it for relocation.
+    pub fn new(value: T) -> Self {
+        let slot = Arc::new(Slot {
+            ptr: Mutex::new(value.into()),
+        });
+
+        let weak = Arc::downgrade(&slot) as Weak<dyn Relocate>;
+        let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+
+        // a dead slot's allocation lives as long as its weak, so prune
+        // before growing, which keeps pushes amortized O(1).
+        if slots.len() == slots.capacity() {
+            slots.retain(|slot| slot.strong_count() != 0);
+        }
+        slots.push(weak);
+        Self { slot }
+    }
+
+    /// Borrow the pointee, holding off relocation until the guard drops.
+    pub fn access(&self) -> RelocationGuard<'_, T> {
+        RelocationGuard {
+            ptr: self.slot.ptr.lock().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+}
+
+impl<T: Send + fmt::Debug + 'static> fmt::Debug for RelocatingPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "RelocatingPtr");
+        f.debug_struct("RelocatingPtr")
+            .field("pointed_value", &*self.access())
+            .finish()
+    }
+}
+
+/// Access to a [`RelocatingPtr`], which can't move while this is alive.
+pub struct RelocationGuard<'a, T: Send + 'static> {
+    ptr: MutexGuard<'a, SendEncryptedPtr<T>>,
+}
+
+impl<T: Send + 'static> Deref for RelocationGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.ptr.inner.access()
+    }
+}
+
+impl<T: Send + 'static> DerefMut for RelocationGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.ptr.inner.access_mut()
+    }
+}
+
+/// Relocate every unborrowed [`RelocatingPtr`] once, returning how many moved.
+pub fn relocate_all() -> usize {
+    // a poisoned process can't decrypt, so leave everything where it is.
+    if master::is_poisoned() {
+        return 0;
+    }
+
+    // upgrade under the lock, relocate outside it so new pointers aren't held up.
+    let live: Vec<_> = {
+        let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+        slots.retain(|slot| slot.strong_count() != 0);
+        slots.iter().filter_map(Weak::upgrade).collect()
+    };
+
+    live.iter().filter(|slot| slot.try_relocate()).count()
+}
+
+/// Handle to the thread started by [`start_relocation_daemon`]; dropping it stops the thread.
+pub struct RelocationDaemon {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RelocationDaemon {
+    /// Stop the daemon and wait for its thread to exit.
+    pub fn stop(self) {}
+}
+
+impl Drop for RelocationDaemon {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl fmt::Debug for RelocationDaemon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "RelocationDaemon");
+        f.debug_struct("RelocationDaemon").finish_non_exhaustive()
+    }
+}
+
+/// Start a thread that runs [`relocate_all`] every `interval`.
+///
+/// Addresses a scanner finds go stale within one interval, without any code
+/// at the access sites.
+pub fn start_relocation_daemon(interval: Duration) -> RelocationDaemon {
+    let (stop, stopped) = mpsc::channel::<()>();
+    let thread = thread::Builder::new()
+        .name("pointerguard-relocation".into())
+        .spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                relocate_all();
+            }
+        })
+        .expect(diag!("failed to spawn the relocation daemon"));
+
+    RelocationDaemon {
+        stop: Some(stop),
+        thread: Some(thread),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn addr<T: Send>(ptr: &RelocatingPtr<T>) -> *const T {
+        &*ptr.access() as *const T
+    }
+
+    #[test]
+    fn relocate_all_skips_borrowed() {
+        let health = RelocatingPtr::new(100u32);
+        let mana = RelocatingPtr::new(50u32);
+        let (health_before, mana_before) = (addr(&health), addr(&mana));
+
+        let borrowed = mana.access();
+        relocate_all();
+
+        assert_eq!(&*borrowed as *const u32, mana_before);
+        assert_ne!(addr(&health), health_before);
+        assert_eq!(*health.access(), 100);
+    }
+
+    #[test]
+    fn dropped_pointers_are_pruned() {
+        for value in 0..1024u32 {
+            drop(RelocatingPtr::new(value));
+        }
+
+        // other tests' pointers may be live too, but not a thousand of them.
+        assert!(SLOTS.lock().unwrap_or_else(|e| e.into_inner()).len() < 512);
+    }
+
+    #[test]
+    fn daemon_relocates_in_background() {
+        let health = RelocatingPtr::new(100u32);
+        let before = addr(&health);
+
+        let daemon = start_relocation_daemon(Duration::from_millis(1));
+        while addr(&health) == before {
+            thread::sleep(Duration::from_millis(1));
+        }
+        daemon.stop();
+
+        *health.access() += 1;
+        assert_eq!(*health.access(), 101);
+    }
+}
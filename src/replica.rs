@@ -0,0 +1,142 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use crate::diag::diag;
+use crate::EncryptedPtr;
+
+/// A value kept in `K` independently encrypted replicas at different addresses.
+///
+/// Every read picks a replica at random and every write is copied to all of
+/// them, so an attacker who finds and freezes one copy only controls a
+/// fraction of the reads, and [`ReplicatedPtr::is_consistent`] notices.
+pub struct ReplicatedPtr<T: Clone, const K: usize = 3> {
+    replicas: [EncryptedPtr<T>; K],
+}
+
+impl<T: Clone, const K: usize> ReplicatedPtr<T, K> {
+    /// Store `K` copies of `value`.
+    ///
+    /// # Panics
+    /// If `K` is zero.
+    pub fn new(value: T) -> Self {
+        assert!(
+            K != 0,
+            "{}",
+            diag!("ReplicatedPtr needs at least one replica")
+        );
+
+        Self {
+            replicas: std::array::from_fn(|_| value.clone().into()),
+        }
+    }
+
+    /// A replica chosen at random.
+    #[inline(always)]
+    fn pick(&self) -> &EncryptedPtr<T> {
+        &self.replicas[rand::random_range(0..K)]
+    }
+
+    /// Get write access to one replica; it is copied to the others when the guard drops.
+    pub fn access_mut(&mut self) -> ReplicaGuardMut<'_, T, K> {
+        ReplicaGuardMut {
+            index: rand::random_range(0..K),
+            replicated: self,
+        }
+    }
+
+    /// Replace the value in every replica.
+    pub fn set(&mut self, value: T) {
+        *self.access_mut() = value;
+    }
+
+    /// Copy replica `index` over every other one.
+    fn propagate(&mut self, index: usize) {
+        let (before, rest) = self.replicas.split_at_mut(index);
+        let (source, after) = rest.split_first_mut().unwrap();
+
+        for replica in before.iter_mut().chain(after) {
+            T::clone_from(replica.access_mut(), source.access());
+        }
+    }
+}
+
+impl<T: Clone + PartialEq, const K: usize> ReplicatedPtr<T, K> {
+    /// Whether every replica still holds the same value.
+    pub fn is_consistent(&self) -> bool {
+        self.replicas
+            .windows(2)
+            .all(|pair| pair[0].access() == pair[1].access())
+    }
+}
+
+impl<T: Clone, const K: usize> Deref for ReplicatedPtr<T, K> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        self.pick().access()
+    }
+}
+
+impl<T: Clone + fmt::Debug, const K: usize> fmt::Debug for ReplicatedPtr<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ReplicatedPtr");
+        f.debug_struct("ReplicatedPtr")
+            .field("replicas", &K)
+            .field("pointed_value", self.pick().access())
+            .finish()
+    }
+}
+
+/// Write access to one replica of a [`ReplicatedPtr`].
+pub struct ReplicaGuardMut<'a, T: Clone, const K: usize> {
+    replicated: &'a mut ReplicatedPtr<T, K>,
+    index: usize,
+}
+
+impl<T: Clone, const K: usize> Deref for ReplicaGuardMut<'_, T, K> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.replicated.replicas[self.index].access()
+    }
+}
+
+impl<T: Clone, const K: usize> DerefMut for ReplicaGuardMut<'_, T, K> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.replicated.replicas[self.index].access_mut()
+    }
+}
+
+impl<T: Clone, const K: usize> Drop for ReplicaGuardMut<'_, T, K> {
+    fn drop(&mut self) {
+        self.replicated.propagate(self.index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn writes_reach_every_replica() {
+        let mut health: ReplicatedPtr<u32> = ReplicatedPtr::new(100);
+        *health.access_mut() -= 25;
+
+        assert!(health.is_consistent());
+        assert!(health.replicas.iter().all(|replica| **replica == 75));
+        assert_eq!(*health, 75);
+    }
+
+    #[test]
+    fn frozen_replica_is_inconsistent() {
+        let mut health: ReplicatedPtr<u32, 4> = ReplicatedPtr::new(100);
+
+        // simulate a cheat freezing the one copy it found.
+        *health.replicas[2] = 9999;
+
+        assert!(!health.is_consistent());
+        assert!((0..256).any(|_| *health == 100));
+    }
+}
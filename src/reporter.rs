@@ -0,0 +1,111 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::tamper::TamperEvent;
+use crate::{HeartbeatReport, Stats};
+
+/// One sink for everything pointerguard reports, e.g. sentry, an anti-cheat backend or a log file.
+///
+/// Install one with [`set_reporter`]. Every method has an empty default, so
+/// implement only what the sink cares about; with none installed nothing is
+/// reported. Methods run on whichever thread made the report.
+pub trait Reporter: Send + Sync {
+    /// Tampering was detected, before the [`TamperPolicy`](crate::TamperPolicy) applies.
+    fn on_tamper(&self, _event: &TamperEvent) {}
+    /// A [`heartbeat`](crate::heartbeat) pass finished.
+    fn on_heartbeat(&self, _report: &HeartbeatReport) {}
+    /// A statistics snapshot, taken on every heartbeat and by [`report_stats`].
+    fn on_stats(&self, _stats: &Stats) {}
+}
+
+/// Whether a reporter is installed, so reports skip the lock otherwise.
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+static REPORTER: RwLock<Option<Arc<dyn Reporter>>> = RwLock::new(None);
+
+/// Install `reporter`, replacing any previous one.
+pub fn set_reporter(reporter: impl Reporter + 'static) {
+    *REPORTER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(reporter));
+    REPORTING.store(true, Ordering::Release);
+}
+
+/// Remove the reporter, if any.
+pub fn clear_reporter() {
+    REPORTING.store(false, Ordering::Release);
+    *REPORTER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Hand the current [`stats`](crate::stats) to the reporter.
+pub fn report_stats() {
+    with_reporter(|reporter| reporter.on_stats(&crate::stats()));
+}
+
+/// Run `f` with the reporter, if one is installed.
+#[inline(always)]
+pub(crate) fn with_reporter(f: impl FnOnce(&dyn Reporter)) {
+    if REPORTING.load(Ordering::Relaxed) {
+        // the reporter may itself be replaced from inside a report, so don't hold the lock.
+        let reporter = REPORTER.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(reporter) = reporter {
+            f(&*reporter);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heartbeat;
+    use pretty_assertions::assert_eq;
+    use std::sync::atomic::AtomicUsize;
+
+    #[derive(Default)]
+    struct Counts {
+        heartbeats: AtomicUsize,
+        stats: AtomicUsize,
+    }
+
+    struct Counter(Arc<Counts>);
+
+    /// Removes itself on its first report.
+    struct Once(Arc<Counts>);
+
+    impl Reporter for Once {
+        fn on_stats(&self, _stats: &Stats) {
+            self.0.stats.fetch_add(1, Ordering::Relaxed);
+            clear_reporter();
+        }
+    }
+
+    impl Reporter for Counter {
+        fn on_heartbeat(&self, _report: &HeartbeatReport) {
+            self.0.heartbeats.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_stats(&self, _stats: &Stats) {
+            self.0.stats.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn reporter_receives_heartbeats_and_stats() {
+        let counts = Arc::new(Counts::default());
+        set_reporter(Counter(counts.clone()));
+
+        heartbeat();
+        report_stats();
+        clear_reporter();
+        heartbeat();
+
+        // other tests may run heartbeats meanwhile, so only check lower bounds.
+        assert!(counts.heartbeats.load(Ordering::Relaxed) >= 1);
+        assert!(counts.stats.load(Ordering::Relaxed) >= 2);
+
+        // in the same test, since the reporter is process-wide.
+        let once = Arc::new(Counts::default());
+        set_reporter(Once(once.clone()));
+        report_stats();
+        report_stats();
+        assert_eq!(once.stats.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,201 @@
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+
+use crate::diag::diag;
+use crate::pod;
+use crate::sealed::Sealed;
+
+/// Default number of value-sized slots in the block.
+const DEFAULT_SLOTS: usize = 8;
+
+/// Default number of mutable accesses between automatic moves.
+const DEFAULT_INTERVAL: u32 = 256;
+
+/// A value placed at a random offset inside an oversized, noise-filled block.
+///
+/// Both the block and the value's address are encrypted. The value is moved
+/// to a fresh offset by [`ScatteredPtr::reposition`], and automatically every
+/// few mutable accesses, so its address within the block isn't stable.
+pub struct ScatteredPtr<T> {
+    block: Sealed,
+    value: Sealed,
+    layout: Layout,
+    interval: u32,
+    accesses: u32,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> ScatteredPtr<T> {
+    /// Place `value` in a block with room for eight copies of it.
+    pub fn new(value: T) -> Self {
+        Self::with_slots(value, DEFAULT_SLOTS)
+    }
+
+    /// Place `value` in a block with room for `slots` copies of it.
+    pub fn with_slots(value: T, slots: usize) -> Self {
+        let size = size_of::<T>()
+            .checked_mul(slots.max(1))
+            .expect(diag!("block too large"))
+            .max(1);
+        let layout =
+            Layout::from_size_align(size, align_of::<T>()).expect(diag!("block too large"));
+
+        let block = unsafe { alloc::alloc(layout) };
+        if block.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+
+        let offset = random_offset::<T>(layout, None);
+        unsafe {
+            fill_noise(block, layout.size());
+            ptr::write(block.add(offset) as *mut T, value);
+        }
+
+        Self {
+            block: Sealed::new(block as u64),
+            value: Sealed::new(block as u64 + offset as u64),
+            layout,
+            interval: DEFAULT_INTERVAL,
+            accesses: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set how many mutable accesses happen between automatic moves; zero disables them.
+    pub fn set_reposition_interval(&mut self, interval: u32) {
+        self.interval = interval;
+        self.accesses = 0;
+    }
+
+    /// Move the value to a new random offset, refilling the block with noise.
+    pub fn reposition(&mut self) {
+        let block = self.block.get() as *mut u8;
+        let current = self.value.get() as *mut T;
+        let offset = random_offset::<T>(self.layout, Some(current as usize - block as usize));
+
+        unsafe {
+            let mut temp = MaybeUninit::<T>::uninit();
+            ptr::copy_nonoverlapping(current, temp.as_mut_ptr(), 1);
+
+            fill_noise(block, self.layout.size());
+            ptr::copy_nonoverlapping(temp.as_ptr(), block.add(offset) as *mut T, 1);
+
+            pod::wipe(temp.as_mut_ptr());
+        }
+
+        self.value = Sealed::new(block as u64 + offset as u64);
+    }
+}
+
+/// Pick an aligned offset for a T inside the block, avoiding `current` if possible.
+fn random_offset<T>(layout: Layout, current: Option<usize>) -> usize {
+    let align = align_of::<T>();
+    let positions = (layout.size() - size_of::<T>()) / align + 1;
+
+    if positions <= 1 {
+        return 0;
+    }
+
+    loop {
+        let offset = rand::random_range(0..positions) * align;
+        if Some(offset) != current {
+            return offset;
+        }
+    }
+}
+
+/// Overwrite `len` bytes at `ptr` with random noise.
+unsafe fn fill_noise(ptr: *mut u8, len: usize) {
+    for i in 0..len {
+        ptr.add(i).write(rand::random());
+    }
+}
+
+impl<T> Deref for ScatteredPtr<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        unsafe { &*(self.value.get() as *const T) }
+    }
+}
+
+impl<T> DerefMut for ScatteredPtr<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut T {
+        // exclusive access means nothing borrows the old location, so it's safe to move.
+        if self.interval != 0 {
+            self.accesses += 1;
+            if self.accesses >= self.interval {
+                self.accesses = 0;
+                self.reposition();
+            }
+        }
+
+        unsafe { &mut *(self.value.get() as *mut T) }
+    }
+}
+
+impl<T> Drop for ScatteredPtr<T> {
+    fn drop(&mut self) {
+        if crate::is_poisoned() {
+            return;
+        }
+
+        unsafe {
+            ptr::drop_in_place(self.value.get() as *mut T);
+            alloc::dealloc(self.block.get() as *mut u8, self.layout);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ScatteredPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ScatteredPtr");
+        f.debug_struct("ScatteredPtr")
+            .field("encrypted_value", &format!("{:#x}", self.value.ciphertext()))
+            .field("pointed_value", &**self)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn scattered_value_survives_moves() {
+        let mut scattered = ScatteredPtr::with_slots(String::from("player"), 4);
+        let before = &*scattered as *const String;
+
+        scattered.reposition();
+        scattered.push_str(" one");
+
+        assert_eq!(*scattered, "player one");
+        assert!(!ptr::eq(before, &*scattered));
+    }
+
+    #[test]
+    fn scattered_moves_automatically() {
+        let mut scattered = ScatteredPtr::new(0u64);
+        scattered.set_reposition_interval(1);
+
+        let before = &*scattered as *const u64;
+        *scattered += 1;
+
+        assert_eq!(*scattered, 1);
+        assert!(!ptr::eq(before, &*scattered));
+    }
+
+    #[cfg(not(feature = "opaque-diagnostics"))]
+    #[test]
+    #[should_panic(expected = "block too large")]
+    fn oversized_blocks_are_rejected() {
+        let _ = ScatteredPtr::with_slots(0u64, usize::MAX / 2);
+    }
+}
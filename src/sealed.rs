@@ -0,0 +1,729 @@
+use std::any::TypeId;
+#[cfg(not(passthrough))]
+use std::marker::PhantomData;
+#[cfg(not(passthrough))]
+use std::ptr;
+
+#[cfg(all(feature = "mac", not(passthrough)))]
+use crate::aes;
+use crate::diag::diag_panic;
+#[cfg(not(passthrough))]
+use crate::layout::{Decoy, Layout};
+#[cfg(not(passthrough))]
+use crate::tamper::{self, TamperEvent, TamperKind};
+use crate::{config, master, methods, Encrypt, MethodA, MethodB, MethodC};
+
+/// An encrypted address with no ownership semantics.
+///
+/// This is the building block of every protected pointer type: it only knows
+/// how to hide and recover an address, the owning types decide what it means.
+///
+/// Outside the paranoid and diffused layouts this is two words: the
+/// ciphertext, and a key word whose low byte tags how to decrypt (see
+/// [`TAG_KEYED`]).
+#[cfg(not(passthrough))]
+pub(crate) struct Sealed {
+    layout: Layout,
+    // the key word may own a boxed method, so keep the auto traits of one.
+    _marker: PhantomData<Box<dyn Encrypt>>,
+}
+
+/// The tag byte of a key word.
+#[cfg(not(passthrough))]
+const TAG_MASK: u64 = 0xFF;
+
+/// The top byte of a key word: the master key version it was encrypted under.
+///
+/// Not part of the key: [`word_mask`] refills it from the bits below.
+#[cfg(not(passthrough))]
+const VERSION_MASK: u64 = 0xFF << 56;
+
+/// Tag bit set for methods run in full.
+///
+/// When clear, the tag is the rotation of a precomputed built-in schedule and
+/// the rest of the word is its mask, still bound to the master key. When set,
+/// the low 7 bits are an index into the shared method table and the rest of
+/// the word is the key, or [`TAG_OWNED`].
+#[cfg(not(passthrough))]
+pub(crate) const TAG_KEYED: u8 = 0x80;
+
+/// Tag of a method owned by this pointer: the rest of the word is the masked
+/// address of an [`Owned`].
+#[cfg(not(passthrough))]
+pub(crate) const TAG_OWNED: u8 = 0xFF;
+
+/// A method owned by a single pointer, with the full key it encrypted under.
+///
+/// Aligned so its address leaves the tag byte free.
+#[cfg(not(passthrough))]
+#[repr(align(256))]
+struct Owned {
+    key: u64,
+    method: Box<dyn Encrypt>,
+}
+
+/// A plain address, so debuggers can follow protected pointers.
+#[cfg(passthrough)]
+pub(crate) struct Sealed {
+    addr: u64,
+}
+
+/// Generate a fresh key from the configured [`KeySource`](crate::KeySource), whitened.
+#[inline(always)]
+pub(crate) fn generate_key() -> u64 {
+    config::generate_key()
+}
+
+/// An encryption method, with the built-ins dispatched inline.
+///
+/// The built-ins are evaluated in closed form (see [`MethodA::schedule`]):
+/// the rotation and mask only depend on the key, so [`Sealed`] precomputes
+/// them and decryption itself is one rotate and one XOR.
+pub(crate) enum Method {
+    A,
+    B,
+    C,
+    /// Leaves data untouched, for the `passthrough` feature.
+    Plain,
+    /// A method in the shared table, by index.
+    Shared(u8),
+    /// A method owned by one pointer.
+    Owned(Box<dyn Encrypt>),
+}
+
+impl Method {
+    /// Wrap a user-supplied method, recognizing the built-ins.
+    pub(crate) fn from_user<M: Encrypt + 'static>(method: M) -> Self {
+        match TypeId::of::<M>() {
+            id if id == TypeId::of::<MethodA>() => Method::A,
+            id if id == TypeId::of::<MethodB>() => Method::B,
+            id if id == TypeId::of::<MethodC>() => Method::C,
+            _ => Method::Owned(Box::new(method)),
+        }
+    }
+
+    /// The schedule behind [`Method::schedule`], for the three built-in methods only.
+    #[cfg(not(passthrough))]
+    #[inline(always)]
+    fn schedule_fn(&self) -> Option<fn(u64) -> (u32, u64)> {
+        match self {
+            Method::A => Some(MethodA::schedule),
+            Method::B => Some(MethodB::schedule),
+            Method::C => Some(MethodC::schedule),
+            _ => None,
+        }
+    }
+
+    /// The closed-form `(rotation, mask)` for `key`, for built-in methods only.
+    #[inline(always)]
+    pub(crate) fn schedule(&self, key: u64) -> Option<(u32, u64)> {
+        match self {
+            Method::A => Some(MethodA::schedule(key)),
+            Method::B => Some(MethodB::schedule(key)),
+            Method::C => Some(MethodC::schedule(key)),
+            Method::Plain => Some((0, 0)),
+            Method::Shared(_) | Method::Owned(_) => None,
+        }
+    }
+}
+
+impl Encrypt for Method {
+    #[inline(always)]
+    fn encrypt(&self, data: u64, key: u64) -> u64 {
+        match self {
+            Method::Shared(index) => methods::shared(*index).encrypt(data, key),
+            Method::Owned(method) => method.encrypt(data, key),
+            builtin => {
+                let (rot, mask) = builtin.schedule(key).unwrap();
+                (data ^ mask).rotate_left(rot)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn decrypt(&self, data: u64, key: u64) -> u64 {
+        match self {
+            Method::Shared(index) => methods::shared(*index).decrypt(data, key),
+            Method::Owned(method) => method.decrypt(data, key),
+            builtin => {
+                let (rot, mask) = builtin.schedule(key).unwrap();
+                data.rotate_right(rot) ^ mask
+            }
+        }
+    }
+}
+
+/// Number of built-in encryption methods.
+pub(crate) const BUILTIN_METHODS: usize = 3;
+
+/// Get a built-in encryption method by index.
+pub(crate) fn builtin_method(index: usize) -> Option<Method> {
+    match index {
+        0 => Some(Method::A),
+        1 => Some(Method::B),
+        2 => Some(Method::C),
+        _ => None,
+    }
+}
+
+/// Choose a method from the rotation at random, see [`crate::register_method`].
+#[inline(always)]
+pub(crate) fn random_method() -> Method {
+    methods::random_method()
+}
+
+#[cfg(not(passthrough))]
+impl Sealed {
+    /// Encrypt `addr` under a fresh key and random method.
+    #[inline(always)]
+    pub(crate) fn new(addr: u64) -> Self {
+        // choose a random method to encrypt the pointer with.
+        Self::with_method(addr, random_method())
+    }
+
+    /// Encrypt `addr` under a fresh key and the given method.
+    #[inline(always)]
+    pub(crate) fn with_method(addr: u64, method: Method) -> Self {
+        let schedule = method.schedule_fn();
+        let (encrypted_ptr, word) = Self::encode(addr, method);
+
+        // decoy fields (paranoid and diffused layouts only) look like more keys and ciphertexts.
+        let layout = Layout::new(encrypted_ptr, word, |decoy| match decoy {
+            Decoy::Ciphertext => {
+                Method::B.encrypt(addr ^ rand::random_range(0..0x10000) << 4, rand::random())
+            }
+            Decoy::Key => decoy_word(word, schedule),
+        });
+
+        Self {
+            layout,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Encrypt `addr` under a fresh key, returning the ciphertext and tagged key word.
+    ///
+    /// An owned method is moved to the heap and the word takes ownership of it.
+    /// The pair counts as live under its key version until [`Sealed::release`]d.
+    #[inline(always)]
+    pub(crate) fn encode(addr: u64, method: Method) -> (u64, u64) {
+        // generate a random key, maybe let user decide.
+        let key = generate_key();
+        let (version, master) = master::acquire_current();
+        let version = (version as u64) << 56;
+
+        // encrypt the pointer, binding it to the process master key. the key
+        // word gives up its low byte to the tag and its top byte to the version.
+        match method.schedule(key ^ master) {
+            Some((rot, mask)) => {
+                let word = ((mask ^ master) & !(TAG_MASK | VERSION_MASK)) | version | rot as u64;
+                let mask = word_mask(word, master);
+                (
+                    (embed_mac(addr, word, master) ^ mask).rotate_left(rot),
+                    word,
+                )
+            }
+            None => match method {
+                Method::Shared(index) => {
+                    let word =
+                        (key & !(TAG_MASK | VERSION_MASK)) | version | (TAG_KEYED | index) as u64;
+                    let addr = embed_mac(addr, word, master);
+                    (
+                        methods::shared(index).encrypt(addr, word_mask(word, master)),
+                        word,
+                    )
+                }
+                Method::Owned(method) => {
+                    let owned = Box::into_raw(Box::new(Owned { key, method }));
+                    let word = ((owned as u64 ^ master) & !(TAG_MASK | VERSION_MASK))
+                        | version
+                        | TAG_OWNED as u64;
+                    let addr = embed_mac(addr, word, master);
+                    let owned = unsafe { &*owned };
+                    (owned.method.encrypt(addr, key ^ master), word)
+                }
+                _ => diag_panic!("built-in methods always have a schedule"),
+            },
+        }
+    }
+
+    /// Recover the address by decrypting.
+    #[inline(always)]
+    pub(crate) fn get(&self) -> u64 {
+        Self::decode(self.layout.encrypted_ptr(), self.layout.key())
+    }
+
+    /// Recover the address, or `None` where [`Sealed::get`] would panic.
+    ///
+    /// That is once poisoned, under a forgotten key version, or on a failed
+    /// MAC, which is still reported to the tamper callback.
+    #[cfg(feature = "ffi")]
+    pub(crate) fn try_get(&self) -> Option<u64> {
+        if master::is_poisoned() {
+            return None;
+        }
+
+        let (encrypted_ptr, word) = (self.layout.encrypted_ptr(), self.layout.key());
+        let master = master::for_version(word_version(word))?;
+        let addr = Self::try_decode_with(encrypted_ptr, word, master);
+        if addr.is_none() {
+            tamper::report(TamperEvent::new(
+                TamperKind::MacMismatch,
+                crate::diag::diag!("<sealed pointer>"),
+            ));
+        }
+        addr
+    }
+
+    /// Decrypt a ciphertext and key word from [`Sealed::encode`].
+    ///
+    /// With the `mac` feature, a pair that fails its MAC is reported to the
+    /// tamper callback and panics rather than yield a forged address.
+    #[inline(always)]
+    pub(crate) fn decode(encrypted_ptr: u64, word: u64) -> u64 {
+        match Self::try_decode(encrypted_ptr, word) {
+            Some(addr) => addr,
+            None => mac_mismatch(),
+        }
+    }
+
+    /// Decrypt a ciphertext and key word, or `None` if they fail the MAC.
+    #[inline(always)]
+    pub(crate) fn try_decode(encrypted_ptr: u64, word: u64) -> Option<u64> {
+        // a poisoned process may have scrambled keys, so never trust the result.
+        if master::is_poisoned() {
+            diag_panic!("protected pointers have been poisoned");
+        }
+
+        // decrypt the pointer, unsealing the master key if it was evicted.
+        Self::try_decode_with(encrypted_ptr, word, master_for(word))
+    }
+
+    /// The key version and master key for a batch of [`Sealed::get_with`] calls, checking for poison once.
+    #[inline(always)]
+    pub(crate) fn batch_key() -> (u8, u64) {
+        if master::is_poisoned() {
+            diag_panic!("protected pointers have been poisoned");
+        }
+
+        master::current()
+    }
+
+    /// Recover the address under a master key from [`Sealed::batch_key`].
+    #[inline(always)]
+    pub(crate) fn get_with(&self, (version, master): (u8, u64)) -> u64 {
+        let (encrypted_ptr, word) = (self.layout.encrypted_ptr(), self.layout.key());
+        let master = match word_version(word) == version {
+            true => master,
+            false => master_for(word),
+        };
+        match Self::try_decode_with(encrypted_ptr, word, master) {
+            Some(addr) => addr,
+            None => mac_mismatch(),
+        }
+    }
+
+    #[inline(always)]
+    fn try_decode_with(encrypted_ptr: u64, word: u64, master: u64) -> Option<u64> {
+        let tag = word as u8;
+        let plain = match tag & TAG_KEYED {
+            0 => encrypted_ptr.rotate_right(tag as u32) ^ word_mask(word, master),
+            _ => Self::decrypt_keyed(encrypted_ptr, word, master),
+        };
+
+        verify_mac(plain, word, master)
+    }
+
+    /// Decrypt a built-in method in closed form, without allocating, locking or panicking.
+    ///
+    /// `None` if the process is poisoned, the master key is evicted, the
+    /// method isn't a built-in or the MAC fails; none of that is reported.
+    #[inline(always)]
+    pub(crate) fn get_realtime(&self) -> Option<u64> {
+        if master::is_poisoned() {
+            return None;
+        }
+
+        let word = self.layout.key();
+        let tag = word as u8;
+        if tag & TAG_KEYED != 0 {
+            return None;
+        }
+
+        let master = master::resident(word_version(word))?;
+
+        let plain = self.layout.encrypted_ptr().rotate_right(tag as u32) ^ word_mask(word, master);
+        verify_mac(plain, word, master)
+    }
+
+    /// Decrypt with a method that isn't a built-in, kept out of line.
+    #[inline(never)]
+    fn decrypt_keyed(encrypted_ptr: u64, word: u64, master: u64) -> u64 {
+        match word as u8 {
+            TAG_OWNED => {
+                let owned = unsafe { &*Self::owned(word, master) };
+                owned.method.decrypt(encrypted_ptr, owned.key ^ master)
+            }
+            tag => {
+                methods::shared(tag & !TAG_KEYED).decrypt(encrypted_ptr, word_mask(word, master))
+            }
+        }
+    }
+
+    /// Locate the [`Owned`] method of a key word tagged [`TAG_OWNED`].
+    #[inline(always)]
+    fn owned(word: u64, master: u64) -> *mut Owned {
+        ((word ^ master) & !(TAG_MASK | VERSION_MASK)) as *mut Owned
+    }
+
+    /// Re-encrypt under the current key version if this was encrypted under an older one.
+    ///
+    /// Keeps shared and owned methods; a built-in one is swapped for a random built-in.
+    #[inline(always)]
+    pub(crate) fn migrate(&mut self) -> bool {
+        let word = self.layout.key();
+        if word_version(word) == master::version() {
+            return false;
+        }
+        self.migrate_slow();
+        true
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn migrate_slow(&mut self) {
+        let addr = self.get();
+        let word = self.layout.key();
+        let method = Self::take_method(word);
+        Self::release(word);
+
+        // an owned method has moved into `method`, so the old seal must not drop.
+        unsafe { ptr::write(self, Self::with_method(addr, method)) };
+    }
+
+    /// Re-encrypt a pair from [`Sealed::encode`] under the current key version if it was encrypted under an older one.
+    ///
+    /// The old pair stays counted until it is replaced and [`Sealed::release`]d.
+    /// It can still be decrypted, unless it owned its method.
+    #[cfg(feature = "atomic")]
+    pub(crate) fn migrate_pair(encrypted_ptr: u64, word: u64) -> Option<(u64, u64)> {
+        if word_version(word) == master::version() {
+            return None;
+        }
+
+        let addr = Self::decode(encrypted_ptr, word);
+        Some(Self::encode(addr, Self::take_method(word)))
+    }
+
+    /// The method a key word decrypts with, taking back ownership of an owned one.
+    fn take_method(word: u64) -> Method {
+        match word as u8 {
+            TAG_OWNED => {
+                let owned = unsafe { Box::from_raw(Self::owned(word, master_for(word))) };
+                Method::Owned(owned.method)
+            }
+            tag if tag & TAG_KEYED != 0 => Method::Shared(tag & !TAG_KEYED),
+            _ => builtin_method(rand::random_range(0..BUILTIN_METHODS)).unwrap(),
+        }
+    }
+
+    /// Stop counting a pair from [`Sealed::encode`] that is being discarded or replaced.
+    #[inline(always)]
+    pub(crate) fn release(word: u64) {
+        master::release(word_version(word));
+    }
+
+    /// The stored ciphertext, for diagnostics.
+    #[inline(always)]
+    pub(crate) fn ciphertext(&self) -> u64 {
+        self.layout.encrypted_ptr()
+    }
+}
+
+#[cfg(not(passthrough))]
+impl Drop for Sealed {
+    fn drop(&mut self) {
+        let word = self.layout.key();
+
+        // a poisoned process can't be trusted to locate the method, so leak it.
+        if word as u8 == TAG_OWNED && !master::is_poisoned() {
+            // a method under a forgotten key version can't be located either.
+            if let Some(master) = master::for_version(word_version(word)) {
+                drop(unsafe { Box::from_raw(Self::owned(word, master)) });
+            }
+        }
+        Self::release(word);
+    }
+}
+
+/// The key version a key word was encrypted under, see [`VERSION_MASK`].
+#[cfg(not(passthrough))]
+#[inline(always)]
+fn word_version(word: u64) -> u8 {
+    (word >> 56) as u8
+}
+
+/// The master key a key word was encrypted under.
+///
+/// # Panics
+/// If its version was dropped with [`forget_retired_keys`](crate::forget_retired_keys).
+#[cfg(not(passthrough))]
+#[inline(always)]
+fn master_for(word: u64) -> u64 {
+    match master::for_version(word_version(word)) {
+        Some(master) => master,
+        None => diag_panic!("protected pointer was encrypted under a forgotten master key"),
+    }
+}
+
+/// The schedule mask or key a non-owned key word stands for, under `master`.
+///
+/// The version byte is the same for every pointer, so the mask's top byte is
+/// refilled from the random bits below it instead of leaking the master key's.
+#[cfg(not(passthrough))]
+#[inline(always)]
+fn word_mask(word: u64, master: u64) -> u64 {
+    let mask = (word & !TAG_MASK) ^ master;
+    mask ^ (mask << 8 & VERSION_MASK)
+}
+
+/// Bits of a user-space address on 64-bit targets, see the `mac` feature.
+#[cfg(all(feature = "mac", not(passthrough)))]
+const ADDRESS_BITS: u32 = 48;
+
+/// The MAC of `addr` under a pointer's key word, truncated into the bits above the address.
+#[cfg(all(feature = "mac", not(passthrough)))]
+#[inline(always)]
+fn address_mac(addr: u64, word: u64, master: u64) -> u64 {
+    aes::mac(word ^ master, addr) >> ADDRESS_BITS << ADDRESS_BITS
+}
+
+/// Fill the unused upper bits of `addr` with its MAC before encryption.
+#[cfg(all(feature = "mac", not(passthrough)))]
+#[inline(always)]
+fn embed_mac(addr: u64, word: u64, master: u64) -> u64 {
+    if addr >> ADDRESS_BITS != 0 {
+        diag_panic!(
+            "the mac feature needs addresses to fit in {} bits",
+            ADDRESS_BITS
+        );
+    }
+    addr | address_mac(addr, word, master)
+}
+
+/// Split a decrypted word into its address, if the MAC above it matches.
+#[cfg(all(feature = "mac", not(passthrough)))]
+#[inline(always)]
+fn verify_mac(plain: u64, word: u64, master: u64) -> Option<u64> {
+    let addr = plain & ((1 << ADDRESS_BITS) - 1);
+    (plain == addr | address_mac(addr, word, master)).then_some(addr)
+}
+
+#[cfg(not(any(feature = "mac", passthrough)))]
+#[inline(always)]
+fn embed_mac(addr: u64, _word: u64, _master: u64) -> u64 {
+    addr
+}
+
+#[cfg(not(any(feature = "mac", passthrough)))]
+#[inline(always)]
+fn verify_mac(plain: u64, _word: u64, _master: u64) -> Option<u64> {
+    Some(plain)
+}
+
+/// A decoy shaped like the key word `word`, with `schedule` the built-in method it was encoded with.
+///
+/// Same version byte and same kind of tag, around random bits, so a dump
+/// can't tell the real key word apart by its structure.
+#[cfg(not(passthrough))]
+#[inline(always)]
+fn decoy_word(word: u64, schedule: Option<fn(u64) -> (u32, u64)>) -> u64 {
+    let key = rand::random::<u64>();
+    let tag = match schedule {
+        Some(schedule) => schedule(key).0 as u64,
+        // a keyed method's tag is the same for every pointer using it.
+        None => word & TAG_MASK,
+    };
+    (key & !(TAG_MASK | VERSION_MASK)) | (word & VERSION_MASK) | tag
+}
+
+/// Report a ciphertext that failed its MAC, then refuse to use it.
+#[cfg(not(passthrough))]
+#[cold]
+#[inline(never)]
+fn mac_mismatch() -> ! {
+    tamper::report(TamperEvent::new(
+        TamperKind::MacMismatch,
+        crate::diag::diag!("<sealed pointer>"),
+    ));
+    diag_panic!("protected pointer failed its integrity check");
+}
+
+#[cfg(passthrough)]
+impl Sealed {
+    /// Store `addr` as is.
+    #[inline(always)]
+    pub(crate) fn new(addr: u64) -> Self {
+        Self { addr }
+    }
+
+    /// Store `addr` as is, ignoring the method.
+    #[inline(always)]
+    pub(crate) fn with_method(addr: u64, _method: Method) -> Self {
+        Self { addr }
+    }
+
+    /// `addr` as is, with an empty key word.
+    #[cfg(any(feature = "atomic", feature = "fuzzing"))]
+    #[inline(always)]
+    pub(crate) fn encode(addr: u64, _method: Method) -> (u64, u64) {
+        (addr, 0)
+    }
+
+    /// The address stored by [`Sealed::encode`].
+    #[cfg(feature = "atomic")]
+    #[inline(always)]
+    pub(crate) fn decode(addr: u64, _word: u64) -> u64 {
+        if master::is_poisoned() {
+            diag_panic!("protected pointers have been poisoned");
+        }
+
+        addr
+    }
+
+    /// The address stored by [`Sealed::encode`], which never fails its MAC.
+    #[cfg(feature = "fuzzing")]
+    #[inline(always)]
+    pub(crate) fn try_decode(addr: u64, _word: u64) -> Option<u64> {
+        if master::is_poisoned() {
+            diag_panic!("protected pointers have been poisoned");
+        }
+
+        Some(addr)
+    }
+
+    /// The stored address.
+    #[inline(always)]
+    pub(crate) fn get(&self) -> u64 {
+        if master::is_poisoned() {
+            diag_panic!("protected pointers have been poisoned");
+        }
+
+        self.addr
+    }
+
+    /// Check for poison once for a batch of [`Sealed::get_with`] calls.
+    #[inline(always)]
+    pub(crate) fn batch_key() -> (u8, u64) {
+        if master::is_poisoned() {
+            diag_panic!("protected pointers have been poisoned");
+        }
+
+        (0, 0)
+    }
+
+    /// The stored address.
+    #[inline(always)]
+    pub(crate) fn get_with(&self, _key: (u8, u64)) -> u64 {
+        self.addr
+    }
+
+    /// Nothing to re-encrypt.
+    #[inline(always)]
+    pub(crate) fn migrate(&mut self) -> bool {
+        false
+    }
+
+    /// Nothing to re-encrypt.
+    #[cfg(feature = "atomic")]
+    #[inline(always)]
+    pub(crate) fn migrate_pair(_addr: u64, _word: u64) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// Nothing is counted.
+    #[cfg(any(feature = "atomic", feature = "fuzzing"))]
+    #[inline(always)]
+    pub(crate) fn release(_word: u64) {}
+
+    /// The stored address, or `None` if poisoned.
+    #[inline(always)]
+    pub(crate) fn get_realtime(&self) -> Option<u64> {
+        (!master::is_poisoned()).then_some(self.addr)
+    }
+
+    /// The stored address, or `None` if poisoned.
+    #[cfg(feature = "ffi")]
+    #[inline(always)]
+    pub(crate) fn try_get(&self) -> Option<u64> {
+        self.get_realtime()
+    }
+
+    /// The stored address, which is also the "ciphertext".
+    #[inline(always)]
+    pub(crate) fn ciphertext(&self) -> u64 {
+        self.addr
+    }
+}
+
+#[cfg(all(test, feature = "mac", not(passthrough)))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn mac_round_trip() {
+        let (encrypted_ptr, word) = Sealed::encode(0x7FFF_DEAD_BEE0, Method::A);
+
+        assert_eq!(
+            Sealed::try_decode(encrypted_ptr, word),
+            Some(0x7FFF_DEAD_BEE0)
+        );
+    }
+
+    #[test]
+    fn decoy_key_words_look_like_real_ones() {
+        use std::collections::BTreeSet;
+
+        // the version and tag bytes, which are all that isn't random.
+        let structure = |word: u64| (word >> 56, word & TAG_MASK);
+        let methods: [fn() -> Method; 4] = [
+            || Method::A,
+            || Method::B,
+            || Method::C,
+            || Method::from_user(crate::Speck::new()),
+        ];
+
+        for method in methods {
+            let (mut real, mut decoys) = (BTreeSet::new(), BTreeSet::new());
+            for _ in 0..1024 {
+                let schedule = method().schedule_fn();
+                let word = Sealed::with_method(0x7FFF_DEAD_BEE0, method()).layout.key();
+                real.insert(structure(word));
+                decoys.insert(structure(decoy_word(word, schedule)));
+            }
+            assert_eq!(decoys, real);
+        }
+    }
+
+    #[test]
+    fn flipped_ciphertext_fails_the_mac() {
+        const ADDR: u64 = 0x7FFF_DEAD_BEE0;
+        let (encrypted_ptr, word) = Sealed::encode(ADDR, Method::B);
+        let master = master_for(word);
+
+        // a built-in method flips the same bit of the plaintext, rotated.
+        for bit in 0..64 {
+            let flipped = (1u64 << bit).rotate_right(word as u8 as u32);
+            let forged = ADDR ^ flipped;
+            let expected = (flipped >> ADDRESS_BITS == 0
+                && address_mac(forged, word, master) == address_mac(ADDR, word, master))
+            .then_some(forged);
+            assert_eq!(Sealed::try_decode(encrypted_ptr ^ 1 << bit, word), expected);
+        }
+
+        // a flip inside the MAC itself always fails.
+        let mac_bit = (1u64 << ADDRESS_BITS).rotate_left(word as u8 as u32);
+        assert_eq!(Sealed::try_decode(encrypted_ptr ^ mac_bit, word), None);
+    }
+}
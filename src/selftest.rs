@@ -0,0 +1,202 @@
+use std::fmt;
+
+use crate::{
+    methods, AesFeistel, Ascon, Encrypt, MethodA, MethodB, MethodC, Prince, SipFeistel, Speck, Xtea,
+};
+
+/// Plaintext and key used for every known-answer check.
+const KAT_DATA: u64 = 0x0123_4567_89AB_CDEF;
+const KAT_KEY: u64 = 0xFEDC_BA98_7654_3210;
+
+/// Random round trips per method.
+const ROUND_TRIPS: usize = 64;
+
+/// How a method failed [`self_test`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SelfTestFailure {
+    /// Decrypting didn't give back the plaintext.
+    RoundTrip {
+        /// The plaintext.
+        data: u64,
+        /// The key it was encrypted under.
+        key: u64,
+    },
+    /// Encryption left every plaintext unchanged, as a stubbed-out method would.
+    Identity,
+    /// A built-in method disagreed with its recorded regression vector.
+    KnownAnswer {
+        /// The expected ciphertext.
+        expected: u64,
+        /// The produced ciphertext.
+        actual: u64,
+    },
+    /// A built-in method's precomputed schedule disagreed with the method itself.
+    Schedule {
+        /// The key they disagreed on.
+        key: u64,
+    },
+}
+
+/// One failed method in a [`SelfTestReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestFinding {
+    /// The method, e.g. `"Speck"` or `"shared method 3"` for a registered one.
+    pub method: String,
+    /// What went wrong.
+    pub failure: SelfTestFailure,
+}
+
+/// Result of a [`self_test`] run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// Every failure that was found.
+    pub findings: Vec<SelfTestFinding>,
+    /// Number of methods tested.
+    pub methods_tested: usize,
+}
+
+impl SelfTestReport {
+    /// Whether every method passed.
+    pub fn is_ok(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    fn check(
+        &mut self,
+        method: impl fmt::Display,
+        cipher: &dyn Encrypt,
+        known_answer: Option<u64>,
+    ) {
+        self.methods_tested += 1;
+        let mut fail = |failure| {
+            self.findings.push(SelfTestFinding {
+                method: method.to_string(),
+                failure,
+            })
+        };
+
+        if let Some(expected) = known_answer {
+            let actual = cipher.encrypt(KAT_DATA, KAT_KEY);
+            if actual != expected {
+                fail(SelfTestFailure::KnownAnswer { expected, actual });
+            }
+        }
+
+        let mut unchanged = 0;
+        for _ in 0..ROUND_TRIPS {
+            let (data, key) = (rand::random(), rand::random());
+            let encrypted = cipher.encrypt(data, key);
+            unchanged += (encrypted == data) as usize;
+
+            if cipher.decrypt(encrypted, key) != data {
+                fail(SelfTestFailure::RoundTrip { data, key });
+                return;
+            }
+        }
+
+        if unchanged == ROUND_TRIPS {
+            fail(SelfTestFailure::Identity);
+        }
+    }
+
+    fn check_schedule(
+        &mut self,
+        method: &str,
+        cipher: &dyn Encrypt,
+        schedule: fn(u64) -> (u32, u64),
+    ) {
+        for _ in 0..ROUND_TRIPS {
+            let (data, key) = (rand::random::<u64>(), rand::random());
+            let (rot, mask) = schedule(key);
+
+            if cipher.encrypt(data, key).rotate_right(rot) ^ mask != data {
+                self.findings.push(SelfTestFinding {
+                    method: method.to_string(),
+                    failure: SelfTestFailure::Schedule { key },
+                });
+                return;
+            }
+        }
+    }
+}
+
+/// Check every built-in and registered method before trusting it.
+///
+/// Runs random encrypt/decrypt round trips on each, plus checks against
+/// recorded regression vectors for the built-ins and a consistency check of
+/// the precomputed schedules pointers actually decrypt with, so a corrupted
+/// or hooked cipher shows up here rather than as garbage pointers later.
+pub fn self_test() -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+
+    let builtins: [(&str, &dyn Encrypt, u64); 9] = [
+        ("MethodA", &MethodA, 0x68c1_5d94_0ea7_3872),
+        ("MethodB", &MethodB, 0xa469_0ec2_975a_3df1),
+        ("MethodC", &MethodC, 0xbba4_e8a4_e8b5_f9b5),
+        ("Speck", &Speck::new(), 0x98b5_df0b_3e42_0421),
+        ("Xtea", &Xtea::new(), 0xd51c_be88_fef7_85d5),
+        ("Prince", &Prince::new(), 0x1f17_0d29_7e8b_67e8),
+        ("SipFeistel", &SipFeistel::new(), 0x86f8_93ff_0a65_84a0),
+        ("Ascon", &Ascon::new(), 0x9f9b_2c60_4cba_5ded),
+        ("AesFeistel", &AesFeistel::new(), 0xfd8e_93d7_ad54_8b63),
+    ];
+    for (name, cipher, expected) in builtins {
+        report.check(name, cipher, Some(expected));
+    }
+
+    report.check_schedule("MethodA", &MethodA, MethodA::schedule);
+    report.check_schedule("MethodB", &MethodB, MethodB::schedule);
+    report.check_schedule("MethodC", &MethodC, MethodC::schedule);
+
+    for (index, method) in methods::shared_methods() {
+        report.check(format_args!("shared method {index}"), method, None);
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// A "cipher" that was patched to do nothing.
+    struct Stubbed;
+
+    impl Encrypt for Stubbed {
+        fn encrypt(&self, data: u64, _: u64) -> u64 {
+            data
+        }
+
+        fn decrypt(&self, data: u64, _: u64) -> u64 {
+            data
+        }
+    }
+
+    #[test]
+    fn builtins_pass() {
+        let report = self_test();
+
+        assert!(report.is_ok(), "{report:?}");
+        assert!(report.methods_tested >= 9);
+    }
+
+    #[test]
+    fn stubbed_method_fails() {
+        let mut report = SelfTestReport::default();
+        report.check("Stubbed", &Stubbed, Some(0x1234));
+
+        let failures: Vec<_> = report.findings.iter().map(|f| f.failure).collect();
+        assert_eq!(
+            failures,
+            [
+                SelfTestFailure::KnownAnswer {
+                    expected: 0x1234,
+                    actual: KAT_DATA
+                },
+                SelfTestFailure::Identity
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use crate::aliasing::Borrow;
+use crate::{Dealloc, DropPolicy, Encrypt, EncryptedPtr};
+
+/// An [`EncryptedPtr`] that is `Send` and `Sync` whenever `T` is, e.g. to hold across `.await` in a spawned task.
+///
+/// `EncryptedPtr` can't be `Send` in general because a method passed to
+/// [`EncryptedPtr::with_method`] may not be; this wrapper only accepts
+/// methods that are `Send + Sync`.
+pub struct SendEncryptedPtr<T, P: DropPolicy = Dealloc> {
+    pub(crate) inner: EncryptedPtr<T, P>,
+}
+
+// owns its T like a `Box`, and every method it can hold is `Send + Sync`.
+unsafe impl<T: Send, P: DropPolicy> Send for SendEncryptedPtr<T, P> {}
+unsafe impl<T: Sync, P: DropPolicy> Sync for SendEncryptedPtr<T, P> {}
+
+impl<T> SendEncryptedPtr<T> {
+    /// Create a new encrypted pointer from a raw pointer, see [`EncryptedPtr::new`].
+    pub fn new(ptr: *mut T) -> Self {
+        Self {
+            inner: EncryptedPtr::new(ptr),
+        }
+    }
+
+    /// Create a new encrypted pointer using a specific thread-safe method, see [`EncryptedPtr::with_method`].
+    pub fn with_method(ptr: *mut T, method: impl Encrypt + Send + Sync + 'static) -> Self {
+        Self {
+            inner: EncryptedPtr::with_method(ptr, method),
+        }
+    }
+
+    /// Move the pointee to a fresh allocation, see [`EncryptedPtr::relocate`].
+    pub fn relocate(&mut self) {
+        self.inner.relocate();
+    }
+}
+
+impl<T, P: DropPolicy> SendEncryptedPtr<T, P> {
+    /// Borrow the pointee through a guard that decrypts on every access.
+    ///
+    /// The guard only holds the address of this pointer, never the
+    /// decrypted one, so a future holding it across `.await` stores nothing
+    /// in plaintext.
+    ///
+    /// # Panics
+    /// In debug builds, if a [`SendGuardMut`] from another pointer to the
+    /// same pointee is alive.
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub fn access(&self) -> SendGuard<'_, T, P> {
+        SendGuard {
+            _borrow: Borrow::shared(self.inner.decrypt_ptr()),
+            ptr: self,
+        }
+    }
+
+    /// Mutable counterpart of [`SendEncryptedPtr::access`].
+    ///
+    /// # Panics
+    /// In debug builds, if any guard from another pointer to the same
+    /// pointee is alive.
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub fn access_mut(&mut self) -> SendGuardMut<'_, T, P> {
+        SendGuardMut {
+            _borrow: Borrow::exclusive(self.inner.decrypt_ptr()),
+            ptr: self,
+        }
+    }
+
+    /// Unwrap the plain [`EncryptedPtr`].
+    pub fn into_inner(self) -> EncryptedPtr<T, P> {
+        self.inner
+    }
+}
+
+impl<T, P: DropPolicy> From<Box<T>> for SendEncryptedPtr<T, P> {
+    fn from(value: Box<T>) -> Self {
+        Self {
+            inner: value.into(),
+        }
+    }
+}
+
+impl<T, P: DropPolicy> From<T> for SendEncryptedPtr<T, P> {
+    fn from(value: T) -> Self {
+        Box::new(value).into()
+    }
+}
+
+#[cfg(any(not(feature = "explicit-access"), test))]
+impl<T, P: DropPolicy> Deref for SendEncryptedPtr<T, P> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.inner.access()
+    }
+}
+
+#[cfg(any(not(feature = "explicit-access"), test))]
+impl<T, P: DropPolicy> DerefMut for SendEncryptedPtr<T, P> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner.access_mut()
+    }
+}
+
+impl<T: fmt::Debug, P: DropPolicy> fmt::Debug for SendEncryptedPtr<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// Shared access to a [`SendEncryptedPtr`], see [`SendEncryptedPtr::access`].
+pub struct SendGuard<'a, T, P: DropPolicy = Dealloc> {
+    ptr: &'a SendEncryptedPtr<T, P>,
+    _borrow: Borrow,
+}
+
+impl<T, P: DropPolicy> Deref for SendGuard<'_, T, P> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.ptr.inner.access()
+    }
+}
+
+/// Exclusive access to a [`SendEncryptedPtr`], see [`SendEncryptedPtr::access_mut`].
+pub struct SendGuardMut<'a, T, P: DropPolicy = Dealloc> {
+    ptr: &'a mut SendEncryptedPtr<T, P>,
+    _borrow: Borrow,
+}
+
+impl<T, P: DropPolicy> Deref for SendGuardMut<'_, T, P> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.ptr.inner.access()
+    }
+}
+
+impl<T, P: DropPolicy> DerefMut for SendGuardMut<'_, T, P> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.ptr.inner.access_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::future::{ready, Future};
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    fn assert_send<F: Send>(_: &F) {}
+
+    #[test]
+    fn guard_held_across_await() {
+        let mut health: SendEncryptedPtr<u32> = 100.into();
+
+        let future = async move {
+            let mut guard = health.access_mut();
+            ready(()).await;
+            *guard -= 25;
+            ready(()).await;
+            drop(guard);
+            *health.access()
+        };
+        assert_send(&future);
+
+        let mut context = Context::from_waker(Waker::noop());
+        assert_eq!(pin!(future).poll(&mut context), Poll::Ready(75));
+    }
+}
@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::{DropPolicy, EncryptedPtr};
+
+/// A pointer opened in a [`Session`], and how to reseal it.
+struct Opened {
+    ptr: *mut u8,
+    reseal: unsafe fn(*mut u8),
+}
+
+/// A scope in which protected pointers are decrypted once and resealed together.
+///
+/// Created by [`session`].
+pub struct Session<'s, 'env: 's> {
+    opened: RefCell<Vec<Opened>>,
+    // invariant in both, as with std::thread::scope: pointers are opened for
+    // 's, which outlives the closure but is bounded by the caller's 'env.
+    _scope: PhantomData<&'s mut &'s ()>,
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'s> Session<'s, '_> {
+    /// Decrypt `ptr` once for the rest of the session.
+    ///
+    /// The returned reference is plain memory access, with no decryption or
+    /// integrity checks on each use. When the session closes, `ptr` is
+    /// rekeyed under a fresh key and method.
+    pub fn open<T, P: DropPolicy>(&self, ptr: &'s mut EncryptedPtr<T, P>) -> &'s mut T {
+        unsafe fn reseal<T, P: DropPolicy>(ptr: *mut u8) {
+            (*(ptr as *mut EncryptedPtr<T, P>)).rekey();
+        }
+
+        let value = unsafe { &mut *(ptr.access_mut() as *mut T) };
+        self.opened.borrow_mut().push(Opened {
+            ptr: ptr as *mut EncryptedPtr<T, P> as *mut u8,
+            reseal: reseal::<T, P>,
+        });
+        value
+    }
+
+    /// Number of pointers opened so far.
+    pub fn len(&self) -> usize {
+        self.opened.borrow().len()
+    }
+
+    /// Whether no pointer has been opened yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Rekey every opened pointer.
+    ///
+    /// Only called once the closure given to [`session`] has returned, so
+    /// every reference handed out by [`Session::open`] has ended.
+    fn close(&self) {
+        for opened in self.opened.borrow_mut().drain(..) {
+            unsafe { (opened.reseal)(opened.ptr) };
+        }
+    }
+}
+
+impl fmt::Debug for Session<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "Session");
+        f.debug_struct("Session")
+            .field("opened", &self.len())
+            .finish()
+    }
+}
+
+/// Run `f` with a [`Session`], e.g. around one frame's worth of accesses.
+///
+/// Pointers opened in the session are decrypted once, however often they
+/// are used inside it, and all of them are rekeyed when `f` returns or
+/// unwinds, so the overhead is paid per frame rather than per access.
+///
+/// ```
+/// use pointerguard::{session, EncryptedPtr};
+///
+/// let mut health: EncryptedPtr<u32> = 100.into();
+/// let mut mana: EncryptedPtr<u32> = 50.into();
+///
+/// session(|s| {
+///     let (health, mana) = (s.open(&mut health), s.open(&mut mana));
+///     *health -= 10;
+///     *mana -= 5;
+/// });
+///
+/// assert_eq!(*health.access(), 90);
+/// ```
+///
+/// Only pointers that outlive the session can be opened in it:
+///
+/// ```compile_fail
+/// pointerguard::session(|s| {
+///     let mut local: pointerguard::EncryptedPtr<u32> = 1.into();
+///     *s.open(&mut local) += 1;
+/// });
+/// ```
+pub fn session<'env, R>(f: impl for<'s> FnOnce(&'s Session<'s, 'env>) -> R) -> R {
+    let session = Session {
+        opened: RefCell::new(Vec::new()),
+        _scope: PhantomData,
+        _env: PhantomData,
+    };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(&session)));
+    session.close();
+
+    match result {
+        Ok(result) => result,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn session_rekeys_on_close() {
+        let mut gold: EncryptedPtr<u64> = 10.into();
+        let before = gold.sealed.ciphertext();
+
+        let opened = session(|s| {
+            let gold = s.open(&mut gold);
+            for _ in 0..5 {
+                *gold += 1;
+            }
+            s.len()
+        });
+
+        assert_eq!(opened, 1);
+        assert_eq!(*gold.access(), 15);
+        assert!(cfg!(passthrough) || gold.sealed.ciphertext() != before);
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::diag::{diag, type_name};
+use crate::pod::{self, NoUninit};
+use crate::tamper::{self, Poisoned, TamperEvent, TamperKind};
+use crate::EncryptedPtr;
+
+/// A value kept alongside a masked shadow copy that is compared on every read.
+///
+/// The primary copy and the shadow live in separate allocations behind
+/// independent encrypted pointers, and the shadow is XOR-masked so it doesn't
+/// show up when scanning for the value. Patching only one of them is detected
+/// on the next access and reported to the tamper callback, and poisons the
+/// pointer for good.
+pub struct ShadowedPtr<T: NoUninit + PartialEq> {
+    primary: EncryptedPtr<T>,
+    shadow: EncryptedPtr<MaybeUninit<T>>,
+    mask: u64,
+    poisoned: AtomicBool,
+}
+
+impl<T: NoUninit + PartialEq> ShadowedPtr<T> {
+    /// Store `value` with a freshly masked shadow.
+    pub fn new(value: T) -> Self {
+        let mask = rand::random::<u64>() | 1;
+        let shadow = Box::new(mask_value(&value, mask)).into();
+
+        Self {
+            primary: Box::new(value).into(),
+            shadow,
+            mask,
+            poisoned: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether the primary and shadow copies still agree.
+    pub fn verify(&self) -> bool {
+        // the unmasked copy is bitwise, so wipe it rather than dropping it.
+        let mut shadow =
+            ManuallyDrop::new(unsafe { unmask(self.shadow.access(), self.mask).assume_init() });
+        let matches = *self.primary.access() == *shadow;
+
+        pod::wipe(&mut *shadow);
+        matches
+    }
+
+    /// Whether a failed check has poisoned this pointer.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    /// Verify, poisoning and reporting on a mismatch.
+    #[inline(always)]
+    fn checked(&self) -> Result<(), Poisoned> {
+        if self.is_poisoned() {
+            return Err(Poisoned);
+        }
+
+        if !self.verify() {
+            self.poisoned.store(true, Ordering::Relaxed);
+            tamper::report(
+                TamperEvent::new(TamperKind::ShadowMismatch, type_name::<T>())
+                    .with_ptr(self.primary.decrypt_ptr()),
+            );
+            return Err(Poisoned);
+        }
+
+        Ok(())
+    }
+
+    /// Verify both copies, then read the value, or [`Poisoned`] once a check has failed.
+    pub fn try_get(&self) -> Result<&T, Poisoned> {
+        self.checked()?;
+        Ok(self.primary.access())
+    }
+
+    /// Verify both copies, then get write access; the shadow is refreshed when the guard drops.
+    ///
+    /// # Panics
+    /// If the pointer is poisoned, see [`ShadowedPtr::try_write`].
+    pub fn write(&mut self) -> ShadowGuard<'_, T> {
+        self.try_write().expect(diag!("shadow copy mismatch"))
+    }
+
+    /// Fallible counterpart of [`ShadowedPtr::write`].
+    pub fn try_write(&mut self) -> Result<ShadowGuard<'_, T>, Poisoned> {
+        self.checked()?;
+        Ok(ShadowGuard { shadowed: self })
+    }
+
+    /// Replace the value, refreshing the shadow.
+    pub fn set(&mut self, value: T) {
+        *self.write() = value;
+    }
+
+    /// Verify both copies, then replace the value with `new_value` if `predicate` accepts the current one.
+    ///
+    /// The check, the predicate and the write all happen under one
+    /// verification, so a patched value can't slip in between them. Returns
+    /// whether the value was replaced; the shadow is only refreshed if so.
+    ///
+    /// # Panics
+    /// If the pointer is poisoned, see [`ShadowedPtr::try_write`].
+    pub fn update_if(&mut self, predicate: impl FnOnce(&T) -> bool, new_value: T) -> bool {
+        self.checked().expect(diag!("shadow copy mismatch"));
+        if !predicate(self.primary.access()) {
+            return false;
+        }
+
+        *self.primary.access_mut() = new_value;
+        self.reshadow();
+        true
+    }
+
+    /// Re-mask the shadow from the primary copy under a fresh mask.
+    fn reshadow(&mut self) {
+        self.mask = rand::random::<u64>() | 1;
+        *self.shadow.access_mut() = mask_value(self.primary.access(), self.mask);
+    }
+}
+
+/// XOR every byte of `value` with a keystream derived from `mask`.
+fn mask_value<T: NoUninit>(value: &T, mask: u64) -> MaybeUninit<T> {
+    let mut out = MaybeUninit::<T>::uninit();
+    let bytes = out.as_mut_ptr() as *mut u8;
+
+    for (i, (byte, noise)) in pod::bytes_of(value).iter().zip(keystream(mask)).enumerate() {
+        unsafe { bytes.add(i).write(byte ^ noise) };
+    }
+
+    out
+}
+
+/// Undo [`mask_value`] on the stored shadow.
+fn unmask<T: NoUninit>(shadow: &MaybeUninit<T>, mask: u64) -> MaybeUninit<T> {
+    let mut out = MaybeUninit::<T>::uninit();
+    let src = shadow.as_ptr() as *const u8;
+    let dst = out.as_mut_ptr() as *mut u8;
+
+    for (i, noise) in keystream(mask).take(size_of::<T>()).enumerate() {
+        unsafe { dst.add(i).write(src.add(i).read() ^ noise) };
+    }
+
+    out
+}
+
+/// Bytes of a xorshift64 stream seeded by `mask`.
+fn keystream(mut state: u64) -> impl Iterator<Item = u8> {
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state.to_ne_bytes()
+    })
+    .flatten()
+}
+
+impl<T: NoUninit + PartialEq> Deref for ShadowedPtr<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        self.try_get().expect(diag!("shadow copy mismatch"))
+    }
+}
+
+impl<T: NoUninit + PartialEq + fmt::Debug> fmt::Debug for ShadowedPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ShadowedPtr");
+        f.debug_struct("ShadowedPtr")
+            .field("pointed_value", self.primary.access())
+            .field("intact", &self.verify())
+            .field("poisoned", &self.is_poisoned())
+            .finish()
+    }
+}
+
+/// Write access to a [`ShadowedPtr`] value.
+pub struct ShadowGuard<'a, T: NoUninit + PartialEq> {
+    shadowed: &'a mut ShadowedPtr<T>,
+}
+
+impl<T: NoUninit + PartialEq> Deref for ShadowGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.shadowed.primary.access()
+    }
+}
+
+impl<T: NoUninit + PartialEq> DerefMut for ShadowGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.shadowed.primary.access_mut()
+    }
+}
+
+impl<T: NoUninit + PartialEq> Drop for ShadowGuard<'_, T> {
+    fn drop(&mut self) {
+        self.shadowed.reshadow();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn shadow_tracks_writes() {
+        let mut health = ShadowedPtr::new(100u32);
+        *health.write() -= 25;
+
+        assert_eq!(*health, 75);
+        assert!(health.verify());
+    }
+
+    #[test]
+    fn update_if_checks_and_writes_together() {
+        let mut health = ShadowedPtr::new(100u32);
+        let damage = |health: &mut ShadowedPtr<u32>, new| health.update_if(|&old| new < old, new);
+
+        assert!(damage(&mut health, 60));
+        assert!(!damage(&mut health, 9999));
+        assert_eq!(*health, 60);
+        assert!(health.verify());
+    }
+
+    #[test]
+    fn shadow_detects_patch() {
+        let health = ShadowedPtr::new(100u32);
+
+        // simulate a cheat patching only the copy it found.
+        unsafe { *health.primary.decrypt_ptr() = 9999 };
+
+        assert!(!health.verify());
+    }
+
+    #[test]
+    fn shadow_mismatch_poisons() {
+        let mut health = ShadowedPtr::new(100u32);
+        unsafe { *health.primary.decrypt_ptr() = 9999 };
+
+        assert_eq!(health.try_get(), Err(Poisoned));
+        assert!(health.is_poisoned());
+
+        // restoring the value doesn't clear the poison.
+        unsafe { *health.primary.decrypt_ptr() = 100 };
+        assert!(health.try_write().is_err());
+    }
+}
@@ -0,0 +1,302 @@
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::diag::{diag, type_name};
+use crate::mapped::{invalid, Mapping};
+use crate::master::MaskedKey;
+use crate::pod::AnyBitPattern;
+use crate::{aes, Encrypt, Speck};
+
+/// First word of every shared region.
+const MAGIC: u64 = u64::from_le_bytes(*b"PGSHARED");
+
+/// Header words: magic, salt, key check, then the allocation cursor.
+const HEADER_WORDS: usize = 4;
+
+/// Header word holding the offset of the first free byte, bumped atomically by every process.
+const CURSOR: usize = 3;
+
+/// Bits of a region offset; the rest of a handle's plaintext is its MAC.
+const OFFSET_BITS: u32 = 40;
+
+/// The region key both processes derive from their shared secret.
+fn region_key(secret: u64, salt: u64) -> u64 {
+    aes::mac(secret ^ salt, MAGIC)
+}
+
+/// The MAC bits sealed into a handle along with its offset, bound to the pointee's size.
+fn handle_mac(key: u64, nonce: u64, offset: u64, size: usize) -> u64 {
+    aes::mac(key ^ nonce ^ (size as u64).rotate_left(OFFSET_BITS), offset) >> OFFSET_BITS
+        << OFFSET_BITS
+}
+
+/// A memory region shared between cooperating processes, whose object references are encrypted handles.
+///
+/// Both sides map the same file (e.g. under `/dev/shm`) and derive the region
+/// key from a secret they established at startup, so a [`SharedHandle`] sent
+/// over IPC is an encrypted, authenticated offset rather than a plaintext
+/// one. Values are allocated by either side with [`SharedRegion::alloc`] and
+/// only live as long as the file. Since the other side can change a value at
+/// any time, values are copied in and out with volatile accesses rather than
+/// borrowed, and must be [`AnyBitPattern`]. As with any shared memory, the
+/// processes have to synchronize writes to a value themselves.
+pub struct SharedRegion {
+    mapping: Mapping,
+    /// The region key, XORed with the master key.
+    key: MaskedKey,
+}
+
+impl SharedRegion {
+    /// Create a region of `size` bytes at `path`, keyed by `secret`.
+    ///
+    /// Regions hold at most 1 TiB, since handles seal 40-bit offsets.
+    pub fn create(path: impl AsRef<Path>, size: usize, secret: u64) -> io::Result<Self> {
+        if (HEADER_WORDS * 8 + size) as u64 >= 1 << OFFSET_BITS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                diag!("shared regions hold at most 1 TiB"),
+            ));
+        }
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len((HEADER_WORDS * 8 + size) as u64)?;
+
+        let salt = rand::random();
+        let mut mapping = Mapping::new(file, HEADER_WORDS)?;
+        mapping.set_word(1, salt);
+        mapping.set_word(2, aes::mac(region_key(secret, salt), MAGIC));
+        mapping.set_word(CURSOR, (HEADER_WORDS * 8) as u64);
+        // written last, so a process opening early sees an incomplete region as invalid.
+        mapping.set_word(0, MAGIC);
+
+        Ok(Self {
+            mapping,
+            key: MaskedKey::new(region_key(secret, salt)),
+        })
+    }
+
+    /// Map the region another process created at `path`.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if it isn't a region, or was created with another secret.
+    pub fn open(path: impl AsRef<Path>, secret: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mapping = Mapping::new(file, HEADER_WORDS)?;
+        if mapping.word(0) != MAGIC {
+            return Err(invalid(diag!("not a shared region")));
+        }
+
+        let key = region_key(secret, mapping.word(1));
+        if mapping.word(2) != aes::mac(key, MAGIC) {
+            return Err(invalid(diag!("wrong secret for this shared region")));
+        }
+
+        Ok(Self {
+            mapping,
+            key: MaskedKey::new(key),
+        })
+    }
+
+    /// Allocation cursor in the header, shared by every process mapping the region.
+    fn cursor(&self) -> &AtomicU64 {
+        unsafe { AtomicU64::from_ptr((self.mapping.ptr as *mut u64).add(CURSOR)) }
+    }
+
+    /// Move `value` into the region, or `None` if it is full.
+    pub fn alloc<T: AnyBitPattern>(&self, value: T) -> Option<SharedHandle<T>> {
+        let (size, align) = (size_of::<T>() as u64, align_of::<T>() as u64);
+        // the cursor lives in the file, so never trust it to lie past the header.
+        let start = |cursor: u64| {
+            cursor
+                .max((HEADER_WORDS * 8) as u64)
+                .next_multiple_of(align)
+        };
+        let offset = self
+            .cursor()
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cursor| {
+                let end = start(cursor).checked_add(size)?;
+                (end <= self.mapping.len as u64).then_some(end)
+            })
+            .map(start)
+            .ok()?;
+
+        unsafe { self.slot::<T>(offset as usize).write_volatile(value) };
+        Some(self.seal(offset))
+    }
+
+    /// A copy of the value behind `handle`, or `None` if it is forged, from another region, or for a type of another size.
+    pub fn read<T: AnyBitPattern>(&self, handle: SharedHandle<T>) -> Option<T> {
+        let offset = self.resolve(handle)?;
+        Some(unsafe { self.slot::<T>(offset).read_volatile() })
+    }
+
+    /// Overwrite the value behind `handle`, returning whether it resolved, see [`SharedRegion::read`].
+    pub fn write<T: AnyBitPattern>(&self, handle: SharedHandle<T>, value: T) -> bool {
+        let Some(offset) = self.resolve(handle) else {
+            return false;
+        };
+        unsafe { self.slot::<T>(offset).write_volatile(value) };
+        true
+    }
+
+    /// A raw pointer to the value behind `handle`, for in-place or atomic access, see [`SharedRegion::read`].
+    ///
+    /// It stays valid for as long as the region is mapped. Turning it into a
+    /// reference is only sound while no other process or mapping writes to
+    /// the value.
+    pub fn as_ptr<T: AnyBitPattern>(&self, handle: SharedHandle<T>) -> Option<*mut T> {
+        let offset = self.resolve(handle)?;
+        Some(self.slot(offset))
+    }
+
+    fn slot<T>(&self, offset: usize) -> *mut T {
+        unsafe { self.mapping.ptr.add(offset) as *mut T }
+    }
+
+    fn key(&self) -> u64 {
+        self.key.get()
+    }
+
+    fn seal<T>(&self, offset: u64) -> SharedHandle<T> {
+        let nonce = rand::random();
+        let plain = offset | handle_mac(self.key(), nonce, offset, size_of::<T>());
+        SharedHandle {
+            nonce,
+            sealed: Speck::new().encrypt(plain, self.key() ^ nonce),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Decrypt and check a handle, returning its offset if it is authentic and in bounds for a `T`.
+    fn resolve<T>(&self, handle: SharedHandle<T>) -> Option<usize> {
+        let plain = Speck::new().decrypt(handle.sealed, self.key() ^ handle.nonce);
+        let offset = plain & ((1 << OFFSET_BITS) - 1);
+        if plain != offset | handle_mac(self.key(), handle.nonce, offset, size_of::<T>()) {
+            return None;
+        }
+
+        let end = offset.checked_add(size_of::<T>() as u64)?;
+        let allocated = self.cursor().load(Ordering::Acquire);
+        let in_bounds = offset >= (HEADER_WORDS * 8) as u64
+            && end <= allocated
+            && end <= self.mapping.len as u64;
+        (in_bounds && offset.is_multiple_of(align_of::<T>() as u64)).then_some(offset as usize)
+    }
+}
+
+impl fmt::Debug for SharedRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "SharedRegion");
+        f.debug_struct("SharedRegion")
+            .field("len", &self.mapping.len)
+            .field("allocated", &self.cursor().load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+/// An encrypted reference to a `T` in a [`SharedRegion`], safe to send to the other process.
+///
+/// Sealed under the region key with a fresh nonce, so two handles to the
+/// same value look unrelated, and authenticated, so a modified handle
+/// resolves to `None` instead of another offset.
+pub struct SharedHandle<T> {
+    nonce: u64,
+    sealed: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> SharedHandle<T> {
+    /// The handle as bytes, for sending over IPC.
+    pub fn to_bytes(self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.nonce.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.sealed.to_le_bytes());
+        bytes
+    }
+
+    /// A handle received as bytes; whether it is authentic is checked when it is resolved.
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self {
+            nonce: u64::from_le_bytes(bytes[..8].try_into().unwrap()),
+            sealed: u64::from_le_bytes(bytes[8..].try_into().unwrap()),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for SharedHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SharedHandle<T> {}
+
+impl<T> fmt::Debug for SharedHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "SharedHandle");
+        f.debug_struct("SharedHandle")
+            .field("type", &type_name::<T>())
+            .field("sealed", &format!("{:#x}", self.sealed))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SECRET: u64 = 0x51A2_ED5E_C2E7_0042;
+
+    #[test]
+    fn handles_resolve_in_the_other_mapping() {
+        let path = std::env::temp_dir().join(format!("pointerguard-shm-{}", std::process::id()));
+        let game = SharedRegion::create(&path, 4096, SECRET).unwrap();
+        let helper = SharedRegion::open(&path, SECRET).unwrap();
+
+        let score = game.alloc([7u32, 11, 13]).unwrap();
+        let received = SharedHandle::<[u32; 3]>::from_bytes(score.to_bytes());
+        assert!(helper.write(received, [7, 12, 13]));
+        assert_eq!(game.read(score), Some([7, 12, 13]));
+
+        // the handle never holds the offset in plaintext, and forging one fails.
+        let mut forged = score.to_bytes();
+        forged[8] ^= 1;
+        assert_eq!(
+            helper.read(SharedHandle::<[u32; 3]>::from_bytes(forged)),
+            None
+        );
+        assert_eq!(
+            helper.read(SharedHandle::<u64>::from_bytes(score.to_bytes())),
+            None
+        );
+        assert_eq!(
+            SharedRegion::open(&path, SECRET ^ 1).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn alloc_never_overwrites_the_header() {
+        let path =
+            std::env::temp_dir().join(format!("pointerguard-shm-cursor-{}", std::process::id()));
+        let region = SharedRegion::create(&path, 64, SECRET).unwrap();
+
+        // a cursor rewound into the header, as by a hostile process.
+        region.cursor().store(0, Ordering::Release);
+        let value = region.alloc(u64::MAX).unwrap();
+        assert_eq!(region.read(value), Some(u64::MAX));
+        assert!(SharedRegion::open(&path, SECRET).is_ok());
+        std::fs::remove_file(path).unwrap();
+    }
+}
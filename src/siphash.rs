@@ -0,0 +1,185 @@
+use crate::diag::diag;
+use crate::Encrypt;
+
+/// SipHash-2-4 of a single little-endian word under the 128-bit key `(k0, k1)`.
+#[inline(always)]
+pub(crate) fn siphash24_u64(k0: u64, k1: u64, msg: u64) -> u64 {
+    let mut v = [
+        k0 ^ 0x736f6d6570736575,
+        k1 ^ 0x646f72616e646f6d,
+        k0 ^ 0x6c7967656e657261,
+        k1 ^ 0x7465646279746573,
+    ];
+
+    compress(&mut v, msg);
+    compress(&mut v, 8 << 56);
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        round(&mut v);
+    }
+
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[inline(always)]
+fn compress(v: &mut [u64; 4], m: u64) {
+    v[3] ^= m;
+    round(v);
+    round(v);
+    v[0] ^= m;
+}
+
+#[inline(always)]
+fn round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+/// A 64-bit Feistel network using SipHash-2-4 as the round function.
+///
+/// SipHash is a keyed PRF, so with four or more rounds this is a strong
+/// pseudorandom permutation while keeping the 64-bit block size needed for
+/// in-place pointer encryption.
+#[derive(Debug, Clone, Copy)]
+pub struct SipFeistel {
+    rounds: u32,
+}
+
+impl Default for SipFeistel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SipFeistel {
+    /// Default number of Feistel rounds.
+    pub const DEFAULT_ROUNDS: u32 = 4;
+
+    /// A Feistel network with the default four rounds.
+    pub const fn new() -> Self {
+        Self::with_rounds(Self::DEFAULT_ROUNDS)
+    }
+
+    /// A Feistel network with a custom number of rounds, at least 1.
+    pub const fn with_rounds(rounds: u32) -> Self {
+        assert!(
+            rounds >= 1,
+            "{}",
+            diag!("SipFeistel needs at least 1 round")
+        );
+        Self { rounds }
+    }
+
+    /// Number of rounds used.
+    pub const fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    /// The round function: SipHash of the half-block and round number.
+    #[inline(always)]
+    fn round_function(key: (u64, u64), half: u32, round: u32) -> u32 {
+        siphash24_u64(key.0, key.1, (round as u64) << 32 | half as u64) as u32
+    }
+
+    /// Stretch a 64-bit key into the 128-bit SipHash key.
+    #[inline(always)]
+    fn expand_key(key: u64) -> (u64, u64) {
+        (key, key.rotate_left(32) ^ 0xA0761D6478BD642F)
+    }
+}
+
+impl Encrypt for SipFeistel {
+    #[inline(always)]
+    fn encrypt(&self, data: u64, key: u64) -> u64 {
+        let key = Self::expand_key(key);
+        let (mut left, mut right) = ((data >> 32) as u32, data as u32);
+
+        for round in 0..self.rounds {
+            (left, right) = (right, left ^ Self::round_function(key, right, round));
+        }
+
+        (left as u64) << 32 | right as u64
+    }
+
+    #[inline(always)]
+    fn decrypt(&self, data: u64, key: u64) -> u64 {
+        let key = Self::expand_key(key);
+        let (mut left, mut right) = ((data >> 32) as u32, data as u32);
+
+        for round in (0..self.rounds).rev() {
+            (left, right) = (right ^ Self::round_function(key, left, round), left);
+        }
+
+        (left as u64) << 32 | right as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Byte-oriented SipHash-2-4 of `msg` under the 128-bit key `(k0, k1)`.
+    fn siphash24(k0: u64, k1: u64, msg: &[u8]) -> u64 {
+        let mut v = [
+            k0 ^ 0x736f6d6570736575,
+            k1 ^ 0x646f72616e646f6d,
+            k0 ^ 0x6c7967656e657261,
+            k1 ^ 0x7465646279746573,
+        ];
+
+        let mut chunks = msg.chunks_exact(8);
+        for chunk in &mut chunks {
+            compress(&mut v, u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+
+        // the final block holds the leftover bytes and the message length.
+        let mut last = [0u8; 8];
+        last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+        compress(&mut v, u64::from_le_bytes(last) | (msg.len() as u64) << 56);
+
+        v[2] ^= 0xff;
+        for _ in 0..4 {
+            round(&mut v);
+        }
+
+        v[0] ^ v[1] ^ v[2] ^ v[3]
+    }
+
+    #[test]
+    fn siphash_known_answer() {
+        // reference vectors: key 00..0f, messages 00..(len - 1).
+        let k0 = u64::from_le_bytes([0, 1, 2, 3, 4, 5, 6, 7]);
+        let k1 = u64::from_le_bytes([8, 9, 10, 11, 12, 13, 14, 15]);
+        let msg = [0, 1, 2, 3, 4, 5, 6, 7];
+
+        assert_eq!(siphash24(k0, k1, &[]), 0x726fdb47dd0e0e31);
+        assert_eq!(siphash24(k0, k1, &msg), 0x93f5f5799a932462);
+        assert_eq!(
+            siphash24_u64(k0, k1, u64::from_le_bytes(msg)),
+            0x93f5f5799a932462
+        );
+    }
+
+    #[test]
+    fn decrypt_value_sip_feistel() {
+        let key: u64 = 0x1234567890ABCDEF;
+        let data: u64 = 0xFEDCBA0987654321;
+
+        for feistel in [SipFeistel::new(), SipFeistel::with_rounds(7)] {
+            let encrypted = feistel.encrypt(data, key);
+            let decrypted = feistel.decrypt(encrypted, key);
+
+            assert_eq!(data, decrypted);
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::sealed::Sealed;
+use crate::{master, SendEncryptedPtr};
+
+/// Encrypt the address of a pointer leaving the caller's hands.
+fn seal<T>(ptr: SendEncryptedPtr<T>) -> Sealed {
+    Sealed::new(ptr.into_inner().into_raw() as u64)
+}
+
+/// Turn a sealed address back into an owning pointer.
+fn unseal<T>(sealed: Sealed) -> SendEncryptedPtr<T> {
+    SendEncryptedPtr::new(sealed.get() as *mut T)
+}
+
+/// Free the pointee of a sealed address nobody will take.
+fn discard<T>(sealed: Sealed) {
+    // leak rather than free memory we may no longer be able to locate.
+    if !master::is_poisoned() {
+        drop(unseal::<T>(sealed));
+    }
+}
+
+/// A single slot for handing a protected pointer to another thread.
+///
+/// The address in the slot is encrypted under its own key, like any
+/// protected pointer, so a slot waiting to be taken holds no plaintext.
+pub struct EncryptedSlot<T> {
+    sealed: Mutex<Option<Sealed>>,
+    _marker: PhantomData<T>,
+}
+
+// hands over ownership of a T, like `Mutex<Option<Box<T>>>`.
+unsafe impl<T: Send> Send for EncryptedSlot<T> {}
+unsafe impl<T: Send> Sync for EncryptedSlot<T> {}
+
+impl<T> EncryptedSlot<T> {
+    /// Create an empty slot.
+    pub const fn new() -> Self {
+        Self {
+            sealed: Mutex::new(None),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Put `ptr` in the slot, or give it back if the slot is full.
+    pub fn put(&self, ptr: impl Into<SendEncryptedPtr<T>>) -> Result<(), SendEncryptedPtr<T>> {
+        let mut sealed = self.sealed.lock().unwrap_or_else(|e| e.into_inner());
+        if sealed.is_some() {
+            return Err(ptr.into());
+        }
+
+        *sealed = Some(seal(ptr.into()));
+        Ok(())
+    }
+
+    /// Take the pointer out of the slot, if there is one.
+    pub fn take(&self) -> Option<SendEncryptedPtr<T>> {
+        let sealed = self.sealed.lock().unwrap_or_else(|e| e.into_inner()).take();
+        sealed.map(unseal)
+    }
+
+    /// Whether the slot holds a pointer.
+    pub fn is_full(&self) -> bool {
+        self.sealed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+}
+
+impl<T> Default for EncryptedSlot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for EncryptedSlot<T> {
+    fn drop(&mut self) {
+        let sealed = self.sealed.get_mut().unwrap_or_else(|e| e.into_inner());
+        if let Some(sealed) = sealed.take() {
+            discard::<T>(sealed);
+        }
+    }
+}
+
+impl<T> fmt::Debug for EncryptedSlot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedSlot");
+        f.debug_struct("EncryptedSlot")
+            .field("full", &self.is_full())
+            .finish()
+    }
+}
+
+struct Channel<T> {
+    state: Mutex<State>,
+    ready: Condvar,
+    _marker: PhantomData<T>,
+}
+
+struct State {
+    queue: VecDeque<Sealed>,
+    sender_alive: bool,
+}
+
+// hands over ownership of T's, like `Mutex<VecDeque<Box<T>>>`.
+unsafe impl<T: Send> Send for Channel<T> {}
+unsafe impl<T: Send> Sync for Channel<T> {}
+
+impl<T> Drop for Channel<T> {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        for sealed in state.queue.drain(..) {
+            discard::<T>(sealed);
+        }
+    }
+}
+
+/// Create a single-producer, single-consumer queue of protected pointers.
+///
+/// Queued addresses stay encrypted, each under its own key, until received,
+/// so a backlog of protected jobs isn't a list of plaintext pointers.
+pub fn encrypted_channel<T>() -> (EncryptedSender<T>, EncryptedReceiver<T>) {
+    let channel = Arc::new(Channel {
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            sender_alive: true,
+        }),
+        ready: Condvar::new(),
+        _marker: PhantomData,
+    });
+
+    (
+        EncryptedSender {
+            channel: channel.clone(),
+        },
+        EncryptedReceiver { channel },
+    )
+}
+
+/// Sending half of an [`encrypted_channel`].
+pub struct EncryptedSender<T> {
+    channel: Arc<Channel<T>>,
+}
+
+impl<T> EncryptedSender<T> {
+    /// Queue `ptr` for the receiver.
+    pub fn send(&self, ptr: impl Into<SendEncryptedPtr<T>>) {
+        let sealed = seal(ptr.into());
+        let mut state = self.channel.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.queue.push_back(sealed);
+        self.channel.ready.notify_one();
+    }
+}
+
+impl<T> Drop for EncryptedSender<T> {
+    fn drop(&mut self) {
+        let mut state = self.channel.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.sender_alive = false;
+        self.channel.ready.notify_one();
+    }
+}
+
+impl<T> fmt::Debug for EncryptedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedSender");
+        f.debug_struct("EncryptedSender").finish_non_exhaustive()
+    }
+}
+
+/// Receiving half of an [`encrypted_channel`].
+pub struct EncryptedReceiver<T> {
+    channel: Arc<Channel<T>>,
+}
+
+impl<T> EncryptedReceiver<T> {
+    /// Wait for the next pointer, or `None` once the sender is gone and the queue is empty.
+    pub fn recv(&self) -> Option<SendEncryptedPtr<T>> {
+        let mut state = self.channel.state.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(sealed) = state.queue.pop_front() {
+                return Some(unseal(sealed));
+            }
+            if !state.sender_alive {
+                return None;
+            }
+            state = self
+                .channel
+                .ready
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Take the next pointer if one is queued.
+    pub fn try_recv(&self) -> Option<SendEncryptedPtr<T>> {
+        let mut state = self.channel.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.queue.pop_front().map(unseal)
+    }
+}
+
+impl<T> fmt::Debug for EncryptedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedReceiver");
+        f.debug_struct("EncryptedReceiver").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::thread;
+
+    #[test]
+    fn slot_hands_over_once() {
+        let slot = EncryptedSlot::new();
+        slot.put(100u32).unwrap();
+
+        assert_eq!(slot.put(200).map_err(|ptr| *ptr), Err(200));
+        let taken = thread::scope(|scope| scope.spawn(|| slot.take()).join().unwrap());
+        assert_eq!(taken.as_deref(), Some(&100));
+        assert!(!slot.is_full());
+    }
+
+    #[test]
+    fn channel_delivers_in_order() {
+        let (sender, receiver) = encrypted_channel::<String>();
+        let worker = thread::spawn(move || {
+            let mut jobs = Vec::new();
+            while let Some(job) = receiver.recv() {
+                jobs.push(job.into_inner().to_string());
+            }
+            jobs
+        });
+
+        for job in ["load", "save"] {
+            sender.send(job.to_string());
+        }
+        drop(sender);
+
+        assert_eq!(worker.join().unwrap(), ["load", "save"]);
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ops::{Deref, DerefMut};
+
+use crate::pod::{self, NoUninit};
+use crate::EncryptedPtr;
+
+/// One random share of a split value.
+type Share<T> = EncryptedPtr<MaybeUninit<T>>;
+
+/// A value split into two random XOR shares kept in separate allocations.
+///
+/// Neither allocation ever holds the plaintext; the value is only recombined
+/// inside the guards returned by [`SplitPtr::access`] and
+/// [`SplitPtr::access_mut`], which wipe the plaintext copy when dropped.
+pub struct SplitPtr<T: NoUninit> {
+    shares: [Share<T>; 2],
+}
+
+impl<T: NoUninit> SplitPtr<T> {
+    /// Split `value` into two fresh shares.
+    pub fn new(value: T) -> Self {
+        let mut value = ManuallyDrop::new(value);
+        let shares = split::<T>(&value);
+        pod::wipe(&mut *value);
+
+        Self { shares }
+    }
+
+    /// Recombine the value for reading.
+    pub fn access(&self) -> SplitGuard<'_, T> {
+        SplitGuard {
+            value: combine(&self.shares),
+            _split: self,
+        }
+    }
+
+    /// Recombine the value for writing; fresh shares are made when the guard drops.
+    pub fn access_mut(&mut self) -> SplitGuardMut<'_, T> {
+        SplitGuardMut {
+            value: combine(&self.shares),
+            split: self,
+        }
+    }
+
+    /// Recombine and return the value, consuming the shares.
+    pub fn into_inner(self) -> T {
+        let this = ManuallyDrop::new(self);
+        let value = combine(&this.shares);
+
+        // the shares only hold bytes, dropping them won't drop a T.
+        let shares = unsafe { std::ptr::read(&this.shares) };
+        drop(shares);
+
+        ManuallyDrop::into_inner(value)
+    }
+}
+
+/// Make two random shares whose XOR is the bytes of `value`.
+fn split<T: NoUninit>(value: &T) -> [Share<T>; 2] {
+    let mut a = Box::new(MaybeUninit::<T>::uninit());
+    let mut b = Box::new(MaybeUninit::<T>::uninit());
+
+    let a_bytes = a.as_mut_ptr() as *mut u8;
+    let b_bytes = b.as_mut_ptr() as *mut u8;
+
+    for (i, byte) in pod::bytes_of(value).iter().enumerate() {
+        let noise: u8 = rand::random();
+        unsafe {
+            a_bytes.add(i).write(noise);
+            b_bytes.add(i).write(byte ^ noise);
+        }
+    }
+
+    [a.into(), b.into()]
+}
+
+/// Rebuild the value from its shares.
+fn combine<T: NoUninit>(shares: &[Share<T>; 2]) -> ManuallyDrop<T> {
+    let mut value = MaybeUninit::<T>::uninit();
+    let out = value.as_mut_ptr() as *mut u8;
+
+    let a = shares[0].access().as_ptr() as *const u8;
+    let b = shares[1].access().as_ptr() as *const u8;
+
+    for i in 0..size_of::<T>() {
+        unsafe { out.add(i).write(a.add(i).read() ^ b.add(i).read()) };
+    }
+
+    ManuallyDrop::new(unsafe { value.assume_init() })
+}
+
+impl<T: NoUninit> Drop for SplitPtr<T> {
+    fn drop(&mut self) {
+        // recombine one last time so T's destructor runs.
+        let mut value = combine(&self.shares);
+        unsafe { ManuallyDrop::drop(&mut value) };
+    }
+}
+
+impl<T: NoUninit + fmt::Debug> fmt::Debug for SplitPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "SplitPtr");
+        f.debug_struct("SplitPtr")
+            .field("value", &*self.access())
+            .finish()
+    }
+}
+
+/// Read access to a recombined [`SplitPtr`] value.
+pub struct SplitGuard<'a, T: NoUninit> {
+    value: ManuallyDrop<T>,
+    _split: &'a SplitPtr<T>,
+}
+
+impl<T: NoUninit> Deref for SplitGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: NoUninit> Drop for SplitGuard<'_, T> {
+    fn drop(&mut self) {
+        // this is a bitwise copy; wipe it instead of running T's destructor.
+        pod::wipe(&mut *self.value);
+    }
+}
+
+/// Write access to a recombined [`SplitPtr`] value.
+pub struct SplitGuardMut<'a, T: NoUninit> {
+    value: ManuallyDrop<T>,
+    split: &'a mut SplitPtr<T>,
+}
+
+impl<T: NoUninit> Deref for SplitGuardMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: NoUninit> DerefMut for SplitGuardMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: NoUninit> Drop for SplitGuardMut<'_, T> {
+    fn drop(&mut self) {
+        // replacing the shares frees the old ones, which only hold bytes.
+        self.split.shares = split(&self.value);
+        pod::wipe(&mut *self.value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn split_round_trip() {
+        let split = SplitPtr::new(0xDEADBEEFu32);
+        assert_eq!(*split.access(), 0xDEADBEEF);
+        assert_eq!(split.into_inner(), 0xDEADBEEF);
+    }
+
+    #[test]
+    fn split_write_reshares() {
+        let mut split = SplitPtr::new([1u64, 2, 3]);
+        let before = unsafe { (*split.shares[0]).as_ptr().read() };
+
+        split.access_mut()[1] = 20;
+
+        let after = unsafe { (*split.shares[0]).as_ptr().read() };
+        assert_eq!(*split.access(), [1, 20, 3]);
+        assert!(before != after);
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+static LIVE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static PROTECTED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static REKEYS: AtomicU64 = AtomicU64::new(0);
+static TAMPER_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+/// Protection overhead counters for the whole process, see [`stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Protected allocations currently alive.
+    pub live_allocations: usize,
+    /// Bytes currently behind protected pointers, including unused vector capacity.
+    pub protected_bytes: usize,
+    /// Key rolls since startup.
+    pub rekeys: u64,
+    /// Tamper events reported since startup.
+    pub tamper_events: u64,
+}
+
+/// Snapshot the process-wide protection counters.
+///
+/// Each counter is read separately, so a snapshot taken while other threads
+/// allocate may be slightly inconsistent.
+pub fn stats() -> Stats {
+    Stats {
+        live_allocations: LIVE_ALLOCATIONS.load(Ordering::Relaxed),
+        protected_bytes: PROTECTED_BYTES.load(Ordering::Relaxed),
+        rekeys: REKEYS.load(Ordering::Relaxed),
+        tamper_events: TAMPER_EVENTS.load(Ordering::Relaxed),
+    }
+}
+
+/// Count a new protected allocation of `bytes`.
+#[inline(always)]
+pub(crate) fn allocated(bytes: usize) {
+    LIVE_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    PROTECTED_BYTES.fetch_add(bytes, Ordering::Relaxed);
+}
+
+/// Count a protected allocation of `bytes` going away.
+#[inline(always)]
+pub(crate) fn freed(bytes: usize) {
+    LIVE_ALLOCATIONS.fetch_sub(1, Ordering::Relaxed);
+    PROTECTED_BYTES.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+/// Count a protected allocation growing or shrinking from `old` to `new` bytes.
+#[inline(always)]
+pub(crate) fn resized(old: usize, new: usize) {
+    if new >= old {
+        PROTECTED_BYTES.fetch_add(new - old, Ordering::Relaxed);
+    } else {
+        PROTECTED_BYTES.fetch_sub(old - new, Ordering::Relaxed);
+    }
+}
+
+/// Count a key roll.
+pub(crate) fn rekeyed() {
+    REKEYS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a tamper event.
+pub(crate) fn tampered() {
+    TAMPER_EVENTS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EncryptedPtr, EncryptedVec};
+
+    #[test]
+    fn stats_track_allocations() {
+        let before = stats();
+
+        let big: EncryptedPtr<[u8; 4096]> = [0; 4096].into();
+        let mut items: EncryptedVec<u64> = Vec::with_capacity(512).into();
+        items.push(1);
+        rekeyed();
+
+        // other tests allocate concurrently, so only lower bounds hold.
+        let during = stats();
+        assert!(during.protected_bytes >= 4096 + 512 * 8);
+        assert!(during.rekeys > before.rekeys);
+
+        drop((big, items));
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::process;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, ThreadId};
+use std::time::SystemTime;
+
+use crate::config::{self, TamperPolicy};
+use crate::diag::diag_panic;
+use crate::{master, registry, reporter, stats};
+
+/// What kind of tampering was detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TamperKind {
+    /// A honeypot's bait value was overwritten from outside.
+    HoneypotModified,
+    /// A honeypot's bait value was pinned to an old value after a refresh.
+    HoneypotFrozen,
+    /// A value and its shadow copy disagree.
+    ShadowMismatch,
+    /// A trait object's vtable was swapped or patched.
+    VtableMismatch,
+    /// A stale copy of a pointer's bytes was pasted back over it.
+    SnapshotReplay,
+    /// A pointer's ciphertext failed the MAC embedded in its upper bits.
+    MacMismatch,
+    /// Reading or writing through a freshly decrypted pointer faulted, see `install_fault_handler`.
+    FaultingDecrypt,
+    /// A watched value changed without a write through its guard, found by the heartbeat.
+    ExternalWrite,
+    /// An object graph no longer matches its `seal_graph` MAC.
+    GraphMismatch,
+}
+
+/// A single tamper detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TamperEvent {
+    /// What was detected.
+    pub kind: TamperKind,
+    /// Type name of the protected object.
+    pub type_name: &'static str,
+    /// The thread that detected it.
+    pub thread: ThreadId,
+    /// When it was detected.
+    pub timestamp: SystemTime,
+    /// [`registry`] id of the affected pointer, when the registry is enabled and knows it.
+    pub ptr_id: Option<u64>,
+}
+
+impl TamperEvent {
+    /// An event detected now, on this thread.
+    pub(crate) fn new(kind: TamperKind, type_name: &'static str) -> Self {
+        Self {
+            kind,
+            type_name,
+            thread: thread::current().id(),
+            timestamp: SystemTime::now(),
+            ptr_id: None,
+        }
+    }
+
+    /// Attribute the event to the registered pointer to `ptr`.
+    pub(crate) fn with_ptr<T>(mut self, ptr: *const T) -> Self {
+        self.ptr_id = registry::id_of(ptr as u64);
+        self
+    }
+}
+
+/// Error accessing a pointer that failed an integrity check earlier.
+///
+/// Once poisoned, a pointer stays that way: it is never verified again
+/// against memory an attacker may now control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Poisoned;
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("pointer was poisoned by a failed integrity check")
+    }
+}
+
+impl Error for Poisoned {}
+
+type Callback = Arc<dyn Fn(&TamperEvent) + Send + Sync>;
+
+static CALLBACK: RwLock<Option<Callback>> = RwLock::new(None);
+
+/// Set the callback invoked whenever tampering is detected.
+pub fn set_tamper_callback<F>(callback: F)
+where
+    F: Fn(&TamperEvent) + Send + Sync + 'static,
+{
+    *CALLBACK.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(callback));
+}
+
+/// Remove the tamper callback, if any.
+pub fn clear_tamper_callback() {
+    *CALLBACK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Events [`drain_events`] keeps by default before dropping the oldest.
+pub const DEFAULT_EVENT_CAPACITY: usize = 256;
+
+struct Stream {
+    events: VecDeque<TamperEvent>,
+    capacity: usize,
+    dropped: u64,
+}
+
+static STREAM: Mutex<Stream> = Mutex::new(Stream {
+    events: VecDeque::new(),
+    capacity: DEFAULT_EVENT_CAPACITY,
+    dropped: 0,
+});
+
+fn stream() -> std::sync::MutexGuard<'static, Stream> {
+    STREAM.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Take every buffered tamper event, oldest first.
+///
+/// Every reported event is also kept in a bounded buffer, so the application
+/// can forward them to its server in batches, from whichever thread suits it,
+/// instead of buffering inside the callback.
+pub fn drain_events() -> Vec<TamperEvent> {
+    stream().events.drain(..).collect()
+}
+
+/// Keep at most `capacity` undrained events, dropping the oldest beyond that; zero disables buffering.
+pub fn set_event_capacity(capacity: usize) {
+    let mut stream = stream();
+    stream.capacity = capacity;
+    while stream.events.len() > capacity {
+        stream.events.pop_front();
+        stream.dropped += 1;
+    }
+}
+
+/// Number of events dropped because the buffer was full.
+pub fn dropped_events() -> u64 {
+    stream().dropped
+}
+
+/// Report a tamper event to the buffer and the registered callback, then apply the [`TamperPolicy`].
+pub(crate) fn report(event: TamperEvent) {
+    stats::tampered();
+
+    let mut stream = stream();
+    if stream.capacity != 0 {
+        if stream.events.len() >= stream.capacity {
+            stream.events.pop_front();
+            stream.dropped += 1;
+        }
+        stream.events.push_back(event);
+    }
+    drop(stream);
+
+    // the callback may itself report or replace the callback, so don't hold the lock.
+    let callback = CALLBACK.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(callback) = callback {
+        callback(&event);
+    }
+    reporter::with_reporter(|reporter| reporter.on_tamper(&event));
+
+    let policy = config::read(|config| config.tamper_policy);
+    match policy {
+        TamperPolicy::Report => {}
+        TamperPolicy::Panic => diag_panic!(
+            "tampering detected: {:?} on {}",
+            event.kind,
+            event.type_name
+        ),
+        TamperPolicy::Poison => master::poison(),
+        TamperPolicy::Abort => process::abort(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EncryptedPtr;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn events_are_buffered_for_draining() {
+        registry::enable();
+        let player: EncryptedPtr<u32> = Box::new(100).into();
+        report(
+            TamperEvent::new(TamperKind::MacMismatch, "tamper-test").with_ptr(player.decrypt_ptr()),
+        );
+
+        // other tests report events too, so look for ours only.
+        let event = drain_events()
+            .into_iter()
+            .find(|event| event.type_name == "tamper-test")
+            .unwrap();
+        assert_eq!(event.thread, thread::current().id());
+        assert!(event.timestamp <= SystemTime::now());
+        assert!(event.ptr_id.is_some());
+    }
+
+    #[test]
+    fn callback_can_replace_itself() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        // replacing the callback from inside it would deadlock if it ran under the lock.
+        set_tamper_callback(|_| {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            clear_tamper_callback();
+        });
+        report(TamperEvent::new(TamperKind::MacMismatch, "callback-test"));
+        report(TamperEvent::new(TamperKind::MacMismatch, "callback-test"));
+
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    }
+}
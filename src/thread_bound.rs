@@ -0,0 +1,146 @@
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::sealed::Sealed;
+use crate::{master, registry};
+
+thread_local! {
+    static SALT: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Establish this thread's salt, see [`ThreadBoundPtr`].
+///
+/// Salts are otherwise made on first use; call this from a thread-start hook
+/// (e.g. tokio's `on_thread_start`) to have it ready before any pointer is
+/// created. Returns whether the thread didn't have a salt yet.
+pub fn init_thread_salt() -> bool {
+    SALT.with(|salt| {
+        if salt.get() != 0 {
+            return false;
+        }
+
+        // 48 bits, so a salted address still leaves room for the mac feature.
+        salt.set(rand::random::<u64>() >> 16 | 1);
+        true
+    })
+}
+
+/// This thread's salt.
+#[inline(always)]
+fn thread_salt() -> u64 {
+    if cfg!(passthrough) {
+        return 0;
+    }
+
+    init_thread_salt();
+    SALT.with(Cell::get)
+}
+
+/// An encrypted pointer that only decrypts on the thread that created it.
+///
+/// A random per-thread salt is mixed into the address before it is
+/// encrypted, so a ciphertext and key dumped on one thread are useless with
+/// the state observed on any other. Like [`EncryptedPtr`](crate::EncryptedPtr)
+/// it owns its pointee, and it is never `Send`.
+pub struct ThreadBoundPtr<T> {
+    sealed: Sealed,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> ThreadBoundPtr<T> {
+    /// Create a new thread-bound pointer from a raw pointer.
+    pub fn new(ptr: *mut T) -> Self {
+        registry::register(ptr);
+
+        Self {
+            sealed: Sealed::new(ptr as u64 ^ thread_salt()),
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    fn decrypt_ptr(&self) -> *mut T {
+        (self.sealed.get() ^ thread_salt()) as *mut T
+    }
+}
+
+impl<T> From<Box<T>> for ThreadBoundPtr<T> {
+    fn from(value: Box<T>) -> Self {
+        Self::new(Box::into_raw(value))
+    }
+}
+
+impl<T> From<T> for ThreadBoundPtr<T> {
+    fn from(value: T) -> Self {
+        Box::new(value).into()
+    }
+}
+
+impl<T> Deref for ThreadBoundPtr<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.decrypt_ptr() }
+    }
+}
+
+impl<T> DerefMut for ThreadBoundPtr<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.decrypt_ptr() }
+    }
+}
+
+impl<T> Drop for ThreadBoundPtr<T> {
+    fn drop(&mut self) {
+        // leak rather than free memory we may no longer be able to locate.
+        if master::is_poisoned() {
+            return;
+        }
+
+        let ptr = self.decrypt_ptr();
+        registry::unregister(ptr);
+        drop(unsafe { Box::from_raw(ptr) });
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ThreadBoundPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "ThreadBoundPtr");
+        f.debug_struct("ThreadBoundPtr")
+            .field(
+                "encrypted_value",
+                &format!("{:#x}", self.sealed.ciphertext()),
+            )
+            .field("pointed_value", &self.deref())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn thread_bound_round_trip() {
+        let mut health: ThreadBoundPtr<u32> = 100.into();
+        *health -= 25;
+
+        assert_eq!(*health, 75);
+    }
+
+    #[cfg(not(passthrough))]
+    #[test]
+    fn salts_differ_between_threads() {
+        let here = thread_salt();
+        let there = std::thread::spawn(thread_salt).join().unwrap();
+
+        assert_eq!(thread_salt(), here);
+        assert!(here != there);
+        assert!(!init_thread_salt());
+    }
+}
@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::thread::LocalKey;
+
+use crate::EncryptedPtr;
+
+#[doc(hidden)]
+pub type Slot<T> = RefCell<Option<EncryptedPtr<T>>>;
+
+/// A thread-local value behind a per-thread encrypted pointer, declared with [`encrypted_thread_local!`](crate::encrypted_thread_local).
+///
+/// Each thread's TLS slot only holds its own independently keyed
+/// ciphertext, with the value itself on the heap, so walking thread-local
+/// storage doesn't lead to it.
+pub struct EncryptedThreadLocal<T: 'static> {
+    slot: &'static LocalKey<Slot<T>>,
+    init: fn() -> T,
+}
+
+impl<T: 'static> EncryptedThreadLocal<T> {
+    #[doc(hidden)]
+    pub const fn new(slot: &'static LocalKey<Slot<T>>, init: fn() -> T) -> Self {
+        Self { slot, init }
+    }
+
+    /// Run `f` with this thread's value, initializing it on first use.
+    ///
+    /// # Panics
+    /// If called from inside [`EncryptedThreadLocal::with_mut`] on the same value.
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        self.slot.with(|slot| {
+            self.init_slot(slot);
+            f(slot.borrow().as_ref().unwrap().access())
+        })
+    }
+
+    /// Run `f` with mutable access to this thread's value, initializing it on first use.
+    ///
+    /// # Panics
+    /// If called from inside `with` or `with_mut` on the same value.
+    pub fn with_mut<R>(&'static self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.slot.with(|slot| {
+            self.init_slot(slot);
+            f(slot.borrow_mut().as_mut().unwrap().access_mut())
+        })
+    }
+
+    /// Replace this thread's value under a fresh key.
+    pub fn set(&'static self, value: T) {
+        self.slot
+            .with(|slot| *slot.borrow_mut() = Some(value.into()));
+    }
+
+    fn init_slot(&self, slot: &Slot<T>) {
+        if slot.borrow().is_none() {
+            let value = (self.init)();
+            *slot.borrow_mut() = Some(value.into());
+        }
+    }
+}
+
+impl<T: 'static> fmt::Debug for EncryptedThreadLocal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedThreadLocal");
+        f.debug_struct("EncryptedThreadLocal")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Declare thread-local values behind per-thread encrypted pointers, like [`thread_local!`].
+///
+/// ```
+/// pointerguard::encrypted_thread_local! {
+///     static SESSION_TOKEN: u64 = 0;
+/// }
+///
+/// SESSION_TOKEN.with_mut(|token| *token = 0x1234);
+/// assert_eq!(SESSION_TOKEN.with(|token| *token), 0x1234);
+/// ```
+#[macro_export]
+macro_rules! encrypted_thread_local {
+    () => {};
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr; $($rest:tt)*) => {
+        $crate::encrypted_thread_local!($(#[$attr])* $vis static $name: $t = $init);
+        $crate::encrypted_thread_local!($($rest)*);
+    };
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr) => {
+        $(#[$attr])*
+        $vis static $name: $crate::EncryptedThreadLocal<$t> = {
+            ::std::thread_local! {
+                static SLOT: $crate::__ThreadLocalSlot<$t> =
+                    const { ::std::cell::RefCell::new(::std::option::Option::None) };
+            }
+
+            $crate::EncryptedThreadLocal::new(&SLOT, || $init)
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use std::thread;
+
+    crate::encrypted_thread_local! {
+        static COUNTER: u32 = 10;
+        static NAME: String = String::from("player");
+    }
+
+    #[test]
+    fn each_thread_has_its_own_value() {
+        COUNTER.with_mut(|counter| *counter += 1);
+
+        let other = thread::spawn(|| COUNTER.with(|counter| *counter))
+            .join()
+            .unwrap();
+
+        assert_eq!(other, 10);
+        assert_eq!(COUNTER.with(|counter| *counter), 11);
+        assert_eq!(NAME.with(|name| name.len()), 6);
+    }
+}
@@ -0,0 +1,495 @@
+use std::ffi::{c_char, CStr, CString, NulError};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::slice;
+
+use crate::diag::diag;
+use crate::sealed::Sealed;
+use crate::{master, stats, wipe, AllocError};
+
+/// A growable array whose buffer address is encrypted, the protected counterpart of `Vec<T>`.
+///
+/// Dereferences to `[T]`; growing goes through [`EncryptedVec::with_vec`],
+/// which re-encrypts the (possibly moved) buffer afterwards.
+pub struct EncryptedVec<T> {
+    buf: Sealed,
+    len: usize,
+    cap: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T> EncryptedVec<T> {
+    /// Create an empty vector; nothing is allocated until it grows.
+    pub fn new() -> Self {
+        Vec::new().into()
+    }
+
+    /// Create an empty vector with room for `capacity` elements, or an error if allocation fails.
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, AllocError> {
+        let mut vec = Vec::new();
+        vec.try_reserve_exact(capacity)?;
+        Ok(vec.into())
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append an element.
+    pub fn push(&mut self, value: T) {
+        self.with_vec(|vec| vec.push(value));
+    }
+
+    /// Reserve room for `additional` more elements, or an error if allocation fails.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        self.with_vec(|vec| vec.try_reserve(additional))?;
+        Ok(())
+    }
+
+    /// Append an element, or give it back with an error if growing fails.
+    pub fn try_push(&mut self, value: T) -> Result<(), (T, AllocError)> {
+        if let Err(error) = self.try_reserve(1) {
+            return Err((value, error));
+        }
+
+        self.push(value);
+        Ok(())
+    }
+
+    /// Remove and return the last element.
+    pub fn pop(&mut self) -> Option<T> {
+        self.with_vec(Vec::pop)
+    }
+
+    /// Iterate over the elements, decrypting the buffer address once up front.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.deref().iter()
+    }
+
+    /// Iterate mutably over the elements, decrypting the buffer address once up front.
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.deref_mut().iter_mut()
+    }
+
+    /// Iterate over `size`-element chunks, decrypting the buffer address afresh for each.
+    ///
+    /// The plaintext address only lives as long as one chunk, while the
+    /// elements within a chunk are read at plain slice speed.
+    ///
+    /// # Panics
+    /// If `size` is zero.
+    pub fn chunks(&self, size: usize) -> EncryptedChunks<'_, T> {
+        assert!(size != 0, "{}", diag!("chunk size must be non-zero"));
+        EncryptedChunks {
+            vec: self,
+            offset: 0,
+            size,
+        }
+    }
+
+    /// Mutable counterpart of [`EncryptedVec::chunks`].
+    ///
+    /// # Panics
+    /// If `size` is zero.
+    pub fn chunks_mut(&mut self, size: usize) -> EncryptedChunksMut<'_, T> {
+        assert!(size != 0, "{}", diag!("chunk size must be non-zero"));
+        EncryptedChunksMut {
+            vec: self,
+            offset: 0,
+            size,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Run `f` on the underlying `Vec`, re-encrypting the buffer afterwards.
+    pub fn with_vec<R>(&mut self, f: impl FnOnce(&mut Vec<T>) -> R) -> R {
+        // re-seal even if `f` unwinds, so the buffer is never freed twice.
+        struct Reseal<'a, T> {
+            owner: &'a mut EncryptedVec<T>,
+            vec: ManuallyDrop<Vec<T>>,
+        }
+        impl<T> Drop for Reseal<'_, T> {
+            fn drop(&mut self) {
+                self.owner.seal(&self.vec);
+            }
+        }
+
+        let vec = unsafe { Vec::from_raw_parts(self.decrypt_ptr(), self.len, self.cap) };
+        let mut reseal = Reseal {
+            owner: self,
+            vec: ManuallyDrop::new(vec),
+        };
+        f(&mut reseal.vec)
+    }
+
+    /// Take the contents back out as a plain `Vec`.
+    pub fn into_vec(self) -> Vec<T> {
+        let this = ManuallyDrop::new(self);
+        stats::freed(this.cap * size_of::<T>());
+        unsafe { Vec::from_raw_parts(this.decrypt_ptr(), this.len, this.cap) }
+    }
+
+    /// Encrypt the raw parts of `vec`, which this vector now owns.
+    fn seal(&mut self, vec: &Vec<T>) {
+        stats::resized(self.cap * size_of::<T>(), vec.capacity() * size_of::<T>());
+        self.buf = Sealed::new(vec.as_ptr() as u64);
+        self.len = vec.len();
+        self.cap = vec.capacity();
+    }
+
+    #[inline(always)]
+    pub(crate) fn decrypt_ptr(&self) -> *mut T {
+        self.buf.get() as *mut T
+    }
+}
+
+impl<T> Default for EncryptedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<Vec<T>> for EncryptedVec<T> {
+    fn from(value: Vec<T>) -> Self {
+        let vec = ManuallyDrop::new(value);
+        stats::allocated(vec.capacity() * size_of::<T>());
+
+        Self {
+            buf: Sealed::new(vec.as_ptr() as u64),
+            len: vec.len(),
+            cap: vec.capacity(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> From<EncryptedVec<T>> for Vec<T> {
+    fn from(value: EncryptedVec<T>) -> Self {
+        value.into_vec()
+    }
+}
+
+impl<T> Deref for EncryptedVec<T> {
+    type Target = [T];
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { slice::from_raw_parts(self.decrypt_ptr(), self.len) }
+    }
+}
+
+impl<T> DerefMut for EncryptedVec<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { slice::from_raw_parts_mut(self.decrypt_ptr(), self.len) }
+    }
+}
+
+impl<T> Drop for EncryptedVec<T> {
+    fn drop(&mut self) {
+        // leak rather than free memory we may no longer be able to locate.
+        if master::is_poisoned() {
+            return;
+        }
+
+        stats::freed(self.cap * size_of::<T>());
+        let ptr = self.decrypt_ptr();
+        unsafe {
+            // drop the elements, then scrub the whole buffer before freeing it.
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(ptr, self.len));
+            let bytes = self.cap * size_of::<T>();
+            if bytes != 0 && wipe::enabled() {
+                wipe::wipe(ptr as *mut u8, bytes);
+            }
+            drop(Vec::from_raw_parts(ptr, 0, self.cap));
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a EncryptedVec<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut EncryptedVec<T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Iterator over chunks of an [`EncryptedVec`], see [`EncryptedVec::chunks`].
+pub struct EncryptedChunks<'a, T> {
+    vec: &'a EncryptedVec<T>,
+    offset: usize,
+    size: usize,
+}
+
+impl<'a, T> Iterator for EncryptedChunks<'a, T> {
+    type Item = &'a [T];
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.vec.len {
+            return None;
+        }
+
+        let len = self.size.min(self.vec.len - self.offset);
+        let chunk = unsafe { slice::from_raw_parts(self.vec.decrypt_ptr().add(self.offset), len) };
+        self.offset += len;
+        Some(chunk)
+    }
+}
+
+/// Iterator over mutable chunks of an [`EncryptedVec`], see [`EncryptedVec::chunks_mut`].
+pub struct EncryptedChunksMut<'a, T> {
+    // raw, so handing out chunks doesn't reborrow the whole vector.
+    vec: *const EncryptedVec<T>,
+    offset: usize,
+    size: usize,
+    _marker: PhantomData<&'a mut EncryptedVec<T>>,
+}
+
+impl<'a, T> Iterator for EncryptedChunksMut<'a, T> {
+    type Item = &'a mut [T];
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let vec = unsafe { &*self.vec };
+        if self.offset >= vec.len {
+            return None;
+        }
+
+        // chunks never overlap, so each may be borrowed mutably for all of 'a.
+        let len = self.size.min(vec.len - self.offset);
+        let chunk = unsafe { slice::from_raw_parts_mut(vec.decrypt_ptr().add(self.offset), len) };
+        self.offset += len;
+        Some(chunk)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for EncryptedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedVec");
+        f.debug_struct("EncryptedVec")
+            .field("encrypted_value", &format!("{:#x}", self.buf.ciphertext()))
+            .field("pointed_value", &self.deref())
+            .finish()
+    }
+}
+
+/// A string whose buffer address is encrypted, the protected counterpart of `String`.
+#[derive(Default)]
+pub struct EncryptedString {
+    bytes: EncryptedVec<u8>,
+}
+
+impl EncryptedString {
+    /// Create an empty string.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `s`.
+    pub fn push_str(&mut self, s: &str) {
+        self.bytes
+            .with_vec(|vec| vec.extend_from_slice(s.as_bytes()));
+    }
+
+    /// Append `s`, or an error if growing fails.
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), AllocError> {
+        self.bytes.try_reserve(s.len())?;
+        self.push_str(s);
+        Ok(())
+    }
+
+    /// Take the contents back out as a plain `String`.
+    pub fn into_string(self) -> String {
+        unsafe { String::from_utf8_unchecked(self.bytes.into_vec()) }
+    }
+}
+
+impl From<String> for EncryptedString {
+    fn from(value: String) -> Self {
+        Self {
+            bytes: value.into_bytes().into(),
+        }
+    }
+}
+
+impl From<&str> for EncryptedString {
+    fn from(value: &str) -> Self {
+        String::from(value).into()
+    }
+}
+
+impl From<EncryptedString> for String {
+    fn from(value: EncryptedString) -> Self {
+        value.into_string()
+    }
+}
+
+impl Deref for EncryptedString {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::str::from_utf8_unchecked(&self.bytes) }
+    }
+}
+
+impl DerefMut for EncryptedString {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { std::str::from_utf8_unchecked_mut(&mut self.bytes) }
+    }
+}
+
+impl fmt::Debug for EncryptedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedString");
+        f.debug_struct("EncryptedString")
+            .field(
+                "encrypted_value",
+                &format!("{:#x}", self.bytes.buf.ciphertext()),
+            )
+            .field("pointed_value", &self.deref())
+            .finish()
+    }
+}
+
+/// A NUL-terminated string whose buffer address is encrypted, the protected counterpart of `CString`.
+///
+/// The raw pointer is only handed out inside [`EncryptedCString::with_c_str`],
+/// for passing secrets to C libraries without leaving the address around.
+pub struct EncryptedCString {
+    // always ends in the NUL terminator.
+    bytes: EncryptedVec<u8>,
+}
+
+impl EncryptedCString {
+    /// Create a string from `bytes`, failing if they contain an interior NUL, like [`CString::new`].
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Result<Self, NulError> {
+        CString::new(bytes).map(Self::from)
+    }
+
+    /// Run `f` with a pointer to the NUL-terminated string, valid only for the call.
+    pub fn with_c_str<R>(&self, f: impl FnOnce(*const c_char) -> R) -> R {
+        f(self.bytes.decrypt_ptr() as *const c_char)
+    }
+
+    /// Take the contents back out as a plain `CString`.
+    pub fn into_c_string(self) -> CString {
+        unsafe { CString::from_vec_with_nul_unchecked(self.bytes.into_vec()) }
+    }
+
+    fn as_c_str(&self) -> &CStr {
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.bytes) }
+    }
+}
+
+impl From<CString> for EncryptedCString {
+    fn from(value: CString) -> Self {
+        Self {
+            bytes: value.into_bytes_with_nul().into(),
+        }
+    }
+}
+
+impl From<&CStr> for EncryptedCString {
+    fn from(value: &CStr) -> Self {
+        CString::from(value).into()
+    }
+}
+
+impl From<EncryptedCString> for CString {
+    fn from(value: EncryptedCString) -> Self {
+        value.into_c_string()
+    }
+}
+
+impl fmt::Debug for EncryptedCString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "EncryptedCString");
+        f.debug_struct("EncryptedCString")
+            .field(
+                "encrypted_value",
+                &format!("{:#x}", self.bytes.buf.ciphertext()),
+            )
+            .field("pointed_value", &self.as_c_str())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn vec_round_trip() {
+        let mut inventory: EncryptedVec<u32> = vec![1, 2, 3].into();
+        inventory.push(4);
+        inventory[0] = 10;
+
+        assert_eq!(&*inventory, &[10, 2, 3, 4]);
+        assert_eq!(inventory.pop(), Some(4));
+        assert_eq!(inventory.into_vec(), vec![10, 2, 3]);
+    }
+
+    #[test]
+    fn vec_fallible_allocation() {
+        let mut inventory = EncryptedVec::try_with_capacity(4).unwrap();
+        inventory.try_push(1u32).unwrap();
+
+        assert_eq!(&*inventory, &[1]);
+        assert_eq!(inventory.try_reserve(usize::MAX), Err(AllocError));
+        assert!(EncryptedVec::<u64>::try_with_capacity(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn vec_chunks() {
+        let mut inventory: EncryptedVec<u32> = (1..=5).collect::<Vec<_>>().into();
+        for chunk in inventory.chunks_mut(2) {
+            chunk[0] *= 10;
+        }
+
+        let chunks: Vec<&[u32]> = inventory.chunks(2).collect();
+        assert_eq!(chunks, [&[10, 2][..], &[30, 4], &[50]]);
+        assert_eq!(inventory.iter().sum::<u32>(), 96);
+    }
+
+    #[test]
+    fn string_round_trip() {
+        let mut name: EncryptedString = String::from("player").into();
+        name.push_str("_one");
+
+        assert_eq!(&*name, "player_one");
+        assert_eq!(String::from(name), "player_one");
+    }
+
+    #[test]
+    fn c_string_round_trip() {
+        let license = EncryptedCString::new("ABCD-1234").unwrap();
+
+        let len = license.with_c_str(|ptr| unsafe { CStr::from_ptr(ptr) }.to_bytes().len());
+        assert_eq!(len, 9);
+        assert!(EncryptedCString::new("a\0b").is_err());
+        assert_eq!(license.into_c_string(), CString::new("ABCD-1234").unwrap());
+    }
+}
@@ -0,0 +1,178 @@
+use std::cell::Cell;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
+
+use crate::tamper::{self, TamperEvent, TamperKind};
+
+/// What the fault handler does after reporting a fault in a protected deref.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FaultResponse {
+    /// Let the fault continue to the next handler, usually crashing as before.
+    #[default]
+    Crash,
+    /// Map a zeroed scratch page at the faulting address and retry the access.
+    ///
+    /// The protected value reads as garbage from then on, but the process
+    /// keeps running under the configured [`TamperPolicy`](crate::TamperPolicy).
+    MapScratch,
+}
+
+/// Decrypted ranges remembered per thread, to recognize faults through them.
+const RECENT: usize = 8;
+
+thread_local! {
+    static DECRYPTED: Cell<[(u64, u64, &'static str); RECENT]> =
+        const { Cell::new([(0, 0, ""); RECENT]) };
+    static NEXT: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Whether the handler is installed, so derefs skip recording otherwise.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+static RESPONSE: AtomicU8 = AtomicU8::new(FaultResponse::Crash as u8);
+
+/// The handle `AddVectoredExceptionHandler` returned, or zero.
+static HANDLE: Mutex<usize> = Mutex::new(0);
+
+const EXCEPTION_ACCESS_VIOLATION: u32 = 0xC000_0005;
+const EXCEPTION_CONTINUE_EXECUTION: i32 = -1;
+const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+const MEM_COMMIT: u32 = 0x1000;
+const MEM_RESERVE: u32 = 0x2000;
+const PAGE_READWRITE: u32 = 0x04;
+
+#[repr(C)]
+struct ExceptionRecord {
+    code: u32,
+    flags: u32,
+    record: *mut ExceptionRecord,
+    address: *mut c_void,
+    parameters: u32,
+    information: [usize; 15],
+}
+
+#[repr(C)]
+struct ExceptionPointers {
+    record: *mut ExceptionRecord,
+    context: *mut c_void,
+}
+
+type Handler = unsafe extern "system" fn(*mut ExceptionPointers) -> i32;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn AddVectoredExceptionHandler(first: u32, handler: Handler) -> *mut c_void;
+    fn RemoveVectoredExceptionHandler(handle: *mut c_void) -> u32;
+    fn VirtualAlloc(address: *mut c_void, size: usize, kind: u32, protect: u32) -> *mut c_void;
+}
+
+/// Install a vectored exception handler for faults through protected pointers.
+///
+/// An access violation at an address this thread recently got from an
+/// [`EncryptedPtr`](crate::EncryptedPtr) deref means its ciphertext decrypted
+/// to garbage, usually because it was tampered with. Such faults are reported
+/// to the tamper callback as [`TamperKind::FaultingDecrypt`], then handled as
+/// `response` says; other faults pass through untouched. Under
+/// [`TamperPolicy::Panic`](crate::TamperPolicy::Panic) the panic can't unwind
+/// out of the handler, so the process aborts.
+///
+/// Returns whether the handler wasn't installed yet; otherwise only the
+/// response is updated.
+pub fn install_fault_handler(response: FaultResponse) -> bool {
+    RESPONSE.store(response as u8, Ordering::Relaxed);
+
+    let mut handle = HANDLE.lock().unwrap_or_else(|e| e.into_inner());
+    if *handle != 0 {
+        return false;
+    }
+
+    *handle = unsafe { AddVectoredExceptionHandler(1, handler) } as usize;
+    INSTALLED.store(*handle != 0, Ordering::Release);
+    *handle != 0
+}
+
+/// Remove the handler installed by [`install_fault_handler`], if any.
+pub fn remove_fault_handler() {
+    let mut handle = HANDLE.lock().unwrap_or_else(|e| e.into_inner());
+    if *handle != 0 {
+        INSTALLED.store(false, Ordering::Release);
+        unsafe { RemoveVectoredExceptionHandler(*handle as *mut c_void) };
+        *handle = 0;
+    }
+}
+
+/// Remember that this thread decrypted `size` bytes at `addr`, while the handler is installed.
+#[inline(always)]
+pub(crate) fn record(addr: u64, size: usize, type_name: &'static str) {
+    if INSTALLED.load(Ordering::Relaxed) && size != 0 {
+        remember(addr, size, type_name);
+    }
+}
+
+#[cold]
+fn remember(addr: u64, size: usize, type_name: &'static str) {
+    let next = NEXT.with(|next| next.replace((next.get() + 1) % RECENT));
+    DECRYPTED.with(|decrypted| {
+        let mut ranges = decrypted.get();
+        ranges[next] = (addr, addr.wrapping_add(size as u64), type_name);
+        decrypted.set(ranges);
+    });
+}
+
+/// The type name of a recently decrypted range containing `addr`.
+fn decrypted_at(addr: u64) -> Option<&'static str> {
+    DECRYPTED
+        .try_with(|decrypted| {
+            decrypted
+                .get()
+                .into_iter()
+                .find(|&(start, end, _)| (start..end).contains(&addr))
+                .map(|(_, _, type_name)| type_name)
+        })
+        .ok()
+        .flatten()
+}
+
+unsafe extern "system" fn handler(info: *mut ExceptionPointers) -> i32 {
+    let record = &*(*info).record;
+    if record.code != EXCEPTION_ACCESS_VIOLATION || record.parameters < 2 {
+        return EXCEPTION_CONTINUE_SEARCH;
+    }
+
+    // the second parameter is the inaccessible address.
+    let addr = record.information[1] as u64;
+    let Some(type_name) = decrypted_at(addr) else {
+        return EXCEPTION_CONTINUE_SEARCH;
+    };
+
+    tamper::report(TamperEvent::new(TamperKind::FaultingDecrypt, type_name));
+
+    if RESPONSE.load(Ordering::Relaxed) == FaultResponse::MapScratch as u8 {
+        let page = VirtualAlloc(
+            addr as *mut c_void,
+            1,
+            MEM_COMMIT | MEM_RESERVE,
+            PAGE_READWRITE,
+        );
+        if !page.is_null() {
+            return EXCEPTION_CONTINUE_EXECUTION;
+        }
+    }
+
+    EXCEPTION_CONTINUE_SEARCH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn recent_decrypts_are_recognized() {
+        remember(0x1000, 16, "u128");
+
+        assert_eq!(decrypted_at(0x1008), Some("u128"));
+        assert_eq!(decrypted_at(0x1010), None);
+    }
+}
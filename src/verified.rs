@@ -0,0 +1,209 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::mem::{self, ManuallyDrop};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::diag::{diag, type_name};
+use crate::tamper::{self, Poisoned, TamperEvent, TamperKind};
+
+/// Vtable words every trait object has: drop_in_place, size and align.
+const VTABLE_HEADER_WORDS: usize = 3;
+
+/// A boxed trait object whose vtable is verified on every access.
+///
+/// A hash of the vtable pointer and its contents is recorded at construction
+/// under a random key. If either is later swapped or patched (the usual way of
+/// hooking virtual calls) the access is reported to the tamper callback and
+/// fails instead of calling into the hook, as does every access after it.
+pub struct VerifiedDyn<T: ?Sized> {
+    inner: ManuallyDrop<Box<T>>,
+    hasher: RandomState,
+    vtable_words: usize,
+    expected: u64,
+    poisoned: AtomicBool,
+}
+
+impl<T: ?Sized> VerifiedDyn<T> {
+    /// Wrap a boxed trait object, hashing its vtable header.
+    ///
+    /// # Safety
+    /// `T` must be a trait object (`dyn Trait`). Other unsized types such as
+    /// `[u8]` or `str` are also two words wide, but their metadata is a length,
+    /// which would be read as a vtable pointer.
+    pub unsafe fn new(inner: Box<T>) -> Self {
+        unsafe { Self::with_vtable_words(inner, VTABLE_HEADER_WORDS) }
+    }
+
+    /// Wrap a boxed trait object, hashing the first `words` words of its vtable.
+    ///
+    /// Use this to also cover method slots: a trait with `n` methods (including
+    /// supertrait methods) has `3 + n` words.
+    ///
+    /// # Safety
+    /// `T` must be a trait object, as for [`VerifiedDyn::new`], and its vtable
+    /// must be at least `words` words long.
+    pub unsafe fn with_vtable_words(inner: Box<T>, words: usize) -> Self {
+        assert!(
+            size_of::<*const T>() == 2 * size_of::<usize>(),
+            "{}",
+            diag!("VerifiedDyn requires a trait object")
+        );
+
+        let mut verified = Self {
+            inner: ManuallyDrop::new(inner),
+            hasher: RandomState::new(),
+            vtable_words: words,
+            expected: 0,
+            poisoned: AtomicBool::new(false),
+        };
+        verified.expected = verified.vtable_hash();
+        verified
+    }
+
+    /// The vtable pointer of the boxed object.
+    #[inline(always)]
+    fn vtable(&self) -> *const usize {
+        let raw: *const T = &**self.inner;
+        let [_data, vtable]: [usize; 2] = unsafe { mem::transmute_copy(&raw) };
+        vtable as *const usize
+    }
+
+    /// Hash the vtable pointer together with the words it points to.
+    fn vtable_hash(&self) -> u64 {
+        let vtable = self.vtable();
+        let words = unsafe { std::slice::from_raw_parts(vtable, self.vtable_words) };
+        self.hasher.hash_one((vtable as usize, words))
+    }
+
+    /// Whether the vtable still matches the one recorded at construction.
+    pub fn verify(&self) -> bool {
+        self.vtable_hash() == self.expected
+    }
+
+    /// Whether a failed check has poisoned this pointer.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    /// Verify, poisoning and reporting on a mismatch.
+    #[inline(always)]
+    fn checked(&self) -> Result<(), Poisoned> {
+        if self.is_poisoned() {
+            return Err(Poisoned);
+        }
+
+        if !self.verify() {
+            self.poisoned.store(true, Ordering::Relaxed);
+            tamper::report(TamperEvent::new(
+                TamperKind::VtableMismatch,
+                type_name::<T>(),
+            ));
+            return Err(Poisoned);
+        }
+
+        Ok(())
+    }
+
+    /// Verify the vtable, then borrow the object, or [`Poisoned`] once a check has failed.
+    pub fn try_get(&self) -> Result<&T, Poisoned> {
+        self.checked()?;
+        Ok(&self.inner)
+    }
+
+    /// Mutable counterpart of [`VerifiedDyn::try_get`].
+    pub fn try_get_mut(&mut self) -> Result<&mut T, Poisoned> {
+        self.checked()?;
+        Ok(&mut self.inner)
+    }
+}
+
+impl<T: ?Sized> Deref for VerifiedDyn<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        self.try_get().expect(diag!("vtable mismatch"))
+    }
+}
+
+impl<T: ?Sized> DerefMut for VerifiedDyn<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut T {
+        self.try_get_mut().expect(diag!("vtable mismatch"))
+    }
+}
+
+impl<T: ?Sized> Drop for VerifiedDyn<T> {
+    fn drop(&mut self) {
+        // never run a destructor out of a hooked vtable, leak instead.
+        if !self.is_poisoned() && self.verify() {
+            unsafe { ManuallyDrop::drop(&mut self.inner) };
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for VerifiedDyn<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "VerifiedDyn");
+        let mut debug = f.debug_struct("VerifiedDyn");
+        debug.field("intact", &self.verify());
+        debug.field("poisoned", &self.is_poisoned());
+
+        // formatting goes through the vtable too, so skip it once hooked.
+        if !self.is_poisoned() && self.verify() {
+            debug.field("value", &&**self.inner);
+        }
+
+        debug.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    trait Entity {
+        fn health(&self) -> u32;
+    }
+
+    struct Player;
+    impl Entity for Player {
+        fn health(&self) -> u32 {
+            100
+        }
+    }
+
+    struct Hook;
+    impl Entity for Hook {
+        fn health(&self) -> u32 {
+            u32::MAX
+        }
+    }
+
+    #[test]
+    fn verified_dyn_calls_through() {
+        let entity: VerifiedDyn<dyn Entity> =
+            unsafe { VerifiedDyn::with_vtable_words(Box::new(Player), 4) };
+
+        assert!(entity.verify());
+        assert_eq!(entity.health(), 100);
+    }
+
+    #[test]
+    fn verified_dyn_detects_vtable_swap() {
+        let mut entity: VerifiedDyn<dyn Entity> = unsafe { VerifiedDyn::new(Box::new(Player)) };
+
+        // swap the vtable pointer for one of a hook type, as a cheat would.
+        let hook: &dyn Entity = &Hook;
+        let [_, hook_vtable]: [usize; 2] = unsafe { mem::transmute(hook) };
+        let fat = &mut *entity.inner as *mut Box<dyn Entity> as *mut [usize; 2];
+        unsafe { (*fat)[1] = hook_vtable };
+
+        assert!(!entity.verify());
+        assert!(entity.try_get().is_err());
+        assert!(entity.is_poisoned());
+    }
+}
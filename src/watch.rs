@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::diag::type_name;
+use crate::pod::{self, NoUninit};
+use crate::tamper::{TamperEvent, TamperKind};
+use crate::{aes, EncryptedPtr};
+
+static WATCHES: Mutex<Option<Watches>> = Mutex::new(None);
+
+struct Watches {
+    /// Mask applied to every stored address, as in the registry.
+    mask: u64,
+    next_id: u64,
+    entries: HashMap<u64, Watch>,
+}
+
+/// The digest a watched value had after its last legitimate write.
+struct Watch {
+    masked_addr: u64,
+    size: usize,
+    key: u64,
+    digest: u64,
+    type_name: &'static str,
+    /// Set once the heartbeat reported a change, until the next legitimate write.
+    reported: bool,
+    /// Set while a [`WatchGuard`] is out, when the value may legitimately differ.
+    writing: bool,
+}
+
+impl Watch {
+    /// Whether the value's current bytes no longer match the recorded digest.
+    ///
+    /// Callers hold the table lock, so no [`WatchGuard`] can hand out
+    /// `&mut` access to the value meanwhile.
+    fn changed(&self, mask: u64) -> bool {
+        if self.writing {
+            return false;
+        }
+
+        let addr = (self.masked_addr ^ mask) as *const u8;
+        let bytes = unsafe { std::slice::from_raw_parts(addr, self.size) };
+        digest(bytes, self.key) != self.digest
+    }
+}
+
+fn lock() -> MutexGuard<'static, Option<Watches>> {
+    WATCHES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A keyed digest of `bytes`.
+fn digest(bytes: &[u8], key: u64) -> u64 {
+    bytes.chunks(8).fold(bytes.len() as u64, |state, chunk| {
+        let mut word = [0; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        aes::mac(key ^ state, u64::from_ne_bytes(word))
+    })
+}
+
+/// Check every watched value, returning how many there are and an event for
+/// each that changed since it was last checked or written.
+pub(crate) fn check_watches() -> (usize, Vec<TamperEvent>) {
+    let mut guard = lock();
+    let Some(watches) = guard.as_mut() else {
+        return (0, Vec::new());
+    };
+
+    let mask = watches.mask;
+    let changed = watches
+        .entries
+        .values_mut()
+        .filter(|watch| !watch.reported && watch.changed(mask))
+        .map(|watch| {
+            watch.reported = true;
+            TamperEvent::new(TamperKind::ExternalWrite, watch.type_name)
+                .with_ptr((watch.masked_addr ^ mask) as *const u8)
+        })
+        .collect();
+    (watches.entries.len(), changed)
+}
+
+/// Protect `value` in a [`WatchedPtr`], recording its digest.
+pub fn watch<T: NoUninit>(value: T) -> WatchedPtr<T> {
+    WatchedPtr::new(value)
+}
+
+/// A protected value that remembers a keyed digest of itself after every legitimate write.
+///
+/// Writes go through [`WatchedPtr::write`], whose guard records the new
+/// digest when dropped. [`WatchedPtr::changed`] then answers "has this
+/// changed since my last write?", catching external writes that land
+/// between our own accesses, however long ago the value was last read.
+pub struct WatchedPtr<T: NoUninit> {
+    pub(crate) ptr: EncryptedPtr<T>,
+    id: u64,
+}
+
+impl<T: NoUninit> WatchedPtr<T> {
+    /// Protect `value` and record its digest.
+    pub fn new(value: T) -> Self {
+        let ptr = EncryptedPtr::from(value);
+        let addr = ptr.decrypt_ptr() as u64;
+
+        let mut guard = lock();
+        let watches = guard.get_or_insert_with(|| Watches {
+            mask: rand::random(),
+            next_id: 0,
+            entries: HashMap::new(),
+        });
+        let id = watches.next_id;
+        watches.next_id += 1;
+
+        let key = rand::random();
+        watches.entries.insert(
+            id,
+            Watch {
+                masked_addr: addr ^ watches.mask,
+                size: size_of::<T>(),
+                key,
+                digest: digest(pod::bytes_of(ptr.access()), key),
+                type_name: type_name::<T>(),
+                reported: false,
+                writing: false,
+            },
+        );
+        drop(guard);
+
+        Self { ptr, id }
+    }
+
+    /// Read the value.
+    pub fn access(&self) -> &T {
+        self.ptr.access()
+    }
+
+    /// Whether the value changed since its last write through [`WatchedPtr::write`].
+    pub fn changed(&self) -> bool {
+        let guard = lock();
+        let watches = guard.as_ref().unwrap();
+        watches.entries[&self.id].changed(watches.mask)
+    }
+
+    /// Write access; the digest is refreshed when the guard drops.
+    pub fn write(&mut self) -> WatchGuard<'_, T> {
+        self.update(|watch| watch.writing = true);
+        WatchGuard { watched: self }
+    }
+
+    /// Replace the value, refreshing the digest.
+    pub fn set(&mut self, value: T) {
+        *self.write() = value;
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Watch)) {
+        let mut guard = lock();
+        f(guard.as_mut().unwrap().entries.get_mut(&self.id).unwrap());
+    }
+}
+
+impl<T: NoUninit> Deref for WatchedPtr<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        self.access()
+    }
+}
+
+impl<T: NoUninit> Drop for WatchedPtr<T> {
+    fn drop(&mut self) {
+        if let Some(watches) = lock().as_mut() {
+            watches.entries.remove(&self.id);
+        }
+    }
+}
+
+impl<T: NoUninit + fmt::Debug> fmt::Debug for WatchedPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::diag::opaque_debug!(f, "WatchedPtr");
+        f.debug_struct("WatchedPtr")
+            .field("ptr", &self.ptr)
+            .field("changed", &self.changed())
+            .finish()
+    }
+}
+
+/// Write access to a [`WatchedPtr`], recording the new digest on drop.
+pub struct WatchGuard<'a, T: NoUninit> {
+    watched: &'a mut WatchedPtr<T>,
+}
+
+impl<T: NoUninit> Deref for WatchGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.watched.ptr.access()
+    }
+}
+
+impl<T: NoUninit> DerefMut for WatchGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.watched.ptr.access_mut()
+    }
+}
+
+impl<T: NoUninit> Drop for WatchGuard<'_, T> {
+    fn drop(&mut self) {
+        let bytes = pod::bytes_of(self.watched.ptr.access());
+        let mut guard = lock();
+        let watch = guard
+            .as_mut()
+            .unwrap()
+            .entries
+            .get_mut(&self.watched.id)
+            .unwrap();
+
+        watch.digest = digest(bytes, watch.key);
+        watch.writing = false;
+        watch.reported = false;
+    }
+}
+
+impl<T: NoUninit + fmt::Debug> fmt::Debug for WatchGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn external_writes_are_noticed() {
+        let mut health = watch(100u32);
+        *health.write() -= 10;
+        assert!(!health.changed());
+        assert_eq!(*health, 90);
+
+        // a trainer writing straight to the heap.
+        unsafe { *health.ptr.decrypt_ptr() = 9999 };
+        assert!(health.changed());
+
+        health.set(50);
+        assert!(!health.changed());
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::atomic::{compiler_fence, Ordering};
+
+use rand::Rng;
+
+use crate::config;
+
+/// Whether freed pointees should be wiped, see [`Config::wipe_on_drop`](crate::Config::wipe_on_drop).
+#[inline(always)]
+pub(crate) fn enabled() -> bool {
+    config::read(|config| config.wipe_on_drop)
+}
+
+/// Overwrite `len` bytes at `ptr` with random bytes the compiler can't elide.
+///
+/// # Safety
+/// `ptr` must be valid for `len` byte writes.
+pub(crate) unsafe fn wipe(ptr: *mut u8, len: usize) {
+    let mut rng = rand::rng();
+    for i in 0..len {
+        ptr.add(i).write_volatile(rng.random());
+    }
+
+    // keep the writes ahead of the deallocation that follows.
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wipe_overwrites_bytes() {
+        let mut secret = [0u8; 64];
+        unsafe { wipe(secret.as_mut_ptr(), secret.len()) };
+
+        // 64 zero bytes in a row is a 2^-512 chance.
+        assert!(secret.iter().any(|&b| b != 0));
+    }
+}
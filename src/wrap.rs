@@ -0,0 +1,110 @@
+use std::marker::PhantomData;
+
+use crate::diag::type_name;
+use crate::observer::{self, Access};
+use crate::sealed::Sealed;
+use crate::{aes, registry, DropPolicy, Encrypt, EncryptedPtr, Speck};
+
+/// An encrypted pointer's address re-wrapped under a caller-supplied key, for handing to another component.
+///
+/// Produced by [`EncryptedPtr::export`] and turned back into a pointer by
+/// [`EncryptedPtr::import`]. Like the `ffi` feature's `PgHandle` it is plain
+/// data that can cross the FFI boundary by value, but it is only meaningful
+/// to whoever holds the wrapping key: neither the address nor the pointer's
+/// own key appear in it, and a modified handle fails to import.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrappedHandle {
+    /// Fresh per export, so wrapping the same pointer twice looks unrelated.
+    pub nonce: u64,
+    /// The address, encrypted under the wrapping key and nonce.
+    pub wrapped: u64,
+    /// MAC over the wrapped address and the pointee's size.
+    pub tag: u64,
+}
+
+/// The MAC of a wrapped address, bound to the pointee's size.
+fn tag(wrapping_key: u64, nonce: u64, wrapped: u64, size: usize) -> u64 {
+    aes::mac(
+        wrapping_key ^ nonce,
+        wrapped ^ (size as u64).rotate_left(32),
+    )
+}
+
+impl<T, P: DropPolicy> EncryptedPtr<T, P> {
+    /// Re-wrap the address under `wrapping_key`, without ever exposing it or this pointer's key.
+    ///
+    /// The handle doesn't own the pointee: ownership moves only if this
+    /// pointer is given up (e.g. with [`forget`](EncryptedPtr::forget)) and
+    /// the receiver imports the handle.
+    pub fn export(&self, wrapping_key: u64) -> WrappedHandle {
+        observer::observe(Access::Deref, type_name::<T>());
+
+        let nonce = rand::random();
+        let wrapped = Speck::new().encrypt(self.sealed.get(), wrapping_key ^ nonce);
+        WrappedHandle {
+            nonce,
+            wrapped,
+            tag: tag(wrapping_key, nonce, wrapped, size_of::<T>()),
+        }
+    }
+
+    /// Unwrap a handle from [`EncryptedPtr::export`] into a pointer encrypted under a fresh key.
+    ///
+    /// Returns `None` if the handle was modified, wrapped under another key,
+    /// or exported from a pointer to a type of another size.
+    ///
+    /// # Safety
+    /// The handle must come from a pointer to a `T` that is still alive, and
+    /// the exporter must have given up ownership of it, or the pointee is
+    /// dropped twice.
+    pub unsafe fn import(wrapped: WrappedHandle, wrapping_key: u64) -> Option<Self> {
+        let expected = tag(wrapping_key, wrapped.nonce, wrapped.wrapped, size_of::<T>());
+        if wrapped.tag != expected {
+            return None;
+        }
+
+        let ptr = Speck::new().decrypt(wrapped.wrapped, wrapping_key ^ wrapped.nonce) as *mut T;
+        registry::register(ptr);
+        Some(Self {
+            sealed: Sealed::new(ptr as u64),
+            _marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const WRAPPING_KEY: u64 = 0x3A7F_0C11_D00D_F00D;
+
+    #[test]
+    fn export_then_import_moves_ownership() {
+        let health: EncryptedPtr<u32> = EncryptedPtr::from(100);
+        let handle = health.export(WRAPPING_KEY);
+        assert_ne!(handle, health.export(WRAPPING_KEY));
+        health.forget();
+
+        let mut received = unsafe { EncryptedPtr::<u32>::import(handle, WRAPPING_KEY) }.unwrap();
+        *received -= 25;
+        assert_eq!(*received, 75);
+    }
+
+    #[test]
+    fn import_rejects_tampered_or_mistyped_handles() {
+        let health: EncryptedPtr<u32> = EncryptedPtr::from(100);
+        let handle = health.export(WRAPPING_KEY);
+
+        let forged = WrappedHandle {
+            wrapped: handle.wrapped ^ 0x40,
+            ..handle
+        };
+        unsafe {
+            assert!(EncryptedPtr::<u32>::import(forged, WRAPPING_KEY).is_none());
+            assert!(EncryptedPtr::<u32>::import(handle, WRAPPING_KEY ^ 1).is_none());
+            assert!(EncryptedPtr::<u64>::import(handle, WRAPPING_KEY).is_none());
+        }
+    }
+}
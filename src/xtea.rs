@@ -0,0 +1,131 @@
+use crate::diag::diag;
+use crate::Encrypt;
+
+const DELTA: u32 = 0x9E3779B9;
+
+/// XTEA, a small and well-studied block cipher needing only shifts, adds and XORs.
+///
+/// Suited to hardware without AES instructions. The round count is
+/// configurable; each cycle is two Feistel rounds.
+#[derive(Debug, Clone, Copy)]
+pub struct Xtea {
+    cycles: u32,
+}
+
+impl Default for Xtea {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Xtea {
+    /// Standard number of cycles.
+    pub const DEFAULT_CYCLES: u32 = 32;
+
+    /// XTEA with the standard 32 cycles.
+    pub const fn new() -> Self {
+        Self::with_cycles(Self::DEFAULT_CYCLES)
+    }
+
+    /// XTEA with a custom number of cycles, at least 1.
+    pub const fn with_cycles(cycles: u32) -> Self {
+        assert!(cycles >= 1, "{}", diag!("XTEA needs at least 1 cycle"));
+        Self { cycles }
+    }
+
+    /// Number of cycles used.
+    pub const fn cycles(&self) -> u32 {
+        self.cycles
+    }
+
+    /// Stretch a 64-bit key into the 128-bit XTEA key.
+    #[inline(always)]
+    fn expand_key(key: u64) -> [u32; 4] {
+        let hi = key.rotate_left(29).wrapping_mul(0x94D049BB133111EB);
+        [(key >> 32) as u32, key as u32, (hi >> 32) as u32, hi as u32]
+    }
+
+    /// Encrypt one block, `v0` in the upper and `v1` in the lower word.
+    #[inline(always)]
+    fn encrypt_block(&self, block: u64, key: [u32; 4]) -> u64 {
+        let (mut v0, mut v1) = ((block >> 32) as u32, block as u32);
+        let mut sum = 0u32;
+
+        for _ in 0..self.cycles {
+            v0 = v0.wrapping_add(
+                ((v1 << 4) ^ (v1 >> 5)).wrapping_add(v1)
+                    ^ sum.wrapping_add(key[(sum & 3) as usize]),
+            );
+            sum = sum.wrapping_add(DELTA);
+            v1 = v1.wrapping_add(
+                ((v0 << 4) ^ (v0 >> 5)).wrapping_add(v0)
+                    ^ sum.wrapping_add(key[((sum >> 11) & 3) as usize]),
+            );
+        }
+
+        (v0 as u64) << 32 | v1 as u64
+    }
+
+    /// Decrypt one block, `v0` in the upper and `v1` in the lower word.
+    #[inline(always)]
+    fn decrypt_block(&self, block: u64, key: [u32; 4]) -> u64 {
+        let (mut v0, mut v1) = ((block >> 32) as u32, block as u32);
+        let mut sum = DELTA.wrapping_mul(self.cycles);
+
+        for _ in 0..self.cycles {
+            v1 = v1.wrapping_sub(
+                ((v0 << 4) ^ (v0 >> 5)).wrapping_add(v0)
+                    ^ sum.wrapping_add(key[((sum >> 11) & 3) as usize]),
+            );
+            sum = sum.wrapping_sub(DELTA);
+            v0 = v0.wrapping_sub(
+                ((v1 << 4) ^ (v1 >> 5)).wrapping_add(v1)
+                    ^ sum.wrapping_add(key[(sum & 3) as usize]),
+            );
+        }
+
+        (v0 as u64) << 32 | v1 as u64
+    }
+}
+
+impl Encrypt for Xtea {
+    #[inline(always)]
+    fn encrypt(&self, data: u64, key: u64) -> u64 {
+        self.encrypt_block(data, Self::expand_key(key))
+    }
+
+    #[inline(always)]
+    fn decrypt(&self, data: u64, key: u64) -> u64 {
+        self.decrypt_block(data, Self::expand_key(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn xtea_known_answer() {
+        // big-endian words of key 000102030405060708090a0b0c0d0e0f.
+        let key = [0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f];
+        let plaintext = 0x41424344_45464748;
+        let ciphertext = 0x497df3d0_72612cb5;
+
+        assert_eq!(Xtea::new().encrypt_block(plaintext, key), ciphertext);
+        assert_eq!(Xtea::new().decrypt_block(ciphertext, key), plaintext);
+    }
+
+    #[test]
+    fn decrypt_value_xtea() {
+        let key: u64 = 0x1234567890ABCDEF;
+        let data: u64 = 0xFEDCBA0987654321;
+
+        for xtea in [Xtea::new(), Xtea::with_cycles(8)] {
+            let encrypted = xtea.encrypt(data, key);
+            let decrypted = xtea.decrypt(encrypted, key);
+
+            assert_eq!(data, decrypted);
+        }
+    }
+}